@@ -162,6 +162,16 @@ impl<'a> Secp256k1Backend for Secp256k1<'a> {
         Ok(key.into())
     }
 
+    fn add_pubkeys(&self, a: &Self::Pubkey, b: &Self::Pubkey) -> Result<Self::Pubkey, Bip32Error> {
+        Ok(a.0.combine(&b.0)?.into())
+    }
+
+    fn mul_pubkey(&self, k: &Self::Pubkey, scalar: [u8; 32]) -> Result<Self::Pubkey, Bip32Error> {
+        let mut key = k.0;
+        key.mul_assign(&self.0, &scalar)?;
+        Ok(key.into())
+    }
+
     fn tweak_privkey(
         &self,
         k: &Self::Privkey,
@@ -186,6 +196,30 @@ impl<'a> Secp256k1Backend for Secp256k1<'a> {
         self.0.sign_recoverable(&m, &k.0)
     }
 
+    fn sign_digest_with_nonce(
+        &self,
+        k: &Self::Privkey,
+        digest: Hash256Digest,
+        nonce: &Self::Privkey,
+    ) -> Result<Self::Signature, Bip32Error> {
+        let point = secp256k1::PublicKey::from_secret_key(&self.0, &nonce.0).serialize();
+        let r = &point[1..];
+
+        // s = k^-1 * (z + r * d). `r` and `z` are tweaks, so they must be below the curve order.
+        // Each fails to be with negligible probability
+        let mut s = k.0;
+        s.mul_assign(r)?;
+        s.add_assign(digest.as_slice())?;
+        s.mul_assign(&invert(&nonce.0)?[..])?;
+
+        let mut compact = [0u8; 64];
+        compact[..32].copy_from_slice(r);
+        compact[32..].copy_from_slice(&s[..]);
+        let mut sig = secp256k1::Signature::from_compact(&compact)?;
+        sig.normalize_s();
+        Ok(sig)
+    }
+
     fn verify_digest(
         &self,
         k: &Self::Pubkey,
@@ -216,6 +250,29 @@ impl<'a> Secp256k1Backend for Secp256k1<'a> {
     }
 }
 
+/// Invert a scalar modulo the curve order, as `k^(n - 2)`. This uses only libsecp's
+/// constant-time scalar multiplication, and the exponent is public, so the sequence of
+/// operations does not depend on `k`.
+fn invert(k: &secp256k1::SecretKey) -> Result<secp256k1::SecretKey, Bip32Error> {
+    // n ends in 0x41, so subtracting 2 doesn't borrow
+    let mut exponent = crate::CURVE_ORDER;
+    exponent[31] -= 2;
+
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    let mut result = secp256k1::SecretKey::from_slice(&one)?;
+    for byte in exponent.iter() {
+        for bit in (0..8).rev() {
+            let square = result;
+            result.mul_assign(&square[..])?;
+            if (byte >> bit) & 1 == 1 {
+                result.mul_assign(&k[..])?;
+            }
+        }
+    }
+    Ok(result)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -116,6 +116,12 @@ pub trait Secp256k1Backend: Clone + std::fmt::Debug + PartialEq {
     /// Add a scalar tweak to a public key. Returns a new key
     fn tweak_pubkey(&self, k: &Self::Pubkey, tweak: [u8; 32]) -> Result<Self::Pubkey, Self::Error>;
 
+    /// Add two public keys. Returns a new key
+    fn add_pubkeys(&self, a: &Self::Pubkey, b: &Self::Pubkey) -> Result<Self::Pubkey, Self::Error>;
+
+    /// Multiply a public key by a scalar. Returns a new key
+    fn mul_pubkey(&self, k: &Self::Pubkey, scalar: [u8; 32]) -> Result<Self::Pubkey, Self::Error>;
+
     /// Add a scalar tweak to a private key. Returns a new key
     fn tweak_privkey(
         &self,
@@ -133,6 +139,17 @@ pub trait Secp256k1Backend: Clone + std::fmt::Debug + PartialEq {
         digest: Hash256Digest,
     ) -> Self::RecoverableSignature;
 
+    /// Sign a digest using `nonce` as the ECDSA nonce, rather than an RFC6979 nonce. The
+    /// signature is normalized to low-s.
+    ///
+    /// Both `k` and `nonce` are secret. Implementations must use constant-time scalar arithmetic.
+    fn sign_digest_with_nonce(
+        &self,
+        k: &Self::Privkey,
+        digest: Hash256Digest,
+        nonce: &Self::Privkey,
+    ) -> Result<Self::Signature, Self::Error>;
+
     /// Sign a message
     fn sign<D>(&self, k: &Self::Privkey, message: &[u8]) -> Self::Signature
     where
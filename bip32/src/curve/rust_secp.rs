@@ -205,6 +205,16 @@ impl<'a> Secp256k1Backend for Secp256k1<'a> {
         Ok(key.into())
     }
 
+    fn add_pubkeys(&self, a: &Self::Pubkey, b: &Self::Pubkey) -> Result<Self::Pubkey, Bip32Error> {
+        Ok(secp256k1::PublicKey::combine(&[a.0.clone(), b.0.clone()])?.into())
+    }
+
+    fn mul_pubkey(&self, k: &Self::Pubkey, scalar: [u8; 32]) -> Result<Self::Pubkey, Bip32Error> {
+        let mut key = k.0.clone();
+        key.tweak_mul_assign_with_context(&secp256k1::SecretKey::parse(&scalar)?, self.0)?;
+        Ok(key.into())
+    }
+
     fn tweak_privkey(
         &self,
         k: &Self::Privkey,
@@ -232,6 +242,19 @@ impl<'a> Secp256k1Backend for Secp256k1<'a> {
             sig: sig.0,
         }
     }
+    fn sign_digest_with_nonce(
+        &self,
+        k: &Self::Privkey,
+        digest: Hash256Digest,
+        nonce: &Self::Privkey,
+    ) -> Result<Self::Signature, Bip32Error> {
+        let m = secp256k1::Message::parse(digest.to_internal().as_ref());
+        let (r, s, _) = self
+            .1
+            .sign_raw(&k.0.clone().into(), &m.0, &nonce.0.clone().into())?;
+        Ok(secp256k1::Signature { r, s })
+    }
+
     fn verify_digest(
         &self,
         k: &Self::Pubkey,
@@ -1,6 +1,7 @@
-use serde::{de::Visitor, ser::SerializeStruct};
-
-use crate::enc::XKeyEncoder;
+use crate::{
+    enc::{DynamicEncoder, Network, XKeyEncoder},
+    Bip32Error, XKey,
+};
 
 /// The default encoder, selected by feature flag
 #[cfg(feature = "mainnet")]
@@ -10,226 +11,472 @@ pub type Encoder = crate::enc::MainnetEncoder;
 #[cfg(feature = "testnet")]
 pub type Encoder = crate::enc::TestnetEncoder;
 
+/// The encoder for the other network. Parsing falls back to it when `Encoder` fails
+#[cfg(feature = "mainnet")]
+type Fallback = crate::enc::TestnetEncoder;
+
+/// The encoder for the other network. Parsing falls back to it when `Encoder` fails
+#[cfg(feature = "testnet")]
+type Fallback = crate::enc::MainnetEncoder;
+
+/// The network recorded on keys parsed by `Fallback`
+#[cfg(feature = "mainnet")]
+fn fallback_network() -> Network {
+    Network::of::<crate::enc::Test>("testnet")
+}
+
+/// The network recorded on keys parsed by `Fallback`
+#[cfg(feature = "testnet")]
+fn fallback_network() -> Network {
+    Network::of::<crate::enc::Main>("bitcoin")
+}
+
+/// The encoder for the network `key` was parsed from
+fn encoder_for<K: XKey>(key: &K) -> DynamicEncoder {
+    key.network()
+        .map_or_else(Encoder::dynamic, |network| network.encoder())
+}
+
+/// Parse with `Encoder`, then with `Fallback`. Keys parsed by `Fallback` record its network
+fn parse_either<K, E>(
+    parse: impl Fn() -> Result<K, Bip32Error>,
+    parse_fallback: impl Fn() -> Result<K, E>,
+    network: impl Fn(&mut K) -> &mut Option<Network>,
+) -> Result<K, Bip32Error> {
+    parse().or_else(|e| {
+        let mut key = parse_fallback().map_err(|_| e)?;
+        *network(&mut key) = Some(fallback_network());
+        Ok(key)
+    })
+}
+
+/// Either network's prefixes are accepted. Keys from the other network remember it, and are
+/// displayed with its prefixes
 impl std::str::FromStr for crate::XPriv {
     type Err = crate::Bip32Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Encoder::xpriv_from_base58(s, Some(&crate::curve::BACKEND))
+        let backend = Some(&crate::curve::BACKEND);
+        parse_either(
+            || Encoder::xpriv_from_base58(s, backend),
+            || Fallback::xpriv_from_base58(s, backend),
+            |key| &mut key.info.network,
+        )
     }
 }
 
+/// Either network's prefixes are accepted. Keys from the other network remember it, and are
+/// displayed with its prefixes
 impl std::str::FromStr for crate::XPub {
     type Err = crate::Bip32Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Encoder::xpub_from_base58(s, Some(&crate::curve::BACKEND))
+        let backend = Some(&crate::curve::BACKEND);
+        parse_either(
+            || Encoder::xpub_from_base58(s, backend),
+            || Fallback::xpub_from_base58(s, backend),
+            |key| &mut key.info.network,
+        )
     }
 }
 
-impl serde::Serialize for crate::XPub {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let encoded =
-            Encoder::xpub_to_base58(self).map_err(|e| serde::ser::Error::custom(e.to_string()))?;
-        serializer.serialize_str(&encoded)
+/// Displays the b58check string for the key's network. This is the default `Encoder`'s
+/// network, unless the key was parsed from the other network
+impl std::fmt::Display for crate::XPriv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let encoded = encoder_for(self)
+            .xpriv_to_base58(self)
+            .map_err(|_| std::fmt::Error)?;
+        f.write_str(&encoded)
     }
 }
 
-impl<'de> serde::Deserialize<'de> for crate::XPub {
-    fn deserialize<D>(deserializer: D) -> Result<crate::XPub, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s: &str = serde::Deserialize::deserialize(deserializer)?;
-        Encoder::xpub_from_base58(s, Some(crate::Secp256k1::static_ref()))
-            .map_err(|e| serde::de::Error::custom(e.to_string()))
+/// Displays the b58check string for the key's network. This is the default `Encoder`'s
+/// network, unless the key was parsed from the other network
+impl std::fmt::Display for crate::XPub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let encoded = encoder_for(self)
+            .xpub_to_base58(self)
+            .map_err(|_| std::fmt::Error)?;
+        f.write_str(&encoded)
     }
 }
 
-impl serde::Serialize for crate::XPriv {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let encoded =
-            Encoder::xpriv_to_base58(self).map_err(|e| serde::ser::Error::custom(e.to_string()))?;
-        serializer.serialize_str(&encoded)
+impl std::convert::TryFrom<[u8; 78]> for crate::XPriv {
+    type Error = crate::Bip32Error;
+
+    /// Read the payload with either network's version bytes, as in `FromStr`
+    fn try_from(data: [u8; 78]) -> Result<Self, Self::Error> {
+        let backend = Some(&crate::curve::BACKEND);
+        parse_either(
+            || Encoder::xpriv_from_bytes(&data, backend),
+            || Fallback::xpriv_from_bytes(&data, backend),
+            |key| &mut key.info.network,
+        )
     }
 }
 
-impl<'de> serde::Deserialize<'de> for crate::XPriv {
-    fn deserialize<D>(deserializer: D) -> Result<crate::XPriv, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s: &str = serde::Deserialize::deserialize(deserializer)?;
-        Encoder::xpriv_from_base58(s, Some(crate::Secp256k1::static_ref()))
-            .map_err(|e| serde::de::Error::custom(e.to_string()))
+impl std::convert::TryFrom<[u8; 78]> for crate::XPub {
+    type Error = crate::Bip32Error;
+
+    /// Read the payload with either network's version bytes, as in `FromStr`
+    fn try_from(data: [u8; 78]) -> Result<Self, Self::Error> {
+        let backend = Some(&crate::curve::BACKEND);
+        parse_either(
+            || Encoder::xpub_from_bytes(&data, backend),
+            || Fallback::xpub_from_bytes(&data, backend),
+            |key| &mut key.info.network,
+        )
     }
 }
 
-impl serde::Serialize for crate::DerivedXPriv {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("DerivedXPriv", 2)?;
-        state.serialize_field("derivation", &self.derivation)?;
-        state.serialize_field("xpriv", &self.xpriv)?;
-        state.end()
+/// Write the payload with the version bytes of the key's network, as in `Display`
+impl From<&crate::XPriv> for [u8; 78] {
+    fn from(key: &crate::XPriv) -> Self {
+        let mut data = [0u8; 78];
+        // Bitcoin networks have version bytes for every hint, and slices don't error on
+        // exact-length writes
+        encoder_for(key)
+            .write_xpriv(&mut &mut data[..], key)
+            .expect("infallible");
+        data
     }
 }
 
-struct DerivedXPrivVisitor;
-
-impl<'de> Visitor<'de> for DerivedXPrivVisitor {
-    type Value = crate::DerivedXPriv;
+/// Write the payload with the version bytes of the key's network, as in `Display`
+impl From<&crate::XPub> for [u8; 78] {
+    fn from(key: &crate::XPub) -> Self {
+        let mut data = [0u8; 78];
+        // Bitcoin networks have version bytes for every hint, and slices don't error on
+        // exact-length writes
+        encoder_for(key)
+            .write_xpub(&mut &mut data[..], key)
+            .expect("infallible");
+        data
+    }
+}
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("struct DerivedXPriv")
+impl From<crate::XPriv> for [u8; 78] {
+    fn from(key: crate::XPriv) -> Self {
+        (&key).into()
     }
+}
 
-    fn visit_seq<V>(self, mut seq: V) -> Result<crate::DerivedXPriv, V::Error>
-    where
-        V: serde::de::SeqAccess<'de>,
-    {
-        let xpriv = seq
-            .next_element()?
-            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
-        let derivation = seq
-            .next_element()?
-            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
-        Ok(crate::DerivedXPriv { xpriv, derivation })
+impl From<crate::XPub> for [u8; 78] {
+    fn from(key: crate::XPub) -> Self {
+        (&key).into()
     }
+}
 
-    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
-    where
-        V: serde::de::MapAccess<'de>,
-    {
-        let mut xpriv = None;
-        let mut derivation = None;
+/// Serde support for extended keys. Requires the `serde` feature
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{de::Visitor, ser::SerializeStruct};
+    use std::convert::TryFrom;
 
-        #[derive(serde::Deserialize)]
-        #[serde(field_identifier, rename_all = "lowercase")]
-        enum Field {
-            XPriv,
-            Derivation,
-        }
+    use super::encoder_for;
 
-        while let Some(key) = map.next_key()? {
-            match key {
-                Field::XPriv => {
-                    if xpriv.is_some() {
-                        return Err(serde::de::Error::duplicate_field("xpriv"));
-                    }
-                    xpriv = Some(map.next_value()?);
-                }
-                Field::Derivation => {
-                    if derivation.is_some() {
-                        return Err(serde::de::Error::duplicate_field("derivation"));
-                    }
-                    derivation = Some(map.next_value()?);
-                }
+    /// Check that a binary payload is exactly 78 bytes long
+    fn payload<E: serde::de::Error>(buf: &[u8]) -> Result<[u8; 78], E> {
+        <[u8; 78]>::try_from(buf)
+            .map_err(|_| E::invalid_length(buf.len(), &"a 78-byte extended key"))
+    }
+
+    /// Human-readable formats use the b58check string. Binary formats use the 78-byte payload
+    impl serde::Serialize for crate::XPub {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                let mut buf = vec![];
+                encoder_for(self)
+                    .write_xpub(&mut buf, self)
+                    .map_err(|e| serde::ser::Error::custom(e.to_string()))?;
+                serializer.serialize_bytes(&buf)
             }
         }
+    }
 
-        let xpriv = xpriv.ok_or_else(|| serde::de::Error::missing_field("xpriv"))?;
-        let derivation = derivation.ok_or_else(|| serde::de::Error::missing_field("derivation"))?;
+    /// Either network's prefixes are accepted, as in `FromStr` and `TryFrom<[u8; 78]>`
+    impl<'de> serde::Deserialize<'de> for crate::XPub {
+        fn deserialize<D>(deserializer: D) -> Result<crate::XPub, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let result = if deserializer.is_human_readable() {
+                let s: String = serde::Deserialize::deserialize(deserializer)?;
+                s.parse()
+            } else {
+                let buf: Vec<u8> = serde::Deserialize::deserialize(deserializer)?;
+                crate::XPub::try_from(payload::<D::Error>(&buf)?)
+            };
+            result.map_err(|e| serde::de::Error::custom(e.to_string()))
+        }
+    }
 
-        Ok(crate::DerivedXPriv { xpriv, derivation })
+    /// Human-readable formats use the b58check string. Binary formats use the 78-byte payload
+    impl serde::Serialize for crate::XPriv {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                let mut buf = vec![];
+                encoder_for(self)
+                    .write_xpriv(&mut buf, self)
+                    .map_err(|e| serde::ser::Error::custom(e.to_string()))?;
+                serializer.serialize_bytes(&buf)
+            }
+        }
     }
-}
 
-impl<'de> serde::Deserialize<'de> for crate::DerivedXPriv {
-    fn deserialize<D>(deserializer: D) -> Result<crate::DerivedXPriv, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        const FIELDS: &[&str] = &["xpriv", "derivation"];
-        deserializer.deserialize_struct("Duration", FIELDS, DerivedXPrivVisitor)
+    /// Either network's prefixes are accepted, as in `FromStr` and `TryFrom<[u8; 78]>`
+    impl<'de> serde::Deserialize<'de> for crate::XPriv {
+        fn deserialize<D>(deserializer: D) -> Result<crate::XPriv, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let result = if deserializer.is_human_readable() {
+                let s: String = serde::Deserialize::deserialize(deserializer)?;
+                s.parse()
+            } else {
+                let buf: Vec<u8> = serde::Deserialize::deserialize(deserializer)?;
+                crate::XPriv::try_from(payload::<D::Error>(&buf)?)
+            };
+            result.map_err(|e| serde::de::Error::custom(e.to_string()))
+        }
     }
-}
 
-impl serde::Serialize for crate::DerivedXPub {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("DerivedXPub", 2)?;
-        state.serialize_field("derivation", &self.derivation)?;
-        state.serialize_field("xpub", &self.xpub)?;
-        state.end()
+    impl serde::Serialize for crate::DerivedXPriv {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut state = serializer.serialize_struct("DerivedXPriv", 2)?;
+            state.serialize_field("derivation", &self.derivation)?;
+            state.serialize_field("xpriv", &self.xpriv)?;
+            state.end()
+        }
     }
-}
 
-struct DerivedXPubVisitor;
+    struct DerivedXPrivVisitor;
+
+    impl<'de> Visitor<'de> for DerivedXPrivVisitor {
+        type Value = crate::DerivedXPriv;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("struct DerivedXPriv")
+        }
+
+        fn visit_seq<V>(self, mut seq: V) -> Result<crate::DerivedXPriv, V::Error>
+        where
+            V: serde::de::SeqAccess<'de>,
+        {
+            let xpriv = seq
+                .next_element()?
+                .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+            let derivation = seq
+                .next_element()?
+                .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            Ok(crate::DerivedXPriv { xpriv, derivation })
+        }
+
+        fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+        where
+            V: serde::de::MapAccess<'de>,
+        {
+            let mut xpriv = None;
+            let mut derivation = None;
+
+            #[derive(serde::Deserialize)]
+            #[serde(field_identifier, rename_all = "lowercase")]
+            enum Field {
+                XPriv,
+                Derivation,
+            }
 
-impl<'de> Visitor<'de> for DerivedXPubVisitor {
-    type Value = crate::DerivedXPub;
+            while let Some(key) = map.next_key()? {
+                match key {
+                    Field::XPriv => {
+                        if xpriv.is_some() {
+                            return Err(serde::de::Error::duplicate_field("xpriv"));
+                        }
+                        xpriv = Some(map.next_value()?);
+                    }
+                    Field::Derivation => {
+                        if derivation.is_some() {
+                            return Err(serde::de::Error::duplicate_field("derivation"));
+                        }
+                        derivation = Some(map.next_value()?);
+                    }
+                }
+            }
+
+            let xpriv = xpriv.ok_or_else(|| serde::de::Error::missing_field("xpriv"))?;
+            let derivation =
+                derivation.ok_or_else(|| serde::de::Error::missing_field("derivation"))?;
+
+            Ok(crate::DerivedXPriv { xpriv, derivation })
+        }
+    }
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("struct DerivedXPub")
+    impl<'de> serde::Deserialize<'de> for crate::DerivedXPriv {
+        fn deserialize<D>(deserializer: D) -> Result<crate::DerivedXPriv, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            const FIELDS: &[&str] = &["xpriv", "derivation"];
+            deserializer.deserialize_struct("Duration", FIELDS, DerivedXPrivVisitor)
+        }
     }
 
-    fn visit_seq<V>(self, mut seq: V) -> Result<crate::DerivedXPub, V::Error>
-    where
-        V: serde::de::SeqAccess<'de>,
-    {
-        let xpub = seq
-            .next_element()?
-            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
-        let derivation = seq
-            .next_element()?
-            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
-        Ok(crate::DerivedXPub { xpub, derivation })
+    impl serde::Serialize for crate::DerivedXPub {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut state = serializer.serialize_struct("DerivedXPub", 2)?;
+            state.serialize_field("derivation", &self.derivation)?;
+            state.serialize_field("xpub", &self.xpub)?;
+            state.end()
+        }
     }
 
-    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
-    where
-        V: serde::de::MapAccess<'de>,
-    {
-        let mut xpub = None;
-        let mut derivation = None;
+    struct DerivedXPubVisitor;
+
+    impl<'de> Visitor<'de> for DerivedXPubVisitor {
+        type Value = crate::DerivedXPub;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("struct DerivedXPub")
+        }
 
-        #[derive(serde::Deserialize)]
-        #[serde(field_identifier, rename_all = "lowercase")]
-        enum Field {
-            XPub,
-            Derivation,
+        fn visit_seq<V>(self, mut seq: V) -> Result<crate::DerivedXPub, V::Error>
+        where
+            V: serde::de::SeqAccess<'de>,
+        {
+            let xpub = seq
+                .next_element()?
+                .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+            let derivation = seq
+                .next_element()?
+                .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            Ok(crate::DerivedXPub { xpub, derivation })
         }
 
-        while let Some(key) = map.next_key()? {
-            match key {
-                Field::XPub => {
-                    if xpub.is_some() {
-                        return Err(serde::de::Error::duplicate_field("xpub"));
+        fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+        where
+            V: serde::de::MapAccess<'de>,
+        {
+            let mut xpub = None;
+            let mut derivation = None;
+
+            #[derive(serde::Deserialize)]
+            #[serde(field_identifier, rename_all = "lowercase")]
+            enum Field {
+                XPub,
+                Derivation,
+            }
+
+            while let Some(key) = map.next_key()? {
+                match key {
+                    Field::XPub => {
+                        if xpub.is_some() {
+                            return Err(serde::de::Error::duplicate_field("xpub"));
+                        }
+                        xpub = Some(map.next_value()?);
                     }
-                    xpub = Some(map.next_value()?);
-                }
-                Field::Derivation => {
-                    if derivation.is_some() {
-                        return Err(serde::de::Error::duplicate_field("derivation"));
+                    Field::Derivation => {
+                        if derivation.is_some() {
+                            return Err(serde::de::Error::duplicate_field("derivation"));
+                        }
+                        derivation = Some(map.next_value()?);
                     }
-                    derivation = Some(map.next_value()?);
                 }
             }
-        }
 
-        let xpub = xpub.ok_or_else(|| serde::de::Error::missing_field("xpub"))?;
-        let derivation = derivation.ok_or_else(|| serde::de::Error::missing_field("derivation"))?;
+            let xpub = xpub.ok_or_else(|| serde::de::Error::missing_field("xpub"))?;
+            let derivation =
+                derivation.ok_or_else(|| serde::de::Error::missing_field("derivation"))?;
 
-        Ok(crate::DerivedXPub { xpub, derivation })
+            Ok(crate::DerivedXPub { xpub, derivation })
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for crate::DerivedXPub {
+        fn deserialize<D>(deserializer: D) -> Result<crate::DerivedXPub, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            const FIELDS: &[&str] = &["xpub", "derivation"];
+            deserializer.deserialize_struct("Duration", FIELDS, DerivedXPubVisitor)
+        }
     }
 }
 
-impl<'de> serde::Deserialize<'de> for crate::DerivedXPub {
-    fn deserialize<D>(deserializer: D) -> Result<crate::DerivedXPub, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        const FIELDS: &[&str] = &["xpub", "derivation"];
-        deserializer.deserialize_struct("Duration", FIELDS, DerivedXPubVisitor)
+#[cfg(all(test, feature = "mainnet"))]
+mod test {
+    use crate::{enc::Test, primitives::Hint, DerivePublicChild, XKey, XPriv, XPub};
+
+    #[test]
+    fn it_parses_and_displays_either_network() {
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpriv: XPriv = xpriv_str.parse().unwrap();
+        assert_eq!(xpriv.to_string(), xpriv_str);
+
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let xpub: XPub = xpub_str.parse().unwrap();
+        assert_eq!(format!("{}", xpub), xpub_str);
+
+        // keys from the other network keep its prefixes, as do their children
+        let vpub_str = "vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT";
+        let vpub: XPub = vpub_str.parse().unwrap();
+        assert_eq!(vpub.hint(), Hint::SegWit);
+        assert!(vpub.network().unwrap().is::<Test>());
+        assert_eq!(vpub.to_string(), vpub_str);
+        let child = vpub.derive_public_child(0).unwrap();
+        assert!(child.to_string().starts_with("vpub"));
+        assert_eq!(xpub.network(), None);
+
+        assert!("not a key".parse::<XPub>().is_err());
+    }
+
+    #[test]
+    fn it_converts_fixed_size_payloads() {
+        use std::convert::TryFrom;
+
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpriv: XPriv = xpriv_str.parse().unwrap();
+        let data: [u8; 78] = xpriv.clone().into();
+        assert_eq!(XPriv::try_from(data).unwrap(), xpriv);
+
+        let vpub_str = "vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT";
+        let mut data = [0u8; 78];
+        data.copy_from_slice(&crate::enc::decode_b58_check(vpub_str).unwrap());
+        let vpub = XPub::try_from(data).unwrap();
+        assert_eq!(vpub.hint(), Hint::SegWit);
+        assert_eq!(vpub, vpub_str.parse().unwrap());
+        assert_eq!(<[u8; 78]>::from(&vpub), data);
+
+        data[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        assert!(XPub::try_from(data).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn it_deserializes_either_network() {
+        use serde::de::{value::StrDeserializer, IntoDeserializer};
+
+        let tpub_str = "vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT";
+        let deserializer: StrDeserializer<serde::de::value::Error> = tpub_str.into_deserializer();
+        let xpub: XPub = serde::Deserialize::deserialize(deserializer).unwrap();
+        assert_eq!(xpub, tpub_str.parse().unwrap());
+        assert_eq!(xpub.hint(), Hint::SegWit);
+        assert_eq!(xpub.to_string(), tpub_str);
     }
 }
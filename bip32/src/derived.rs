@@ -110,6 +110,17 @@ impl DerivedXPriv {
     pub fn root_from_seed(data: &[u8], hint: Option<Hint>) -> Result<DerivedXPriv, Bip32Error> {
         Self::custom_root_from_seed(data, hint, crate::curve::Secp256k1::static_ref())
     }
+
+    /// Generate a master node from some seed data, using `domain` as the hmac key instead of
+    /// `"Bitcoin seed"`. E.g. SLIP-0010 uses a different key per curve. This is `master_node`
+    /// with the seed first.
+    pub fn root_from_seed_with_domain(
+        data: &[u8],
+        domain: &[u8],
+        hint: Option<Hint>,
+    ) -> Result<DerivedXPriv, Bip32Error> {
+        Self::master_node(domain, data, hint)
+    }
 }
 
 impl<'a, T: Secp256k1Backend> GenericDerivedXPriv<'a, T> {
@@ -152,6 +163,12 @@ impl<'a, T: Secp256k1Backend> GenericDerivedXPriv<'a, T> {
         })
     }
 
+    /// Derive the child at `index`, extending this key's derivation so the child carries its
+    /// full origin.
+    pub fn derive_child(&self, index: u32) -> Result<GenericDerivedXPriv<'a, T>, Bip32Error> {
+        self.derive_private_child(index)
+    }
+
     /// Check if this XPriv is the private ancestor of some other derived key
     pub fn is_private_ancestor_of<D: DerivedKey + HasPubkey<'a, T>>(
         &self,
@@ -201,6 +218,12 @@ inherit_backend!(GenericDerivedXPub.xpub);
 inherit_has_xkeyinfo!(GenericDerivedXPub.xpub);
 
 impl<'a, T: Secp256k1Backend> GenericDerivedXPub<'a, T> {
+    /// Derive the child at `index`, extending this key's derivation so the child carries its
+    /// full origin.
+    pub fn derive_child(&self, index: u32) -> Result<GenericDerivedXPub<'a, T>, Bip32Error> {
+        self.derive_public_child(index)
+    }
+
     /// Check if this XPriv is the private ancestor of some other derived key
     pub fn is_public_ancestor_of<D: DerivedKey + HasPubkey<'a, T>>(
         &self,
@@ -634,6 +657,29 @@ mod test {
         }
     }
 
+    #[test]
+    fn it_threads_derivations_through_children() {
+        let backend = Secp256k1::static_ref();
+        let m = GenericDerivedXPriv::custom_root_from_seed(&[0u8; 32][..], None, backend).unwrap();
+        let root = m.derivation().root;
+        assert_eq!(root, m.xpriv.derive_fingerprint().unwrap());
+
+        let account = m.derive_child(84 + BIP32_HARDEN).unwrap();
+        let address = account
+            .to_derived_xpub()
+            .unwrap()
+            .derive_child(0)
+            .unwrap()
+            .derive_child(7)
+            .unwrap();
+        assert_eq!(address.derivation().root, root);
+        assert_eq!(
+            address.derivation().path,
+            "m/84'/0/7".parse::<DerivationPath>().unwrap()
+        );
+        assert!(m.is_private_ancestor_of(&address).unwrap());
+    }
+
     #[test]
     fn it_checks_ancestry() {
         let backend = Secp256k1::static_ref();
@@ -0,0 +1,136 @@
+//! Ed25519 key derivation, as defined in SLIP-0010.
+//!
+//! SLIP-0010 derives Ed25519 keys from a BIP32-style tree, using a curve-specific HMAC key for
+//! the master node. Ed25519 has no public derivation, so every index must be hardened, and child
+//! keys are the left half of the HMAC output with no tweak.
+//!
+//! This module derives private keys only. Computing the Ed25519 public key, and signing, are
+//! left to an Ed25519 library.
+//!
+//! `Ed25519XPriv` is a standalone type, not a `Secp256k1Backend`. That trait is built around
+//! secp256k1 points, ECDSA signatures, and public derivation, none of which Ed25519 SLIP-0010
+//! supports. Private derivation needs only HMAC-SHA512, so this module has no curve
+//! dependency. As a result, Ed25519 keys do not work with `XPriv`, `DerivedXPriv`, or the
+//! `enc` module, and they have no xpub form.
+
+use crate::{primitives::ChainCode, xkeys::hmac_and_split, Bip32Error, BIP32_HARDEN};
+
+/// The SLIP-0010 HMAC key for Ed25519 master nodes
+pub const ED25519_SEED: &[u8; 12] = b"ed25519 seed";
+
+/// A SLIP-0010 Ed25519 extended private key
+#[derive(Clone, PartialEq, Eq)]
+pub struct Ed25519XPriv {
+    depth: u8,
+    index: u32,
+    chain_code: ChainCode,
+    key: [u8; 32],
+}
+
+impl Ed25519XPriv {
+    /// Generate a master node from some seed data
+    pub fn root_from_seed(data: &[u8]) -> Result<Self, Bip32Error> {
+        if data.len() < 16 {
+            return Err(Bip32Error::SeedTooShort);
+        }
+        let (key, chain_code) = hmac_and_split(ED25519_SEED, data);
+        Ok(Self {
+            depth: 0,
+            index: 0,
+            chain_code,
+            key,
+        })
+    }
+
+    /// Derive the child at `index`. Errors with `Bip32Error::NonHardenedDerivation` if the index
+    /// is not hardened
+    pub fn derive_private_child(&self, index: u32) -> Result<Self, Bip32Error> {
+        if index < BIP32_HARDEN {
+            return Err(Bip32Error::NonHardenedDerivation(index));
+        }
+        let depth = self
+            .depth
+            .checked_add(1)
+            .ok_or(Bip32Error::MaxDepthExceeded)?;
+        let mut data = vec![0u8];
+        data.extend(&self.key);
+        data.extend(&index.to_be_bytes());
+        let (key, chain_code) = hmac_and_split(&self.chain_code.0, &data);
+        Ok(Self {
+            depth,
+            index,
+            chain_code,
+            key,
+        })
+    }
+
+    /// Derive the descendant at `path`. Every index must be hardened
+    pub fn derive_private_path(&self, path: &[u32]) -> Result<Self, Bip32Error> {
+        path.iter()
+            .try_fold(self.clone(), |key, index| key.derive_private_child(*index))
+    }
+
+    /// The depth of the key in its tree
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// The index of the key in its parent's children
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The chain code
+    pub fn chain_code(&self) -> ChainCode {
+        self.chain_code
+    }
+
+    /// The 32-byte Ed25519 private key
+    pub fn privkey_bytes(&self) -> [u8; 32] {
+        self.key
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_derives_slip10_test_vectors() {
+        // SLIP-0010 Ed25519 test vector 1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let root = Ed25519XPriv::root_from_seed(&seed).unwrap();
+        assert_eq!(
+            hex::encode(root.privkey_bytes()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(root.chain_code().0),
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+
+        let child = root
+            .derive_private_path(&[BIP32_HARDEN, BIP32_HARDEN + 1, BIP32_HARDEN + 2])
+            .unwrap();
+        assert_eq!(child.depth(), 3);
+        assert_eq!(child.index(), BIP32_HARDEN + 2);
+        assert_eq!(
+            hex::encode(child.privkey_bytes()),
+            "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9"
+        );
+        assert_eq!(
+            hex::encode(child.chain_code().0),
+            "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c"
+        );
+    }
+
+    #[test]
+    fn it_rejects_non_hardened_derivation() {
+        let root = Ed25519XPriv::root_from_seed(&[0u8; 16]).unwrap();
+        match root.derive_private_child(1) {
+            Err(Bip32Error::NonHardenedDerivation(1)) => {}
+            _ => assert!(false, "expected NonHardenedDerivation"),
+        }
+        assert!(Ed25519XPriv::root_from_seed(&[0u8; 15]).is_err());
+    }
+}
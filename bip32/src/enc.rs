@@ -1,6 +1,9 @@
 use std::marker::PhantomData;
 
-use coins_core::hashes::{Digest, Hash256};
+use coins_core::{
+    hashes::{Digest, Hash256},
+    ser::SerError,
+};
 
 use crate::{
     curve::model::{PointDeserialize, ScalarDeserialize, Secp256k1Backend},
@@ -14,6 +17,9 @@ use crate::{
 /// Decode a bytevector from a base58 check string
 pub fn decode_b58_check(s: &str) -> Result<Vec<u8>, Bip32Error> {
     let data: Vec<u8> = bs58::decode(s).into_vec()?;
+    if data.len() < 4 {
+        return Err(Bip32Error::BadB58Checksum);
+    }
     let idx = data.len() - 4;
     let payload = &data[..idx];
     let checksum = &data[idx..];
@@ -42,7 +48,130 @@ pub fn encode_b58_check(v: &[u8]) -> String {
     bs58::encode(data).into_string()
 }
 
-/// Contains network-specific serialization information
+/// Write all of `buf`, returning its length. Unlike `Write::write`, this never writes a prefix
+fn write_all<W: std::io::Write>(writer: &mut W, buf: &[u8]) -> Result<usize, Bip32Error> {
+    writer.write_all(buf)?;
+    Ok(buf.len())
+}
+
+fn write_key_details<K, W>(writer: &mut W, key: &K) -> Result<usize, Bip32Error>
+where
+    K: XKey,
+    W: std::io::Write,
+{
+    let mut written = write_all(writer, &[key.depth()])?;
+    written += write_all(writer, &key.parent().0)?;
+    written += write_all(writer, &key.index().to_be_bytes())?;
+    written += write_all(writer, &key.chain_code().0)?;
+    Ok(written)
+}
+
+/// BIP32 master keys have depth 0, and a zero parent fingerprint and index. Child keys have a
+/// nonzero parent fingerprint
+fn check_metadata<K: XKey>(key: &K) -> Result<(), Bip32Error> {
+    let zero_parent = key.parent().0 == [0; 4];
+    let consistent = if key.depth() == 0 {
+        zero_parent && key.index() == 0
+    } else {
+        !zero_parent
+    };
+    if consistent {
+        Ok(())
+    } else {
+        Err(Bip32Error::InvalidMasterKeyMetadata)
+    }
+}
+
+fn read_info<R>(reader: &mut R, hint: Hint) -> Result<XKeyInfo, Bip32Error>
+where
+    R: std::io::Read,
+{
+    let mut depth = [0u8; 1];
+    reader.read_exact(&mut depth)?;
+    let mut parent = [0u8; 4];
+    reader.read_exact(&mut parent)?;
+    let mut index = [0u8; 4];
+    reader.read_exact(&mut index)?;
+    let mut chain_code = [0u8; 32];
+    reader.read_exact(&mut chain_code)?;
+    Ok(XKeyInfo {
+        depth: depth[0],
+        parent: parent.into(),
+        index: u32::from_be_bytes(index),
+        chain_code: chain_code.into(),
+        hint,
+        network: None,
+    })
+}
+
+fn read_xpriv_details<'a, R, T>(
+    reader: &mut R,
+    hint: Hint,
+    backend: Option<&'a T>,
+) -> Result<GenericXPriv<'a, T>, Bip32Error>
+where
+    R: std::io::Read,
+    T: Secp256k1Backend,
+{
+    let info = read_info(reader, hint)?;
+
+    let mut buf = [0u8];
+    reader.read_exact(&mut buf)?;
+    if buf != [0] {
+        return Err(Bip32Error::BadPadding(buf[0]));
+    }
+
+    let mut buf = [0u8; 32];
+    reader.read_exact(&mut buf)?;
+    let key = T::Privkey::from_privkey_array(buf)?;
+
+    Ok(GenericXPriv {
+        info,
+        privkey: GenericPrivkey { key, backend },
+    })
+}
+
+fn read_xpub_details<'a, R, T>(
+    reader: &mut R,
+    hint: Hint,
+    backend: Option<&'a T>,
+) -> Result<GenericXPub<'a, T>, Bip32Error>
+where
+    R: std::io::Read,
+    T: Secp256k1Backend,
+{
+    let info = read_info(reader, hint)?;
+
+    let mut buf = [0u8; 33];
+    reader.read_exact(&mut buf)?;
+    if buf[0] == 0x04 {
+        return Err(Bip32Error::UncompressedKeyUnsupported);
+    }
+    let key = T::Pubkey::from_pubkey_array(buf)?;
+
+    Ok(GenericXPub {
+        info,
+        pubkey: GenericPubkey { key, backend },
+    })
+}
+
+/// Error if a payload has bytes left after reading a key
+fn check_consumed(rest: &[u8]) -> Result<(), Bip32Error> {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            SerError::ComponentError(format!("{} trailing bytes after extended key", rest.len()))
+                .into(),
+        )
+    }
+}
+
+/// Version bytes for hints a network does not support. Encoders error with `UnsupportedHint`
+/// when serializing a key with such a hint, and never parse these bytes.
+pub const NO_VERSION: u32 = 0;
+
+/// Contains network-specific serialization information. Any version may be `NO_VERSION`
 pub trait NetworkParams {
     /// The Bip32 privkey version bytes
     const PRIV_VERSION: u32;
@@ -56,6 +185,14 @@ pub trait NetworkParams {
     const BIP49_PUB_VERSION: u32;
     /// The Bip84 pubkey version bytes
     const BIP84_PUB_VERSION: u32;
+    /// The SLIP-0132 multisig P2WSH-in-P2SH privkey version bytes
+    const BIP49_MULTISIG_PRIV_VERSION: u32;
+    /// The SLIP-0132 multisig P2WSH privkey version bytes
+    const BIP84_MULTISIG_PRIV_VERSION: u32;
+    /// The SLIP-0132 multisig P2WSH-in-P2SH pubkey version bytes
+    const BIP49_MULTISIG_PUB_VERSION: u32;
+    /// The SLIP-0132 multisig P2WSH pubkey version bytes
+    const BIP84_MULTISIG_PUB_VERSION: u32;
 }
 
 /// Bip32/49/84 encoder
@@ -66,11 +203,7 @@ pub trait XKeyEncoder {
         K: XKey,
         W: std::io::Write,
     {
-        let mut written = writer.write(&[key.depth()])?;
-        written += writer.write(&key.parent().0)?;
-        written += writer.write(&key.index().to_be_bytes())?;
-        written += writer.write(&key.chain_code().0)?;
-        Ok(written)
+        write_key_details(writer, key)
     }
 
     /// Serialize the xpub to `std::io::Write`
@@ -138,36 +271,13 @@ pub trait XKeyEncoder {
         R: std::io::Read,
         T: Secp256k1Backend,
     {
-        let depth = Self::read_depth(reader)?;
-        let parent = Self::read_parent(reader)?;
-        let index = Self::read_index(reader)?;
-        let chain_code = Self::read_chain_code(reader)?;
-
-        let mut buf = [0u8];
-        reader.read_exact(&mut buf)?;
-        if buf != [0] {
-            return Err(Bip32Error::BadPadding(buf[0]));
-        }
-
-        let mut buf = [0u8; 32];
-        reader.read_exact(&mut buf)?;
-        let key = T::Privkey::from_privkey_array(buf)?;
-
-        Ok(GenericXPriv {
-            info: XKeyInfo {
-                depth,
-                parent,
-                index,
-                chain_code,
-                hint,
-            },
-            privkey: GenericPrivkey { key, backend },
-        })
+        read_xpriv_details(reader, hint, backend)
     }
 
     #[doc(hidden)]
     // Can be used for unhealthy but sometimes-desiable behavior. E.g. accepting an xpriv from any
-    // network.
+    // network. The hint is inferred from Bitcoin mainnet and testnet version bytes, and is
+    // `Legacy` for unknown version bytes.
     fn read_xpriv_without_network<'a, R, T>(
         reader: &mut R,
         backend: Option<&'a T>,
@@ -178,8 +288,9 @@ pub trait XKeyEncoder {
     {
         let mut buf = [0u8; 4];
         reader.read_exact(&mut buf)?;
+        let hint = known_hint(u32::from_be_bytes(buf), VersionBytes::xpriv_hint);
 
-        Self::read_xpriv_body(reader, Hint::Legacy, backend)
+        Self::read_xpriv_body(reader, hint, backend)
     }
 
     /// Attempt to instantiate an `XPriv` from a `std::io::Read`
@@ -219,30 +330,12 @@ pub trait XKeyEncoder {
         R: std::io::Read,
         T: Secp256k1Backend,
     {
-        let depth = Self::read_depth(reader)?;
-        let parent = Self::read_parent(reader)?;
-        let index = Self::read_index(reader)?;
-        let chain_code = Self::read_chain_code(reader)?;
-
-        let mut buf = [0u8; 33];
-        reader.read_exact(&mut buf)?;
-        let key = T::Pubkey::from_pubkey_array(buf)?;
-
-        Ok(GenericXPub {
-            info: XKeyInfo {
-                depth,
-                parent,
-                index,
-                chain_code,
-                hint,
-            },
-            pubkey: GenericPubkey { key, backend },
-        })
+        read_xpub_details(reader, hint, backend)
     }
 
     #[doc(hidden)]
     // Can be used for unhealthy but sometimes-desiable behavior. E.g. accepting an xpub from any
-    // network.
+    // network. The hint is inferred as in `read_xpriv_without_network`.
     fn read_xpub_without_network<'a, R, T>(
         reader: &mut R,
         backend: Option<&'a T>,
@@ -253,8 +346,9 @@ pub trait XKeyEncoder {
     {
         let mut buf = [0u8; 4];
         reader.read_exact(&mut buf)?;
+        let hint = known_hint(u32::from_be_bytes(buf), VersionBytes::xpub_hint);
 
-        Self::read_xpub_body(reader, Hint::Legacy, backend)
+        Self::read_xpub_body(reader, hint, backend)
     }
 
     /// Attempt to instantiate an `XPriv` from a `std::io::Read`
@@ -284,6 +378,12 @@ pub trait XKeyEncoder {
         R: std::io::Read,
         T: Secp256k1Backend;
 
+    /// Return true if the base58check string is an xpub serialized for this encoder's network,
+    /// with any of its bip32, bip49, bip84, or SLIP-0132 multisig pubkey version bytes. This is a fast guard for
+    /// user input. It checks only the length and version bytes, not the checksum or the key.
+    /// Use `xpub_from_base58` to fully validate a key.
+    fn is_network(s: &str) -> bool;
+
     /// Serialize an XPriv to base58
     fn xpriv_to_base58<'a, T>(k: &GenericXPriv<'a, T>) -> Result<String, Bip32Error>
     where
@@ -304,6 +404,54 @@ pub trait XKeyEncoder {
         Ok(encode_b58_check(&v))
     }
 
+    /// Serialize an XPriv to its 78-byte payload, without base58check encoding
+    fn xpriv_to_bytes<'a, T>(k: &GenericXPriv<'a, T>) -> Result<Vec<u8>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        Self::write_xpriv(&mut v, k)?;
+        Ok(v)
+    }
+
+    /// Serialize an XPub to its 78-byte payload, without base58check encoding
+    fn xpub_to_bytes<'a, T>(k: &GenericXPub<'a, T>) -> Result<Vec<u8>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        Self::write_xpub(&mut v, k)?;
+        Ok(v)
+    }
+
+    /// Attempt to read an XPriv from its 78-byte payload. Errors if `data` has trailing bytes
+    fn xpriv_from_bytes<'a, T>(
+        data: &[u8],
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut reader = data;
+        let key = Self::read_xpriv(&mut reader, backend)?;
+        check_consumed(reader)?;
+        Ok(key)
+    }
+
+    /// Attempt to read an XPub from its 78-byte payload. Errors if `data` has trailing bytes
+    fn xpub_from_bytes<'a, T>(
+        data: &[u8],
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut reader = data;
+        let key = Self::read_xpub(&mut reader, backend)?;
+        check_consumed(reader)?;
+        Ok(key)
+    }
+
     /// Attempt to read an XPriv from a b58check string.
     ///
     /// # Note
@@ -363,6 +511,75 @@ pub trait XKeyEncoder {
         let data = decode_b58_check(s)?;
         Self::read_xpub(&mut &data[..], backend)
     }
+
+    /// Read an XPriv as `read_xpriv`, then reject it with `InvalidMasterKeyMetadata` if its depth
+    /// is inconsistent with its parent fingerprint and index. The lenient readers accept these
+    /// keys for compatibility.
+    fn read_xpriv_strict<'a, R, T>(
+        reader: &mut R,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        R: std::io::Read,
+        T: Secp256k1Backend,
+    {
+        let key = Self::read_xpriv(reader, backend)?;
+        check_metadata(&key)?;
+        Ok(key)
+    }
+
+    /// Read an XPub as `read_xpub`, then check its metadata as `read_xpriv_strict` does
+    fn read_xpub_strict<'a, R, T>(
+        reader: &mut R,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        R: std::io::Read,
+        T: Secp256k1Backend,
+    {
+        let key = Self::read_xpub(reader, backend)?;
+        check_metadata(&key)?;
+        Ok(key)
+    }
+
+    /// Attempt to read an XPriv from a b58check string with `read_xpriv_strict`
+    fn xpriv_from_base58_strict<'a, T>(
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        Self::read_xpriv_strict(&mut &data[..], backend)
+    }
+
+    /// Attempt to read an XPub from a b58check string with `read_xpub_strict`
+    fn xpub_from_base58_strict<'a, T>(
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        Self::read_xpub_strict(&mut &data[..], backend)
+    }
+
+    /// Attempt to read an XPub with any of the network's SLIP-0132 prefixes. Returns the key,
+    /// its single-key hint, and whether the prefix was a multisig (`Ypub` or `Zpub`) prefix.
+    /// The key itself keeps its original hint, so it re-serializes with the same prefix
+    fn from_slip132<'a, T>(
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<(GenericXPub<'a, T>, Hint, bool), Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let xpub = Self::xpub_from_base58(s, backend)?;
+        let hint = xpub.hint();
+        Ok((xpub, hint.with_multisig(false), hint.is_multisig()))
+    }
 }
 
 params!(
@@ -373,7 +590,11 @@ params!(
         bip84: 0x04b2_430c,
         bip32_pub: 0x0488_B21E,
         bip49_pub: 0x049d_7cb2,
-        bip84_pub: 0x04b2_4746
+        bip84_pub: 0x04b2_4746,
+        bip49_multisig: 0x0295_b005,
+        bip84_multisig: 0x02aa_7a99,
+        bip49_multisig_pub: 0x0295_b43f,
+        bip84_multisig_pub: 0x02aa_7ed3
     }
 );
 
@@ -385,51 +606,244 @@ params!(
         bip84: 0x045f_18bc,
         bip32_pub: 0x0435_87CF,
         bip49_pub: 0x044a_5262,
-        bip84_pub: 0x045f_1cf6
+        bip84_pub: 0x045f_1cf6,
+        bip49_multisig: 0x0242_85b5,
+        bip84_multisig: 0x0257_5048,
+        bip49_multisig_pub: 0x0242_89ef,
+        bip84_multisig_pub: 0x0257_5483
     }
 );
 
-/// Parameterizable Bitcoin encoder
-#[derive(Debug, Clone)]
-pub struct BitcoinEncoder<P: NetworkParams>(PhantomData<fn(P) -> P>);
+params!(
+    /// Litecoin mainnet encoding param. Litecoin has no distinct BIP84 or multisig version
+    /// bytes, so those use the Bitcoin SLIP-0132 values, as Litecoin wallets do
+    Litecoin {
+        bip32: 0x019d_9cfe,
+        bip49: 0x01b2_6792,
+        bip84: 0x04b2_430c,
+        bip32_pub: 0x019d_a462,
+        bip49_pub: 0x01b2_6ef6,
+        bip84_pub: 0x04b2_4746,
+        bip49_multisig: 0x0295_b005,
+        bip84_multisig: 0x02aa_7a99,
+        bip49_multisig_pub: 0x0295_b43f,
+        bip84_multisig_pub: 0x02aa_7ed3
+    }
+);
+
+params!(
+    /// Litecoin testnet encoding param. Only the BIP32 version bytes differ from Bitcoin
+    /// testnet
+    LitecoinTest {
+        bip32: 0x0436_ef7d,
+        bip49: 0x044a_4e28,
+        bip84: 0x045f_18bc,
+        bip32_pub: 0x0436_f6e1,
+        bip49_pub: 0x044a_5262,
+        bip84_pub: 0x045f_1cf6,
+        bip49_multisig: 0x0242_85b5,
+        bip84_multisig: 0x0257_5048,
+        bip49_multisig_pub: 0x0242_89ef,
+        bip84_multisig_pub: 0x0257_5483
+    }
+);
+
+params!(
+    /// Dogecoin mainnet encoding param. Dogecoin has no BIP49, BIP84, or multisig version
+    /// bytes. Rather than emit keys indistinguishable from BIP32 keys, serializing a key with
+    /// any other hint errors with `UnsupportedHint`
+    Dogecoin {
+        bip32: 0x02fa_c398,
+        bip49: NO_VERSION,
+        bip84: NO_VERSION,
+        bip32_pub: 0x02fa_cafd,
+        bip49_pub: NO_VERSION,
+        bip84_pub: NO_VERSION,
+        bip49_multisig: NO_VERSION,
+        bip84_multisig: NO_VERSION,
+        bip49_multisig_pub: NO_VERSION,
+        bip84_multisig_pub: NO_VERSION
+    }
+);
+
+/// Version bytes for each hint, as runtime values. Use this with `DynamicEncoder` for networks
+/// that are not known at compile time. Any version may be `NO_VERSION`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionBytes {
+    /// The Bip32 privkey version bytes
+    pub priv32: u32,
+    /// The Bip49 privkey version bytes
+    pub priv49: u32,
+    /// The Bip84 privkey version bytes
+    pub priv84: u32,
+    /// The SLIP-0132 multisig P2WSH-in-P2SH privkey version bytes
+    pub priv49_multisig: u32,
+    /// The SLIP-0132 multisig P2WSH privkey version bytes
+    pub priv84_multisig: u32,
+    /// The Bip32 pubkey version bytes
+    pub pub32: u32,
+    /// The Bip49 pubkey version bytes
+    pub pub49: u32,
+    /// The Bip84 pubkey version bytes
+    pub pub84: u32,
+    /// The SLIP-0132 multisig P2WSH-in-P2SH pubkey version bytes
+    pub pub49_multisig: u32,
+    /// The SLIP-0132 multisig P2WSH pubkey version bytes
+    pub pub84_multisig: u32,
+}
+
+impl VersionBytes {
+    /// The version bytes of a compile-time `NetworkParams`
+    pub fn of<P: NetworkParams>() -> Self {
+        Self {
+            priv32: P::PRIV_VERSION,
+            priv49: P::BIP49_PRIV_VERSION,
+            priv84: P::BIP84_PRIV_VERSION,
+            priv49_multisig: P::BIP49_MULTISIG_PRIV_VERSION,
+            priv84_multisig: P::BIP84_MULTISIG_PRIV_VERSION,
+            pub32: P::PUB_VERSION,
+            pub49: P::BIP49_PUB_VERSION,
+            pub84: P::BIP84_PUB_VERSION,
+            pub49_multisig: P::BIP49_MULTISIG_PUB_VERSION,
+            pub84_multisig: P::BIP84_MULTISIG_PUB_VERSION,
+        }
+    }
+
+    fn privs(&self) -> [(u32, Hint); 5] {
+        [
+            (self.priv32, Hint::Legacy),
+            (self.priv49, Hint::Compatibility),
+            (self.priv84, Hint::SegWit),
+            (self.priv49_multisig, Hint::CompatibilityMultisig),
+            (self.priv84_multisig, Hint::SegWitMultisig),
+        ]
+    }
+
+    fn pubs(&self) -> [(u32, Hint); 5] {
+        [
+            (self.pub32, Hint::Legacy),
+            (self.pub49, Hint::Compatibility),
+            (self.pub84, Hint::SegWit),
+            (self.pub49_multisig, Hint::CompatibilityMultisig),
+            (self.pub84_multisig, Hint::SegWitMultisig),
+        ]
+    }
+
+    fn version_for(versions: [(u32, Hint); 5], hint: Hint) -> Result<u32, Bip32Error> {
+        versions
+            .iter()
+            .find(|(version, h)| *h == hint && *version != NO_VERSION)
+            .map(|(version, _)| *version)
+            .ok_or(Bip32Error::UnsupportedHint(hint))
+    }
+
+    fn hint_for(versions: [(u32, Hint); 5], version: u32) -> Option<Hint> {
+        if version == NO_VERSION {
+            return None;
+        }
+        versions
+            .iter()
+            .find(|(v, _)| *v == version)
+            .map(|(_, hint)| *hint)
+    }
+
+    /// The xpriv version bytes for `hint`. Errors with `UnsupportedHint` if it is `NO_VERSION`
+    pub fn xpriv_version(&self, hint: Hint) -> Result<u32, Bip32Error> {
+        Self::version_for(self.privs(), hint)
+    }
+
+    /// The xpub version bytes for `hint`. Errors with `UnsupportedHint` if it is `NO_VERSION`
+    pub fn xpub_version(&self, hint: Hint) -> Result<u32, Bip32Error> {
+        match self.slip132_pub_version(hint, false) {
+            NO_VERSION => Err(Bip32Error::UnsupportedHint(hint)),
+            version => Ok(version),
+        }
+    }
+
+    /// The SLIP-0132 xpub version bytes for `hint`, using its multisig variant if `multisig`.
+    /// Returns `NO_VERSION` if the network doesn't define them. See `Hint::with_multisig`
+    pub fn slip132_pub_version(&self, hint: Hint, multisig: bool) -> u32 {
+        let hint = hint.with_multisig(multisig);
+        self.pubs()
+            .iter()
+            .find(|(_, h)| *h == hint)
+            .map(|(version, _)| *version)
+            .unwrap_or(NO_VERSION)
+    }
+
+    /// The hint of an xpriv with these version bytes, if they belong to this network
+    pub fn xpriv_hint(&self, version: u32) -> Option<Hint> {
+        Self::hint_for(self.privs(), version)
+    }
+
+    /// The hint of an xpub with these version bytes, if they belong to this network
+    pub fn xpub_hint(&self, version: u32) -> Option<Hint> {
+        Self::hint_for(self.pubs(), version)
+    }
+}
+
+/// An encoder whose version bytes are set at runtime, e.g. from a config file. It has the same
+/// methods as `XKeyEncoder`, but they take `&self`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicEncoder {
+    versions: VersionBytes,
+}
+
+impl From<VersionBytes> for DynamicEncoder {
+    fn from(versions: VersionBytes) -> Self {
+        Self::new(versions)
+    }
+}
+
+impl DynamicEncoder {
+    /// Instantiate an encoder for a network's version bytes
+    pub fn new(versions: VersionBytes) -> Self {
+        Self { versions }
+    }
+
+    /// The version bytes used by this encoder
+    pub fn versions(&self) -> &VersionBytes {
+        &self.versions
+    }
 
-impl<P: NetworkParams> XKeyEncoder for BitcoinEncoder<P> {
     /// Serialize the xpub to `std::io::Write`
-    fn write_xpub<'a, W, T>(writer: &mut W, key: &GenericXPub<'a, T>) -> Result<usize, Bip32Error>
+    pub fn write_xpub<'a, W, T>(
+        &self,
+        writer: &mut W,
+        key: &GenericXPub<'a, T>,
+    ) -> Result<usize, Bip32Error>
     where
         W: std::io::Write,
         T: Secp256k1Backend,
     {
-        let version = match key.hint() {
-            Hint::Legacy => P::PUB_VERSION,
-            Hint::Compatibility => P::BIP49_PUB_VERSION,
-            Hint::SegWit => P::BIP84_PUB_VERSION,
-        };
-        let mut written = writer.write(&version.to_be_bytes())?;
-        written += Self::write_key_details(writer, key)?;
-        written += writer.write(&key.pubkey_bytes())?;
+        let version = self.versions.xpub_version(key.hint())?;
+        let mut written = write_all(writer, &version.to_be_bytes())?;
+        written += write_key_details(writer, key)?;
+        written += write_all(writer, &key.pubkey_bytes())?;
         Ok(written)
     }
 
     /// Serialize the xpriv to `std::io::Write`
-    fn write_xpriv<'a, W, T>(writer: &mut W, key: &GenericXPriv<'a, T>) -> Result<usize, Bip32Error>
+    pub fn write_xpriv<'a, W, T>(
+        &self,
+        writer: &mut W,
+        key: &GenericXPriv<'a, T>,
+    ) -> Result<usize, Bip32Error>
     where
         W: std::io::Write,
         T: Secp256k1Backend,
     {
-        let version = match key.hint() {
-            Hint::Legacy => P::PRIV_VERSION,
-            Hint::Compatibility => P::BIP49_PRIV_VERSION,
-            Hint::SegWit => P::BIP84_PRIV_VERSION,
-        };
-        let mut written = writer.write(&version.to_be_bytes())?;
-        written += Self::write_key_details(writer, key)?;
-        written += writer.write(&[0])?;
-        written += writer.write(&key.privkey_bytes())?;
+        let version = self.versions.xpriv_version(key.hint())?;
+        let mut written = write_all(writer, &version.to_be_bytes())?;
+        written += write_key_details(writer, key)?;
+        written += write_all(writer, &[0])?;
+        written += write_all(writer, &key.privkey_bytes())?;
         Ok(written)
     }
 
-    fn read_xpriv<'a, R, T>(
+    /// Attempt to instantiate an `XPriv` from a `std::io::Read`
+    pub fn read_xpriv<'a, R, T>(
+        &self,
         reader: &mut R,
         backend: Option<&'a T>,
     ) -> Result<GenericXPriv<'a, T>, Bip32Error>
@@ -439,22 +853,16 @@ impl<P: NetworkParams> XKeyEncoder for BitcoinEncoder<P> {
     {
         let mut buf = [0u8; 4];
         reader.read_exact(&mut buf)?;
-        let version_bytes = u32::from_be_bytes(buf);
-
-        // Can't use associated constants in matches :()
-        let hint = if version_bytes == P::PRIV_VERSION {
-            Hint::Legacy
-        } else if version_bytes == P::BIP49_PRIV_VERSION {
-            Hint::Compatibility
-        } else if version_bytes == P::BIP84_PRIV_VERSION {
-            Hint::SegWit
-        } else {
-            return Err(Bip32Error::BadXPrivVersionBytes(buf));
-        };
-        Self::read_xpriv_body(reader, hint, backend)
+        let hint = self
+            .versions
+            .xpriv_hint(u32::from_be_bytes(buf))
+            .ok_or(Bip32Error::BadXPrivVersionBytes(buf))?;
+        read_xpriv_details(reader, hint, backend)
     }
 
-    fn read_xpub<'a, R, T>(
+    /// Attempt to instantiate an `XPub` from a `std::io::Read`
+    pub fn read_xpub<'a, R, T>(
+        &self,
         reader: &mut R,
         backend: Option<&'a T>,
     ) -> Result<GenericXPub<'a, T>, Bip32Error>
@@ -464,19 +872,361 @@ impl<P: NetworkParams> XKeyEncoder for BitcoinEncoder<P> {
     {
         let mut buf = [0u8; 4];
         reader.read_exact(&mut buf)?;
-        let version_bytes = u32::from_be_bytes(buf);
-
-        // Can't use associated constants in matches :()
-        let hint = if version_bytes == P::PUB_VERSION {
-            Hint::Legacy
-        } else if version_bytes == P::BIP49_PUB_VERSION {
-            Hint::Compatibility
-        } else if version_bytes == P::BIP84_PUB_VERSION {
-            Hint::SegWit
-        } else {
-            return Err(Bip32Error::BadXPrivVersionBytes(buf));
+        let hint = self
+            .versions
+            .xpub_hint(u32::from_be_bytes(buf))
+            .ok_or(Bip32Error::BadXPubVersionBytes(buf))?;
+        read_xpub_details(reader, hint, backend)
+    }
+
+    /// Return true if the base58check string is an xpub serialized with any of this encoder's
+    /// pubkey version bytes. See `XKeyEncoder::is_network`
+    pub fn is_network(&self, s: &str) -> bool {
+        let data = match bs58::decode(s).into_vec() {
+            Ok(data) => data,
+            Err(_) => return false,
         };
-        Self::read_xpub_body(reader, hint, backend)
+        // 78-byte serialized key, plus a 4-byte checksum
+        if data.len() != 82 {
+            return false;
+        }
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(&data[..4]);
+        self.versions.xpub_hint(u32::from_be_bytes(buf)).is_some()
+    }
+
+    /// Serialize an XPriv to base58
+    pub fn xpriv_to_base58<'a, T>(&self, k: &GenericXPriv<'a, T>) -> Result<String, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        self.write_xpriv(&mut v, k)?;
+        Ok(encode_b58_check(&v))
+    }
+
+    /// Serialize an XPub to base58
+    pub fn xpub_to_base58<'a, T>(&self, k: &GenericXPub<'a, T>) -> Result<String, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        self.write_xpub(&mut v, k)?;
+        Ok(encode_b58_check(&v))
+    }
+
+    /// Serialize an XPriv to its 78-byte payload, without base58check encoding
+    pub fn xpriv_to_bytes<'a, T>(&self, k: &GenericXPriv<'a, T>) -> Result<Vec<u8>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        self.write_xpriv(&mut v, k)?;
+        Ok(v)
+    }
+
+    /// Serialize an XPub to its 78-byte payload, without base58check encoding
+    pub fn xpub_to_bytes<'a, T>(&self, k: &GenericXPub<'a, T>) -> Result<Vec<u8>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut v: Vec<u8> = vec![];
+        self.write_xpub(&mut v, k)?;
+        Ok(v)
+    }
+
+    /// Attempt to read an XPriv from its 78-byte payload. Errors if `data` has trailing bytes
+    pub fn xpriv_from_bytes<'a, T>(
+        &self,
+        data: &[u8],
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut reader = data;
+        let key = self.read_xpriv(&mut reader, backend)?;
+        check_consumed(reader)?;
+        Ok(key)
+    }
+
+    /// Attempt to read an XPub from its 78-byte payload. Errors if `data` has trailing bytes
+    pub fn xpub_from_bytes<'a, T>(
+        &self,
+        data: &[u8],
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let mut reader = data;
+        let key = self.read_xpub(&mut reader, backend)?;
+        check_consumed(reader)?;
+        Ok(key)
+    }
+
+    /// Attempt to read an XPriv from a b58check string
+    pub fn xpriv_from_base58<'a, T>(
+        &self,
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        self.read_xpriv(&mut &data[..], backend)
+    }
+
+    /// Attempt to read an XPub from a b58check string
+    pub fn xpub_from_base58<'a, T>(
+        &self,
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        self.read_xpub(&mut &data[..], backend)
+    }
+}
+
+/// The hint for version bytes from Bitcoin mainnet or testnet. `Legacy` if they are unknown.
+fn known_hint(version: u32, hint_for: fn(&VersionBytes, u32) -> Option<Hint>) -> Hint {
+    [VersionBytes::of::<Main>(), VersionBytes::of::<Test>()]
+        .iter()
+        .find_map(|versions| hint_for(versions, version))
+        .unwrap_or(Hint::Legacy)
+}
+
+/// A named network and its version bytes, as registered in a `NetworkRegistry`. Returned by
+/// the registry's decoders to identify the network a key belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Network {
+    name: &'static str,
+    versions: VersionBytes,
+}
+
+impl Network {
+    /// Instantiate a network from its runtime version bytes
+    pub fn new(name: &'static str, versions: VersionBytes) -> Self {
+        Self { name, versions }
+    }
+
+    /// Instantiate a network from the compile-time `NetworkParams` `P`
+    pub fn of<P: NetworkParams>(name: &'static str) -> Self {
+        Self::new(name, VersionBytes::of::<P>())
+    }
+
+    /// The name the network was registered under
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The network's version bytes
+    pub fn versions(&self) -> &VersionBytes {
+        &self.versions
+    }
+
+    /// True if the network uses the version bytes of `P`, regardless of its name
+    pub fn is<P: NetworkParams>(&self) -> bool {
+        self.versions == VersionBytes::of::<P>()
+    }
+
+    /// An encoder for the network
+    pub fn encoder(&self) -> DynamicEncoder {
+        DynamicEncoder::new(self.versions)
+    }
+}
+
+/// A list of named networks, used to decode extended keys from an unknown network. Networks are
+/// tried in registration order, so when two networks share version bytes, the first registered
+/// wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkRegistry {
+    networks: Vec<Network>,
+}
+
+impl Default for NetworkRegistry {
+    /// Bitcoin mainnet and testnet, Litecoin mainnet and testnet, and Dogecoin, in that order
+    fn default() -> Self {
+        Self::empty()
+            .register::<Main>("bitcoin")
+            .register::<Test>("testnet")
+            .register::<Litecoin>("litecoin")
+            .register::<LitecoinTest>("litecoin-testnet")
+            .register::<Dogecoin>("dogecoin")
+    }
+}
+
+impl NetworkRegistry {
+    /// A registry with no networks
+    pub fn empty() -> Self {
+        Self { networks: vec![] }
+    }
+
+    /// Register the compile-time network `P` under `name`
+    pub fn register<P: NetworkParams>(self, name: &'static str) -> Self {
+        self.register_network(Network::of::<P>(name))
+    }
+
+    /// Register a network's runtime version bytes under `name`
+    pub fn register_versions(self, name: &'static str, versions: VersionBytes) -> Self {
+        self.register_network(Network::new(name, versions))
+    }
+
+    /// Register a network
+    pub fn register_network(mut self, network: Network) -> Self {
+        self.networks.push(network);
+        self
+    }
+
+    /// The registered networks, in registration order
+    pub fn networks(&self) -> &[Network] {
+        &self.networks
+    }
+
+    /// The first registered network using these version bytes, and their hint
+    fn find(
+        &self,
+        version: [u8; 4],
+        hint_for: fn(&VersionBytes, u32) -> Option<Hint>,
+    ) -> Option<(Network, Hint)> {
+        let version = u32::from_be_bytes(version);
+        self.networks
+            .iter()
+            .find_map(|network| hint_for(&network.versions, version).map(|hint| (*network, hint)))
+    }
+
+    /// Decode an xpriv from any registered network. Returns the network, the key's hint, and
+    /// the key.
+    ///
+    /// # Errors
+    ///
+    /// - `Bip32Error::BadXPrivVersionBytes` if no registered network uses the version bytes
+    pub fn decode_xpriv<'a, T>(
+        &self,
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<(Network, Hint, GenericXPriv<'a, T>), Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        let mut version = [0u8; 4];
+        std::io::Read::read_exact(&mut &data[..], &mut version)?;
+        let (network, hint) = self
+            .find(version, VersionBytes::xpriv_hint)
+            .ok_or(Bip32Error::BadXPrivVersionBytes(version))?;
+        let key = network.encoder().read_xpriv(&mut &data[..], backend)?;
+        Ok((network, hint, key))
+    }
+
+    /// Decode an xpub from any registered network. Returns the network, the key's hint, and
+    /// the key.
+    ///
+    /// # Errors
+    ///
+    /// - `Bip32Error::BadXPubVersionBytes` if no registered network uses the version bytes
+    pub fn decode_xpub<'a, T>(
+        &self,
+        s: &str,
+        backend: Option<&'a T>,
+    ) -> Result<(Network, Hint, GenericXPub<'a, T>), Bip32Error>
+    where
+        T: Secp256k1Backend,
+    {
+        let data = decode_b58_check(s)?;
+        let mut version = [0u8; 4];
+        std::io::Read::read_exact(&mut &data[..], &mut version)?;
+        let (network, hint) = self
+            .find(version, VersionBytes::xpub_hint)
+            .ok_or(Bip32Error::BadXPubVersionBytes(version))?;
+        let key = network.encoder().read_xpub(&mut &data[..], backend)?;
+        Ok((network, hint, key))
+    }
+}
+
+/// Decode an xpriv from any network in the default `NetworkRegistry`. See
+/// `NetworkRegistry::decode_xpriv`
+pub fn decode_any_xpriv<'a, T>(
+    s: &str,
+    backend: Option<&'a T>,
+) -> Result<(Network, Hint, GenericXPriv<'a, T>), Bip32Error>
+where
+    T: Secp256k1Backend,
+{
+    NetworkRegistry::default().decode_xpriv(s, backend)
+}
+
+/// Decode an xpub from any network in the default `NetworkRegistry`. See
+/// `NetworkRegistry::decode_xpub`
+pub fn decode_any_xpub<'a, T>(
+    s: &str,
+    backend: Option<&'a T>,
+) -> Result<(Network, Hint, GenericXPub<'a, T>), Bip32Error>
+where
+    T: Secp256k1Backend,
+{
+    NetworkRegistry::default().decode_xpub(s, backend)
+}
+
+/// Parameterizable Bitcoin encoder. This delegates to a `DynamicEncoder` with the version bytes
+/// of `P`
+#[derive(Debug, Clone)]
+pub struct BitcoinEncoder<P: NetworkParams>(PhantomData<fn(P) -> P>);
+
+impl<P: NetworkParams> BitcoinEncoder<P> {
+    /// The equivalent runtime encoder
+    pub fn dynamic() -> DynamicEncoder {
+        DynamicEncoder::new(VersionBytes::of::<P>())
+    }
+}
+
+impl<P: NetworkParams> XKeyEncoder for BitcoinEncoder<P> {
+    /// Serialize the xpub to `std::io::Write`
+    fn write_xpub<'a, W, T>(writer: &mut W, key: &GenericXPub<'a, T>) -> Result<usize, Bip32Error>
+    where
+        W: std::io::Write,
+        T: Secp256k1Backend,
+    {
+        Self::dynamic().write_xpub(writer, key)
+    }
+
+    /// Serialize the xpriv to `std::io::Write`
+    fn write_xpriv<'a, W, T>(writer: &mut W, key: &GenericXPriv<'a, T>) -> Result<usize, Bip32Error>
+    where
+        W: std::io::Write,
+        T: Secp256k1Backend,
+    {
+        Self::dynamic().write_xpriv(writer, key)
+    }
+
+    fn read_xpriv<'a, R, T>(
+        reader: &mut R,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPriv<'a, T>, Bip32Error>
+    where
+        R: std::io::Read,
+        T: Secp256k1Backend,
+    {
+        Self::dynamic().read_xpriv(reader, backend)
+    }
+
+    fn read_xpub<'a, R, T>(
+        reader: &mut R,
+        backend: Option<&'a T>,
+    ) -> Result<GenericXPub<'a, T>, Bip32Error>
+    where
+        R: std::io::Read,
+        T: Secp256k1Backend,
+    {
+        Self::dynamic().read_xpub(reader, backend)
+    }
+
+    fn is_network(s: &str) -> bool {
+        Self::dynamic().is_network(s)
     }
 }
 
@@ -484,15 +1234,458 @@ impl<P: NetworkParams> XKeyEncoder for BitcoinEncoder<P> {
 pub type MainnetEncoder = BitcoinEncoder<Main>;
 /// XKeyEncoder for Testnet xkeys
 pub type TestnetEncoder = BitcoinEncoder<Test>;
+/// XKeyEncoder for Litecoin mainnet xkeys
+pub type LitecoinEncoder = BitcoinEncoder<Litecoin>;
+/// XKeyEncoder for Litecoin testnet xkeys
+pub type LitecoinTestnetEncoder = BitcoinEncoder<LitecoinTest>;
+/// XKeyEncoder for Dogecoin mainnet xkeys
+pub type DogecoinEncoder = BitcoinEncoder<Dogecoin>;
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::xkeys::XPriv;
+    use crate::{
+        curve::Secp256k1,
+        model::{DerivePublicChild, HasBackend},
+        xkeys::{XPriv, XPub},
+    };
 
     #[test]
     fn it_can_read_keys_without_a_backend() {
         let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi".to_owned();
         let _xpriv: XPriv = MainnetEncoder::xpriv_from_base58(&xpriv_str, None).unwrap();
     }
+
+    #[test]
+    fn it_rejects_short_b58_check_strings() {
+        for s in ["", "1", "111"].iter() {
+            match decode_b58_check(s) {
+                Err(Bip32Error::BadB58Checksum) => {}
+                _ => assert!(false, "expected BadB58Checksum"),
+            }
+        }
+        let xpub: Result<XPub, _> = MainnetEncoder::xpub_from_base58("1", None);
+        assert!(xpub.is_err());
+    }
+
+    #[test]
+    fn it_exports_slip132_versions() {
+        assert_eq!(Hint::Legacy.slip132_pub_version::<Main>(false), 0x0488_b21e);
+        assert_eq!(Hint::Legacy.slip132_pub_version::<Main>(true), 0x0488_b21e);
+        assert_eq!(
+            Hint::Compatibility.slip132_pub_version::<Main>(true),
+            0x0295_b43f
+        );
+        assert_eq!(Hint::SegWit.slip132_pub_version::<Main>(false), 0x04b2_4746);
+        assert_eq!(Hint::SegWit.slip132_pub_version::<Main>(true), 0x02aa_7ed3);
+        assert_eq!(
+            Hint::SegWitMultisig.slip132_pub_version::<Test>(false),
+            0x045f_1cf6
+        );
+        assert_eq!(
+            Hint::SegWit.slip132_pub_version::<Dogecoin>(false),
+            NO_VERSION
+        );
+
+        let xpub = XPriv::root_from_seed(&[0u8; 32], Some(Hint::SegWit))
+            .unwrap()
+            .to_xpub()
+            .unwrap();
+        assert!(MainnetEncoder::xpub_to_base58(&xpub)
+            .unwrap()
+            .starts_with("zpub"));
+    }
+
+    #[test]
+    fn it_parses_slip132_prefixes() {
+        let cases = [
+            ("xpub", Hint::Legacy, Hint::Legacy, false),
+            ("ypub", Hint::Compatibility, Hint::Compatibility, false),
+            ("zpub", Hint::SegWit, Hint::SegWit, false),
+            (
+                "Ypub",
+                Hint::CompatibilityMultisig,
+                Hint::Compatibility,
+                true,
+            ),
+            ("Zpub", Hint::SegWitMultisig, Hint::SegWit, true),
+        ];
+        for (prefix, key_hint, hint, multisig) in cases.iter() {
+            let xpub = XPriv::root_from_seed(&[0u8; 32], Some(*key_hint))
+                .unwrap()
+                .to_xpub()
+                .unwrap();
+            let s = MainnetEncoder::xpub_to_base58(&xpub).unwrap();
+            assert!(s.starts_with(prefix));
+
+            let (parsed, h, m) = MainnetEncoder::from_slip132(&s, xpub.backend().ok()).unwrap();
+            assert_eq!(parsed, xpub);
+            assert_eq!(parsed.hint(), *key_hint);
+            assert_eq!(h, *hint);
+            assert_eq!(m, *multisig);
+        }
+    }
+
+    #[test]
+    fn it_decodes_keys_from_any_network() {
+        let cases = [
+            ("xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y", Network::of::<Main>("bitcoin"), Hint::Legacy),
+            ("vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT", Network::of::<Test>("testnet"), Hint::SegWit),
+            ("dgub8rKhExqKD6L2SBipHhXoL7W6jm2Comg6fNaQ5aYXoiVS98ZaZiwSBVaxcT6hW9LZZA7u3kefaWZwGmXU1B6YeacTfPt8gF7hTSEervsqwQA", Network::of::<Dogecoin>("dogecoin"), Hint::Legacy),
+        ];
+        for (s, network, hint) in cases.iter() {
+            let (n, h, xpub): (_, _, XPub) = decode_any_xpub(s, None).unwrap();
+            assert_eq!(n, *network);
+            assert_eq!(h, *hint);
+            assert_eq!(xpub.hint(), *hint);
+            // the network's encoder reproduces the string
+            assert_eq!(n.encoder().xpub_to_base58(&xpub).unwrap(), *s);
+        }
+
+        let dgpv = "dgpv57SfuGuRewfh9L4fFTuqDFxWHCeZyeePNmoBvU9Z1bSRb5St9xPfYCBK3DFYNt3mzwuBq4Mq6T6Vd16NJonCfune151GeZZByT3DT68QN4F";
+        let (n, _, _): (_, _, XPriv) = decode_any_xpriv(dgpv, None).unwrap();
+        assert!(n.is::<Dogecoin>());
+        assert!(!n.is::<Main>());
+
+        let registry = NetworkRegistry::empty().register::<Dogecoin>("doge");
+        assert_eq!(registry.networks(), &[Network::of::<Dogecoin>("doge")]);
+        let (n, _, _): (_, _, XPriv) = registry.decode_xpriv(dgpv, None).unwrap();
+        assert_eq!(n.name(), "doge");
+        assert!(n.is::<Dogecoin>());
+        match registry.decode_xpub::<Secp256k1>(cases[0].0, None) {
+            Err(Bip32Error::BadXPubVersionBytes(v)) => assert_eq!(v, [0x04, 0x88, 0xb2, 0x1e]),
+            _ => assert!(false, "expected BadXPubVersionBytes"),
+        }
+    }
+
+    #[test]
+    fn it_roundtrips_raw_payloads() {
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpriv: XPriv = MainnetEncoder::xpriv_from_base58(xpriv_str, None).unwrap();
+        let bytes = MainnetEncoder::xpriv_to_bytes(&xpriv).unwrap();
+        assert_eq!(bytes.len(), 78);
+        assert_eq!(bytes, decode_b58_check(xpriv_str).unwrap());
+        let parsed: XPriv = MainnetEncoder::xpriv_from_bytes(&bytes, None).unwrap();
+        assert_eq!(parsed, xpriv);
+
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let xpub: XPub = MainnetEncoder::xpub_from_base58(xpub_str, None).unwrap();
+        let mut bytes = MainnetEncoder::dynamic().xpub_to_bytes(&xpub).unwrap();
+        let parsed: XPub = MainnetEncoder::xpub_from_bytes(&bytes, None).unwrap();
+        assert_eq!(parsed, xpub);
+
+        bytes.push(0);
+        let trailing: Result<XPub, _> = MainnetEncoder::xpub_from_bytes(&bytes, None);
+        assert!(trailing.is_err());
+        let short: Result<XPub, _> = MainnetEncoder::xpub_from_bytes(&bytes[..77], None);
+        assert!(short.is_err());
+    }
+
+    #[test]
+    fn it_reports_xpub_version_errors() {
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpub: Result<XPub, _> = MainnetEncoder::xpub_from_base58(xpriv_str, None);
+        match xpub {
+            Err(Bip32Error::BadXPubVersionBytes(v)) => assert_eq!(v, [0x04, 0x88, 0xad, 0xe4]),
+            _ => assert!(false, "expected BadXPubVersionBytes"),
+        }
+    }
+
+    #[test]
+    fn it_reports_uncompressed_keys() {
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let mut data = decode_b58_check(xpub_str).unwrap();
+        data[45] = 0x04;
+        let xpub: Result<XPub, _> = MainnetEncoder::read_xpub(&mut &data[..], None);
+        match xpub {
+            Err(Bip32Error::UncompressedKeyUnsupported) => {}
+            _ => assert!(false, "expected UncompressedKeyUnsupported"),
+        }
+    }
+
+    /// Accepts at most one byte per call
+    struct Trickle(Vec<u8>);
+
+    impl std::io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend(buf.iter().take(1));
+            Ok(buf.len().min(1))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_writes_complete_keys_to_short_writers() {
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpriv: XPriv = MainnetEncoder::xpriv_from_base58(xpriv_str, None).unwrap();
+        let mut writer = Trickle(vec![]);
+        assert_eq!(
+            MainnetEncoder::write_xpriv(&mut writer, &xpriv).unwrap(),
+            78
+        );
+        assert_eq!(encode_b58_check(&writer.0), xpriv_str);
+
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let xpub: XPub = MainnetEncoder::xpub_from_base58(xpub_str, None).unwrap();
+        let mut writer = Trickle(vec![]);
+        assert_eq!(MainnetEncoder::write_xpub(&mut writer, &xpub).unwrap(), 78);
+        assert_eq!(encode_b58_check(&writer.0), xpub_str);
+    }
+
+    #[test]
+    fn it_infers_hints_without_a_network() {
+        let cases = [
+            ("zpub6n36Kf78pA3v8gxoxVMNRn7JzPXLpM142eGJf5PgNd1AULxMWNpY6HXnjFWshGb2Q5w121PrHfNFSQNUzBvpp3kb55MHkwKuwpB1UETtD11", Hint::SegWit),
+            ("vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT", Hint::SegWit),
+            ("xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y", Hint::Legacy),
+            // Litecoin version bytes are unknown
+            ("Ltub2YEz7qzkZSGcWK8PatiqktKQaGCGVz3ikCarLMHQrzYQKJnvH4upL5zhcF7jnaLM9e1bBzLupY1NN8aZMLYFVgYKvqN5pf6dbLiP3zaCxFw", Hint::Legacy),
+        ];
+        for (xpub_str, hint) in cases.iter() {
+            let data = decode_b58_check(xpub_str).unwrap();
+            let xpub: XPub =
+                MainnetEncoder::read_xpub_without_network(&mut &data[..], None).unwrap();
+            assert_eq!(xpub.hint(), *hint);
+        }
+
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let mut xpriv: XPriv = MainnetEncoder::xpriv_from_base58(xpriv_str, None).unwrap();
+        xpriv.info.hint = Hint::Compatibility;
+        let data = decode_b58_check(&MainnetEncoder::xpriv_to_base58(&xpriv).unwrap()).unwrap();
+        let parsed: XPriv =
+            TestnetEncoder::read_xpriv_without_network(&mut &data[..], None).unwrap();
+        assert_eq!(parsed.hint(), Hint::Compatibility);
+    }
+
+    #[test]
+    fn it_checks_metadata_when_strict() {
+        let master = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let child = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let _: XPriv = MainnetEncoder::xpriv_from_base58_strict(master, None).unwrap();
+        let _: XPub = MainnetEncoder::xpub_from_base58_strict(child, None).unwrap();
+
+        // a master key with a nonzero index
+        let mut data = decode_b58_check(master).unwrap();
+        data[12] = 1;
+        let bad_master = encode_b58_check(&data);
+        let _: XPriv = MainnetEncoder::xpriv_from_base58(&bad_master, None).unwrap();
+        let strict: Result<XPriv, _> = MainnetEncoder::xpriv_from_base58_strict(&bad_master, None);
+        match strict {
+            Err(Bip32Error::InvalidMasterKeyMetadata) => {}
+            _ => assert!(false, "expected InvalidMasterKeyMetadata"),
+        }
+
+        // a child key with a zero parent fingerprint
+        let mut data = decode_b58_check(child).unwrap();
+        data[5..9].copy_from_slice(&[0; 4]);
+        let orphan = encode_b58_check(&data);
+        let _: XPub = MainnetEncoder::xpub_from_base58(&orphan, None).unwrap();
+        let strict: Result<XPub, _> = MainnetEncoder::xpub_from_base58_strict(&orphan, None);
+        match strict {
+            Err(Bip32Error::InvalidMasterKeyMetadata) => {}
+            _ => assert!(false, "expected InvalidMasterKeyMetadata"),
+        }
+    }
+
+    #[test]
+    fn it_checks_xpub_networks() {
+        let mainnet = [
+            "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+            "zpub6n36Kf78pA3v8gxoxVMNRn7JzPXLpM142eGJf5PgNd1AULxMWNpY6HXnjFWshGb2Q5w121PrHfNFSQNUzBvpp3kb55MHkwKuwpB1UETtD11",
+        ];
+        let testnet = [
+            "tpubD8kCEZazE4vQhtmRjxmDDXFfyaL6vVX7k3pASqf3xX1J7Rzc5HLVzbtLvsgVDxERNiEJ8dibuSVCN1dxwex371qgPzhkGeMAzKe8T7ivSof",
+            "vpub5Ui36zRUDRszjWCLd4CsbRjJJWwZ3s34NCBRXVp8rbVeFwhSVkAHc2uEeRgXhdyLmXTn271cT1x3uFvE7QGmd72BbiZbRJ3xruvRuuCo7MT",
+        ];
+        for xpub in mainnet.iter() {
+            assert!(MainnetEncoder::is_network(xpub));
+            assert!(!TestnetEncoder::is_network(xpub));
+        }
+        for xpub in testnet.iter() {
+            assert!(TestnetEncoder::is_network(xpub));
+            assert!(!MainnetEncoder::is_network(xpub));
+        }
+
+        // xprivs and junk are rejected
+        let xpriv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        assert!(!MainnetEncoder::is_network(xpriv));
+        assert!(!MainnetEncoder::is_network(""));
+        assert!(!MainnetEncoder::is_network("not base58 0OIl"));
+    }
+
+    #[test]
+    fn it_attaches_the_backend_when_parsing() {
+        let backend = Secp256k1::static_ref();
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+
+        let xpub = MainnetEncoder::xpub_from_base58(xpub_str, Some(backend)).unwrap();
+        assert_eq!(xpub.backend().unwrap(), backend);
+        let child = xpub.derive_public_path(&[0u32, 1][..]).unwrap();
+        assert_eq!(child.backend().unwrap(), backend);
+
+        let xpub: XPub = MainnetEncoder::xpub_from_base58(xpub_str, None).unwrap();
+        match xpub.derive_public_child(0) {
+            Err(Bip32Error::NoBackend) => {}
+            _ => assert!(false, "expected NoBackend"),
+        }
+    }
+
+    #[test]
+    fn it_roundtrips_slip132_keys() {
+        // SLIP-0132 vectors for the "abandon ... about" mnemonic
+        let singlesig = [
+            ("xprv9xpXFhFpqdQK3TmytPBqXtGSwS3DLjojFhTGht8gwAAii8py5X6pxeBnQ6ehJiyJ6nDjWGJfZ95WxByFXVkDxHXrqu53WCRGypk2ttuqncb", "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj", Hint::Legacy),
+            ("yprvAHwhK6RbpuS3dgCYHM5jc2ZvEKd7Bi61u9FVhYMpgMSuZS613T1xxQeKTffhrHY79hZ5PsskBjcc6C2V7DrnsMsNaGDaWev3GLRQRgV7hxF", "ypub6Ww3ibxVfGzLrAH1PNcjyAWenMTbbAosGNB6VvmSEgytSER9azLDWCxoJwW7Ke7icmizBMXrzBx9979FfaHxHcrArf3zbeJJJUZPf663zsP", Hint::Compatibility),
+            ("zprvAdG4iTXWBoARxkkzNpNh8r6Qag3irQB8PzEMkAFeTRXxHpbF9z4QgEvBRmfvqWvGp42t42nvgGpNgYSJA9iefm1yYNZKEm7z6qUWCroSQnE", "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs", Hint::SegWit),
+        ];
+        for (xpriv_str, xpub_str, hint) in singlesig.iter() {
+            let xpriv: XPriv = MainnetEncoder::xpriv_from_base58(xpriv_str, None).unwrap();
+            let xpub: XPub = MainnetEncoder::xpub_from_base58(xpub_str, None).unwrap();
+            assert_eq!(xpriv.hint(), *hint);
+            assert_eq!(xpub.hint(), *hint);
+            assert_eq!(MainnetEncoder::xpriv_to_base58(&xpriv).unwrap(), *xpriv_str);
+            assert_eq!(MainnetEncoder::xpub_to_base58(&xpub).unwrap(), *xpub_str);
+        }
+
+        // The zpub and ypub above, re-encoded with the multisig and testnet version bytes
+        let zpub: XPub = MainnetEncoder::xpub_from_base58(singlesig[2].1, None).unwrap();
+        let ypub: XPub = MainnetEncoder::xpub_from_base58(singlesig[1].1, None).unwrap();
+        let multisig = [
+            (zpub, Hint::SegWitMultisig, "Zpub739WFCnqb8H6bozqRWNgL4NwrVvUUDaa5UodTovoPXuLnoVJTvkwKA6b5ioUif4ntuhU53ob9LUdZ66F3uNGoX8S6gzjGa1yvYFtkDRknR2", "Vpub5jpT2Y7AzQ7BCdEN65EBVhzwAdLghjcaR2ikLEMFsWPpaQEPTJ6gpuU2zty8j2T7GMEF59RMJh4S1wdzB7iDcaQ2dLD2vvk2qe1KByf7ky3"),
+            (ypub, Hint::CompatibilityMultisig, "Ypub6hq8qqgwEEYiGjSPL35ioErTW9VroXVTadpmRC2ycTpJ4Qz4MPiVbKpj7eTat5LcrEny3wXysQLeGGm1xoSuS6xqh8AQ13mJCCqGF1FEFNo", "Upub5QW5dB1GdWNnsYfuzbwDxtUSpGv533XTvBjtHcTS6SJmr1j9Lm4F75CB2pdEtSiwDgKk439k2kvSj8Jm61nrFAESDmNhfQVM7Jaggeqi6Ki"),
+        ];
+        for (key, hint, mainnet, testnet) in multisig.iter() {
+            let mut key = key.clone();
+            key.info.hint = *hint;
+            assert_eq!(MainnetEncoder::xpub_to_base58(&key).unwrap(), *mainnet);
+            assert_eq!(TestnetEncoder::xpub_to_base58(&key).unwrap(), *testnet);
+            assert!(MainnetEncoder::is_network(mainnet));
+            assert!(TestnetEncoder::is_network(testnet));
+
+            let parsed: XPub = MainnetEncoder::xpub_from_base58(mainnet, None).unwrap();
+            assert_eq!(parsed, key);
+            let parsed: XPub = TestnetEncoder::xpub_from_base58(testnet, None).unwrap();
+            assert_eq!(parsed, key);
+        }
+
+        let mut zprv: XPriv = MainnetEncoder::xpriv_from_base58(singlesig[2].0, None).unwrap();
+        zprv.info.hint = Hint::SegWitMultisig;
+        let encoded = MainnetEncoder::xpriv_to_base58(&zprv).unwrap();
+        assert_eq!(
+            encoded,
+            "ZprvApA9qhFwkkioPKvNKUqfxvSDJU5z4kriiFt2fRXBqCNMv1A9vPSgmMn7EUdQPx9B3X6rvco3ZVCsoi44TNsbpF8eNqfieAayzZkNnquACTC"
+        );
+        let parsed: XPriv = MainnetEncoder::xpriv_from_base58(&encoded, None).unwrap();
+        assert_eq!(parsed.hint(), Hint::SegWitMultisig);
+    }
+
+    #[test]
+    fn it_roundtrips_litecoin_keys() {
+        let xpub: XPub = MainnetEncoder::xpub_from_base58("xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj", None).unwrap();
+        let xpriv: XPriv = MainnetEncoder::xpriv_from_base58("xprv9xpXFhFpqdQK3TmytPBqXtGSwS3DLjojFhTGht8gwAAii8py5X6pxeBnQ6ehJiyJ6nDjWGJfZ95WxByFXVkDxHXrqu53WCRGypk2ttuqncb", None).unwrap();
+
+        let ltub = "Ltub2YEz7qzkZSGcWK8PatiqktKQaGCGVz3ikCarLMHQrzYQKJnvH4upL5zhcF7jnaLM9e1bBzLupY1NN8aZMLYFVgYKvqN5pf6dbLiP3zaCxFw";
+        let parsed: XPub = LitecoinEncoder::xpub_from_base58(ltub, None).unwrap();
+        assert_eq!(parsed, xpub);
+        assert_eq!(LitecoinEncoder::xpub_to_base58(&parsed).unwrap(), ltub);
+        assert!(LitecoinEncoder::is_network(ltub));
+        assert!(!MainnetEncoder::is_network(ltub));
+
+        let ltpv = "Ltpv774eX4aDKHjetSRZLHETgJVC8hW5RjV4fgUpfNX7vokoiwbqx9sPKUtSMsBiBScV1to1amxBiDnAwh6KvcjCbPuCoU2Dp9UJnDMvxPNr4Dk";
+        let parsed: XPriv = LitecoinEncoder::xpriv_from_base58(ltpv, None).unwrap();
+        assert_eq!(parsed, xpriv);
+        assert_eq!(LitecoinEncoder::xpriv_to_base58(&parsed).unwrap(), ltpv);
+
+        let mut mtub = xpub.clone();
+        mtub.info.hint = Hint::Compatibility;
+        assert_eq!(
+            LitecoinEncoder::xpub_to_base58(&mtub).unwrap(),
+            "Mtub2s5FRWffi7p6McKWRFWTxyQukELiSc3DfK757kBJEzvHNQc9Xj5Nx9eqdT5KnUzGZH8PwTwUHCMvFRC852xGHvDvoB4WQZv7s4n2SVWpEio"
+        );
+
+        let ttub = "ttub4dAk8Gxj3FWc2pJqUjmEPRe84FB8syJcqiaitrH59GMA4pvcHGgUbQjjwysNDVgtmemFZjWi9x6iRyFk5zHPvrCsAk8eWdN3bgt2BzY2sNk";
+        let parsed: XPub = LitecoinTestnetEncoder::xpub_from_base58(ttub, None).unwrap();
+        assert_eq!(parsed, xpub);
+        assert_eq!(
+            LitecoinTestnetEncoder::xpub_to_base58(&parsed).unwrap(),
+            ttub
+        );
+    }
+
+    #[test]
+    fn it_roundtrips_dogecoin_keys() {
+        let xpub: XPub = MainnetEncoder::xpub_from_base58("xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj", None).unwrap();
+        let xpriv: XPriv = MainnetEncoder::xpriv_from_base58("xprv9xpXFhFpqdQK3TmytPBqXtGSwS3DLjojFhTGht8gwAAii8py5X6pxeBnQ6ehJiyJ6nDjWGJfZ95WxByFXVkDxHXrqu53WCRGypk2ttuqncb", None).unwrap();
+
+        let dgub = "dgub8rKhExqKD6L2SBipHhXoL7W6jm2Comg6fNaQ5aYXoiVS98ZaZiwSBVaxcT6hW9LZZA7u3kefaWZwGmXU1B6YeacTfPt8gF7hTSEervsqwQA";
+        let parsed: XPub = DogecoinEncoder::xpub_from_base58(dgub, None).unwrap();
+        assert_eq!(parsed, xpub);
+        assert_eq!(DogecoinEncoder::xpub_to_base58(&parsed).unwrap(), dgub);
+        assert!(DogecoinEncoder::is_network(dgub));
+
+        let dgpv = "dgpv57SfuGuRewfh9L4fFTuqDFxWHCeZyeePNmoBvU9Z1bSRb5St9xPfYCBK3DFYNt3mzwuBq4Mq6T6Vd16NJonCfune151GeZZByT3DT68QN4F";
+        let parsed: XPriv = DogecoinEncoder::xpriv_from_base58(dgpv, None).unwrap();
+        assert_eq!(parsed, xpriv);
+        assert_eq!(DogecoinEncoder::xpriv_to_base58(&parsed).unwrap(), dgpv);
+
+        // keys hinted for segwit can't be serialized
+        let mut segwit = xpriv.clone();
+        segwit.info.hint = Hint::SegWit;
+        match DogecoinEncoder::xpriv_to_base58(&segwit) {
+            Err(Bip32Error::UnsupportedHint(Hint::SegWit)) => {}
+            _ => assert!(false, "expected UnsupportedHint"),
+        }
+        let mut compat = xpub.clone();
+        compat.info.hint = Hint::Compatibility;
+        match DogecoinEncoder::xpub_to_base58(&compat) {
+            Err(Bip32Error::UnsupportedHint(Hint::Compatibility)) => {}
+            _ => assert!(false, "expected UnsupportedHint"),
+        }
+
+        // and the placeholder version bytes are never parsed
+        let mut data = vec![0u8; 4];
+        data.extend(&decode_b58_check(dgub).unwrap()[4..]);
+        let zeroed = encode_b58_check(&data);
+        assert!(!DogecoinEncoder::is_network(&zeroed));
+        let parsed: Result<XPub, _> = DogecoinEncoder::xpub_from_base58(&zeroed, None);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn it_encodes_with_runtime_version_bytes() {
+        let xpub_str = "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y";
+        let xpub: XPub = MainnetEncoder::xpub_from_base58(xpub_str, None).unwrap();
+
+        let mainnet = DynamicEncoder::new(VersionBytes::of::<Main>());
+        assert_eq!(mainnet, MainnetEncoder::dynamic());
+        assert!(mainnet.is_network(xpub_str));
+        assert_eq!(mainnet.xpub_to_base58(&xpub).unwrap(), xpub_str);
+        let parsed: XPub = mainnet.xpub_from_base58(xpub_str, None).unwrap();
+        assert_eq!(parsed, xpub);
+
+        // a network configured at runtime with the Litecoin BIP32 bytes and no others
+        let versions = VersionBytes {
+            priv32: 0x019d_9cfe,
+            pub32: 0x019d_a462,
+            ..VersionBytes::of::<Dogecoin>()
+        };
+        let custom = DynamicEncoder::from(versions);
+        assert_eq!(custom.versions(), &versions);
+        let encoded = custom.xpub_to_base58(&xpub).unwrap();
+        assert_eq!(encoded, LitecoinEncoder::xpub_to_base58(&xpub).unwrap());
+        assert!(custom.is_network(&encoded));
+        assert!(!custom.is_network(xpub_str));
+        let parsed: XPub = custom.xpub_from_base58(&encoded, None).unwrap();
+        assert_eq!(parsed, xpub);
+
+        let mut segwit = xpub;
+        segwit.info.hint = Hint::SegWit;
+        match custom.xpub_to_base58(&segwit) {
+            Err(Bip32Error::UnsupportedHint(Hint::SegWit)) => {}
+            _ => assert!(false, "expected UnsupportedHint"),
+        }
+    }
 }
@@ -1,7 +1,8 @@
-use coins_core::hashes::Hash256Digest;
+use coins_core::hashes::{Hash256Digest, MarkedDigestOutput};
+use sha2::{Digest, Sha256};
 
 use crate::{
-    curve::{ScalarSerialize, Secp256k1Backend},
+    curve::{PointSerialize, ScalarDeserialize, ScalarSerialize, Secp256k1Backend},
     model::{CanDerivePubkey, HasBackend, HasPrivkey, HasPubkey, SigningKey, VerifyingKey},
     Bip32Error,
 };
@@ -44,6 +45,42 @@ impl<'a, T: Secp256k1Backend> HasPrivkey<'a, T> for GenericPrivkey<'a, T> {
     }
 }
 
+impl<'a, T: Secp256k1Backend> GenericPrivkey<'a, T> {
+    /// Perform ECDH with another party's pubkey. Returns the SHA256 of the compressed shared
+    /// point, as libsecp256k1's ECDH module does. Both parties derive the same secret.
+    pub fn ecdh(&self, their_pubkey: &GenericPubkey<'a, T>) -> Result<[u8; 32], Bip32Error> {
+        let shared = self
+            .backend()?
+            .mul_pubkey(their_pubkey.pubkey(), self.key.privkey_array())
+            .map_err(Into::into)?;
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&Sha256::digest(&shared.pubkey_array()));
+        Ok(secret)
+    }
+
+    /// Produce an ECDSA signature on a 32-byte digest using the provided nonce `k`, rather than
+    /// an RFC6979 nonce. The signature is normalized to low-s. The arithmetic is done by the
+    /// backend, with the same constant-time scalar operations it uses for signing.
+    ///
+    /// # Warning
+    ///
+    /// This is DANGEROUS. Reusing a nonce, or using a predictable or biased nonce, reveals the
+    /// private key. It exists for checking signers against fixed test vectors and for
+    /// threshold-signing integrations. Use `sign_digest` otherwise.
+    ///
+    /// Errors with `Bip32Error::InvalidKey` if the nonce is zero or not below the curve order.
+    pub fn sign_ecdsa_with_nonce(
+        &self,
+        msg: &[u8; 32],
+        nonce: &[u8; 32],
+    ) -> Result<T::Signature, Bip32Error> {
+        let nonce = T::Privkey::from_privkey_array(*nonce).map_err(|_| Bip32Error::InvalidKey)?;
+        self.backend()?
+            .sign_digest_with_nonce(&self.key, (*msg).into(), &nonce)
+            .map_err(Into::into)
+    }
+}
+
 impl<'a, T: Secp256k1Backend> HasBackend<'a, T> for GenericPrivkey<'a, T> {
     fn set_backend(&mut self, backend: &'a T) {
         self.backend = Some(backend);
@@ -88,6 +125,14 @@ impl<'a, T: Secp256k1Backend> GenericPubkey<'a, T> {
             backend: Some(backend),
         })
     }
+
+    /// Return the HASH160 of the compressed public key. This is the 20-byte payload of p2pkh and
+    /// p2wpkh scripts.
+    pub fn pubkey_hash(&self) -> [u8; 20] {
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(self.pubkey_hash160().as_slice());
+        hash
+    }
 }
 
 impl<'a, T: Secp256k1Backend> HasPubkey<'a, T> for GenericPubkey<'a, T> {
@@ -109,3 +154,106 @@ impl<'a, T: Secp256k1Backend> HasBackend<'a, T> for GenericPubkey<'a, T> {
 impl<'a, T: Secp256k1Backend> VerifyingKey<'a, T> for GenericPubkey<'a, T> {
     type SigningKey = GenericPrivkey<'a, T>;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        curve::{PointDeserialize, ScalarDeserialize, Secp256k1, SigSerialize},
+        CURVE_ORDER,
+    };
+
+    #[test]
+    fn it_calculates_pubkey_hashes() {
+        let mut buf = [0u8; 33];
+        buf.copy_from_slice(
+            &hex::decode("0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2")
+                .unwrap(),
+        );
+        let pubkey = Pubkey {
+            key: crate::curve::Pubkey::from_pubkey_array(buf).unwrap(),
+            backend: Some(Secp256k1::static_ref()),
+        };
+        assert_eq!(
+            hex::encode(pubkey.pubkey_hash()),
+            "3442193e1bb70916e914552172cd4e2dbc9df811"
+        );
+    }
+
+    #[test]
+    fn it_derives_shared_secrets() {
+        let backend = Secp256k1::static_ref();
+        let alice = Privkey {
+            key: crate::curve::Privkey::from_privkey_array([1u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let bob = Privkey {
+            key: crate::curve::Privkey::from_privkey_array([2u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let alice_pub = alice.derive_verifying_key().unwrap();
+        let bob_pub = bob.derive_verifying_key().unwrap();
+        assert_eq!(alice.ecdh(&bob_pub).unwrap(), bob.ecdh(&alice_pub).unwrap());
+
+        // 1 * 2G
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut two = [0u8; 32];
+        two[31] = 2;
+        let one = Privkey {
+            key: crate::curve::Privkey::from_privkey_array(one).unwrap(),
+            backend: Some(backend),
+        };
+        let two = Privkey {
+            key: crate::curve::Privkey::from_privkey_array(two).unwrap(),
+            backend: Some(backend),
+        };
+        assert_eq!(
+            hex::encode(one.ecdh(&two.derive_verifying_key().unwrap()).unwrap()),
+            "b1c9938f01121e159887ac2c8d393a22e4476ff8212de13fe1939de2a236f0a7"
+        );
+    }
+
+    #[test]
+    fn it_signs_with_external_nonces() {
+        let backend = Secp256k1::static_ref();
+        let key = Privkey {
+            key: crate::curve::Privkey::from_privkey_array([0x11u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let msg = [0x22u8; 32];
+        let digest: Hash256Digest = msg.into();
+
+        let mut n_minus_one = CURVE_ORDER;
+        n_minus_one[31] -= 1;
+        let mut rfc6979 = [0u8; 32];
+        rfc6979.copy_from_slice(
+            &hex::decode("6931e1828ba0afba580ed7c833bfe082c84f1331afa33a6b98ad8c493cc5edd0")
+                .unwrap(),
+        );
+        let cases = [
+            ([0x33u8; 32], "304402203c72addb4fdf09af94f0c94d7fe92a386a7e70cf8a1d85916386bb2535c7b1b10220412f1b61900afcc57905123b80079c9770102407b70e5e38c96e8bcd337a2fda"),
+            (n_minus_one, "3044022079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802201dc730cbff8dbe8e0d030038815ff3dd75f015a7d887826e7b6d2497f06e7aa4"),
+            (rfc6979, "3045022100cfd18ee918d6729134adbc61212142cf71fcf186dfc3123cfca8f7062e0fad5a0220703cc467d9857349ddb6e148bd1663f5050f3f6b9d788d64349c357f14eb4a5f"),
+        ];
+        let pubkey = key.derive_verifying_key().unwrap();
+        for (nonce, expected) in cases.iter() {
+            let sig = key.sign_ecdsa_with_nonce(&msg, nonce).unwrap();
+            assert_eq!(hex::encode(sig.to_der()), *expected);
+            pubkey.verify_digest(digest, &sig).unwrap();
+        }
+
+        // the RFC6979 nonce reproduces the default signer
+        assert_eq!(
+            key.sign_ecdsa_with_nonce(&msg, &rfc6979).unwrap(),
+            key.sign_digest(digest).unwrap()
+        );
+
+        for nonce in [[0u8; 32], CURVE_ORDER].iter() {
+            match key.sign_ecdsa_with_nonce(&msg, nonce) {
+                Err(Bip32Error::InvalidKey) => {}
+                _ => assert!(false, "expected InvalidKey"),
+            }
+        }
+    }
+}
@@ -109,6 +109,14 @@ pub mod path;
 /// Provides keys that are coupled with their derivation path
 pub mod derived;
 
+/// BIP352 silent payment output derivation
+#[cfg(feature = "silent-payments")]
+pub mod silent_payments;
+
+/// SLIP-0010 Ed25519 private key derivation
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+
 #[doc(hidden)]
 #[cfg(any(feature = "mainnet", feature = "testnet"))]
 pub mod defaults;
@@ -166,9 +174,15 @@ pub enum Bip32Error {
     #[error("HMAC left segment was 0 or greated than the curve order. How?")]
     InvalidKey,
 
-    /// pted to derive the hardened child of an xpub
-    #[error("Attempted to derive the hardened child of an xpub")]
-    HardenedDerivationFailed,
+    /// Attempted to derive the hardened child of an xpub. Public derivation of hardened
+    /// children is impossible, so this is checked before any curve operation
+    #[error("Attempted to derive hardened child {index} of an xpub at path position {position}")]
+    HardenedDerivationFailed {
+        /// The position of the hardened index in the derivation path. 0 for a single child
+        position: usize,
+        /// The hardened child index
+        index: u32,
+    },
 
     /// Attempted to tweak an xpriv or xpub directly
     #[error("Attempted to tweak an xpriv or xpub directly")]
@@ -182,6 +196,20 @@ pub enum Bip32Error {
     #[error("Version bytes 0x{0:x?} don't match any network xpub version bytes")]
     BadXPubVersionBytes([u8; 4]),
 
+    /// A decoded key's depth is inconsistent with its parent and index. Master keys must have a
+    /// zero parent fingerprint and index, and child keys a nonzero parent fingerprint
+    #[error("Inconsistent depth, parent fingerprint, and index in extended key")]
+    InvalidMasterKeyMetadata,
+
+    /// The network has no version bytes for this hint
+    #[error("Network has no version bytes for hint {0:?}")]
+    UnsupportedHint(primitives::Hint),
+
+    /// An xpub contains an uncompressed pubkey. BIP32 requires compressed keys, and the 78-byte
+    /// serialization has no room for a 65-byte key
+    #[error("Extended keys with uncompressed pubkeys are not supported")]
+    UncompressedKeyUnsupported,
+
     /// No backed in xtended key
     #[error("Attempted to operate on an extended key without supplying a backend")]
     NoBackend,
@@ -209,6 +237,27 @@ pub enum Bip32Error {
     /// Attempted to deserialize a very long path
     #[error("Invalid Bip32 Path.")]
     InvalidBip32Path,
+
+    /// Attempted to derive a silent payment output without any input pubkeys
+    #[error("No input pubkeys provided")]
+    NoInputPubkeys,
+
+    /// Attempted a non-hardened derivation on a curve that only supports hardened derivation
+    #[error("Non-hardened derivation at index {0} is unsupported for this curve")]
+    NonHardenedDerivation(u32),
+
+    /// Derivation would produce a key deeper than 255, the most a BIP32 depth byte can encode
+    #[error("Derivation would exceed the maximum depth of 255")]
+    MaxDepthExceeded,
+
+    /// A key origin's fingerprint does not match the root key
+    #[error("Key origin fingerprint {got:?} does not match root fingerprint {expected:?}")]
+    FingerprintMismatch {
+        /// The fingerprint of the root key
+        expected: primitives::KeyFingerprint,
+        /// The fingerprint in the key origin
+        got: primitives::KeyFingerprint,
+    },
 }
 
 impl From<std::convert::Infallible> for Bip32Error {
@@ -7,7 +7,11 @@ macro_rules! params {
             bip84: $bip84:expr,
             bip32_pub: $bip32pub:expr,
             bip49_pub: $bip49pub:expr,
-            bip84_pub: $bip84pub:expr
+            bip84_pub: $bip84pub:expr,
+            bip49_multisig: $bip49ms:expr,
+            bip84_multisig: $bip84ms:expr,
+            bip49_multisig_pub: $bip49mspub:expr,
+            bip84_multisig_pub: $bip84mspub:expr
         }
     ) => {
         $(#[$outer])*
@@ -21,6 +25,10 @@ macro_rules! params {
             const PUB_VERSION: u32 = $bip32pub;
             const BIP49_PUB_VERSION: u32 = $bip49pub;
             const BIP84_PUB_VERSION: u32 = $bip84pub;
+            const BIP49_MULTISIG_PRIV_VERSION: u32 = $bip49ms;
+            const BIP84_MULTISIG_PRIV_VERSION: u32 = $bip84ms;
+            const BIP49_MULTISIG_PUB_VERSION: u32 = $bip49mspub;
+            const BIP84_MULTISIG_PUB_VERSION: u32 = $bip84mspub;
         }
     }
 }
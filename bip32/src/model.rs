@@ -4,9 +4,10 @@ use coins_core::hashes::{Hash160Digest, Hash256, Hash256Digest, MarkedDigest};
 
 use crate::{
     curve::model::{PointSerialize, RecoverableSigSerialize, ScalarSerialize, Secp256k1Backend},
+    enc::Network,
     path::{DerivationPath, KeyDerivation},
     primitives::{ChainCode, Hint, KeyFingerprint, XKeyInfo},
-    Bip32Error,
+    Bip32Error, BIP32_HARDEN,
 };
 
 /// Any type that has access to a Secp256k1 backend.
@@ -40,6 +41,11 @@ pub trait HasPubkey<'a, T: Secp256k1Backend> {
         self.pubkey().pubkey_array()
     }
 
+    /// Return the associated public key in its uncompressed representation
+    fn pubkey_bytes_uncompressed(&self) -> [u8; 65] {
+        self.pubkey().pubkey_array_uncompressed()
+    }
+
     /// Calculate the key fingerprint of the associated public key. This is the first 4 bytes of
     /// the Bitcoin HASH_160 of the compressed representation of the public key.
     fn fingerprint(&self) -> KeyFingerprint {
@@ -215,7 +221,9 @@ pub trait HasXKeyInfo {
 pub trait XKey: std::marker::Sized + Clone {
     /// Get the key's depth
     fn depth(&self) -> u8;
-    /// Get the key's parent
+    /// Get the fingerprint of the key's parent. This is all zeros for a master key. The master
+    /// key's fingerprint can't be recovered from a derived key. Use a `KeyDerivation` to carry it
+    /// alongside the key.
     fn parent(&self) -> KeyFingerprint;
     /// Get the key's index
     fn index(&self) -> u32;
@@ -223,6 +231,8 @@ pub trait XKey: std::marker::Sized + Clone {
     fn chain_code(&self) -> ChainCode;
     /// Get the key's hint
     fn hint(&self) -> Hint;
+    /// Get the network the key was parsed from, if it is not the default network
+    fn network(&self) -> Option<Network>;
 }
 
 impl<T: HasXKeyInfo + std::marker::Sized + Clone> XKey for T {
@@ -241,6 +251,9 @@ impl<T: HasXKeyInfo + std::marker::Sized + Clone> XKey for T {
     fn hint(&self) -> Hint {
         self.xkey_info().hint
     }
+    fn network(&self) -> Option<Network> {
+        self.xkey_info().network
+    }
 }
 
 /// A trait for extended keys which can derive private children
@@ -252,7 +265,9 @@ pub trait DerivePrivateChild<'a, T: Secp256k1Backend>: XKey + HasPrivkey<'a, T>
     fn derive_private_child(&self, index: u32) -> Result<Self, Bip32Error>;
 
     /// Derive a series of child indices. Allows traversing several levels of the tree at once.
-    /// Accepts an iterator producing u32, or a string.
+    /// Accepts an iterator producing u32, or a string. Errors with
+    /// `Bip32Error::MaxDepthExceeded` before deriving anything if the result would be deeper
+    /// than 255.
     fn derive_private_path<E, P>(&self, p: P) -> Result<Self, Bip32Error>
     where
         E: Into<Bip32Error>,
@@ -264,6 +279,10 @@ pub trait DerivePrivateChild<'a, T: Secp256k1Backend>: XKey + HasPrivkey<'a, T>
             return Ok(self.to_owned());
         }
 
+        if self.depth() as usize + path.len() > 255 {
+            return Err(Bip32Error::MaxDepthExceeded);
+        }
+
         let mut current = self.to_owned();
         for index in path.iter() {
             current = current.derive_private_child(*index)?;
@@ -277,11 +296,15 @@ pub trait DerivePrivateChild<'a, T: Secp256k1Backend>: XKey + HasPrivkey<'a, T>
 /// This is generically implemented for any type that implements `VerifyingKey` and
 /// `DerivePublicChild`
 pub trait DerivePublicChild<'a, T: Secp256k1Backend>: XKey + HasPubkey<'a, T> {
-    /// Derive a child pubkey
+    /// Derive a child pubkey. Errors with `Bip32Error::HardenedDerivationFailed` if `index` is
+    /// hardened
     fn derive_public_child(&self, index: u32) -> Result<Self, Bip32Error>;
 
     /// Derive a series of child indices. Allows traversing several levels of the tree at once.
-    /// Accepts an iterator producing u32, or a string.
+    /// Accepts an iterator producing u32, or a string. Errors with
+    /// `Bip32Error::HardenedDerivationFailed` with the position of the first hardened index, or
+    /// `Bip32Error::MaxDepthExceeded` if the result would be deeper than 255, before deriving
+    /// anything.
     fn derive_public_path<E, P>(&self, p: P) -> Result<Self, Bip32Error>
     where
         E: Into<Bip32Error>,
@@ -293,8 +316,16 @@ pub trait DerivePublicChild<'a, T: Secp256k1Backend>: XKey + HasPubkey<'a, T> {
             return Ok(self.to_owned());
         }
 
-        if let (_, Some(_)) = path.last_hardened() {
-            return Err(Bip32Error::HardenedDerivationFailed);
+        if self.depth() as usize + path.len() > 255 {
+            return Err(Bip32Error::MaxDepthExceeded);
+        }
+
+        if let Some((position, index)) = path.iter().enumerate().find(|(_, i)| **i >= BIP32_HARDEN)
+        {
+            return Err(Bip32Error::HardenedDerivationFailed {
+                position,
+                index: *index,
+            });
         }
 
         let mut current = self.to_owned();
@@ -11,23 +11,29 @@ use crate::{primitives::KeyFingerprint, Bip32Error, BIP32_HARDEN};
 
 fn try_parse_index(s: &str) -> Result<u32, Bip32Error> {
     let mut index_str = s.to_owned();
-    let harden = if s.ends_with('\'') || s.ends_with('h') {
+    let harden = if s.ends_with(|c| c == '\'' || c == 'h' || c == 'H') {
         index_str.pop();
         true
     } else {
         false
     };
 
+    // `u32::from_str` accepts a leading `+`
     index_str
         .parse::<u32>()
+        .ok()
+        .filter(|_| index_str.bytes().all(|b| b.is_ascii_digit()))
+        .filter(|v| *v < BIP32_HARDEN)
         .map(|v| if harden { v + BIP32_HARDEN } else { v })
-        .map_err(|_| Bip32Error::MalformattedDerivation(s.to_owned()))
+        .ok_or_else(|| Bip32Error::MalformattedDerivation(s.to_owned()))
 }
 
 fn try_parse_path(path: &str) -> Result<Vec<u32>, Bip32Error> {
-    path.to_owned()
-        .split('/')
-        .filter(|v| v != &"m")
+    let mut components = path.split('/').peekable();
+    if components.peek() == Some(&"m") {
+        components.next();
+    }
+    components
         .map(try_parse_index)
         .collect::<Result<Vec<u32>, Bip32Error>>()
         .map_err(|_| Bip32Error::MalformattedDerivation(path.to_owned()))
@@ -41,10 +47,35 @@ fn encode_index(idx: u32, harden: char) -> String {
     s
 }
 
+/// The suffix marking a hardened index in a derivation string
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HardenedMarker {
+    /// `'`, as in `m/44'/0'/0'`
+    Apostrophe,
+    /// `h`, as in `m/44h/0h/0h`. Useful where quotes are awkward, e.g. in shells
+    H,
+}
+
+impl HardenedMarker {
+    fn as_char(self) -> char {
+        match self {
+            HardenedMarker::Apostrophe => '\'',
+            HardenedMarker::H => 'h',
+        }
+    }
+}
+
+impl Default for HardenedMarker {
+    fn default() -> Self {
+        HardenedMarker::Apostrophe
+    }
+}
+
 /// A Bip32 derivation path
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
 pub struct DerivationPath(Vec<u32>);
 
+#[cfg(feature = "serde")]
 impl serde::Serialize for DerivationPath {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -54,6 +85,7 @@ impl serde::Serialize for DerivationPath {
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> serde::Deserialize<'de> for DerivationPath {
     fn deserialize<D>(deserializer: D) -> Result<DerivationPath, D::Error>
     where
@@ -84,6 +116,22 @@ impl DerivationPath {
         self.custom_string("m", '/', '\'')
     }
 
+    /// Converts the path to a bip32 string with the chosen hardened marker. e.g `"m/44h/0h/0/32"`.
+    pub fn to_string_with(&self, marker: HardenedMarker) -> String {
+        self.custom_string("m", '/', marker.as_char())
+    }
+
+    /// Converts the path to a bip32 string without the leading `m`, for paths relative to some
+    /// key other than the root. e.g `"0/32"`. The empty path produces an empty string, which
+    /// does not parse.
+    pub fn relative_string_with(&self, marker: HardenedMarker) -> String {
+        self.0
+            .iter()
+            .map(|s| encode_index(*s, marker.as_char()))
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
     /// Returns `True` if there are no indices in the path
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
@@ -143,6 +191,20 @@ impl DerivationPath {
     }
 }
 
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.derivation_string())
+    }
+}
+
+impl std::ops::Deref for DerivationPath {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        &self.0
+    }
+}
+
 impl From<&DerivationPath> for DerivationPath {
     fn from(v: &DerivationPath) -> Self {
         v.clone()
@@ -185,7 +247,8 @@ impl FromStr for DerivationPath {
 }
 
 /// A Derivation Path for a bip32 key
-#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyDerivation {
     /// The root key fingerprint
     pub root: KeyFingerprint,
@@ -236,34 +299,28 @@ impl ByteFormat for KeyDerivation {
         4 + 4 * self.path.len()
     }
 
-    fn read_from<T>(_reader: &mut T) -> Result<Self, Self::Error>
+    /// Read the PSBT key origin format: a 4-byte root fingerprint followed by each path
+    /// component as a little-endian u32. The path runs to the end of the reader, so callers
+    /// must bound it, e.g. to a PSBT value. Errors with `Bip32Error::InvalidBip32Path` if the
+    /// path is not a whole number of components, or is longer than 255.
+    fn read_from<T>(reader: &mut T) -> Result<Self, Self::Error>
     where
         T: Read,
         Self: std::marker::Sized,
     {
-        unimplemented!()
-        // if limit == 0 {
-        //     return Err(SerError::RequiresLimit.into());
-        // }
-
-        // if limit > 255 {
-        //     return Err(Bip32Error::InvalidBip32Path);
-        // }
+        let root = KeyFingerprint::read_from(reader)?;
 
-        // let mut finger = [0u8; 4];
-        // reader.read_exact(&mut finger)?;
-
-        // let mut path = vec![];
-        // for _ in 0..limit {
-        //     let mut buf = [0u8; 4];
-        //     reader.read_exact(&mut buf)?;
-        //     path.push(u32::from_le_bytes(buf));
-        // }
+        let mut rest = vec![];
+        reader.read_to_end(&mut rest)?;
+        if rest.len() % 4 != 0 || rest.len() / 4 > 255 {
+            return Err(Bip32Error::InvalidBip32Path);
+        }
 
-        // Ok(KeyDerivation {
-        //     root: finger.into(),
-        //     path: path.into(),
-        // })
+        let path = rest
+            .chunks(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        Ok(KeyDerivation { root, path })
     }
 
     fn write_to<T>(&self, writer: &mut T) -> Result<usize, Self::Error>
@@ -284,7 +341,14 @@ pub mod test {
 
     #[test]
     fn it_parses_index_strings() {
-        let cases = [("32", 32), ("32h", 32 + BIP32_HARDEN), ("0h", BIP32_HARDEN)];
+        let cases = [
+            ("32", 32),
+            ("32h", 32 + BIP32_HARDEN),
+            ("0h", BIP32_HARDEN),
+            ("2147483647'", u32::max_value()),
+            ("32'", 32 + BIP32_HARDEN),
+            ("32H", 32 + BIP32_HARDEN),
+        ];
         for case in cases.iter() {
             match try_parse_index(&case.0) {
                 Ok(v) => assert_eq!(v, case.1),
@@ -295,7 +359,21 @@ pub mod test {
 
     #[test]
     fn it_handles_malformatted_indices() {
-        let cases = ["-", "h", "toast", "憂鬱"];
+        let cases = [
+            "-",
+            "h",
+            "H",
+            "'",
+            "toast",
+            "憂鬱",
+            "",
+            "2147483648",
+            "2147483648'",
+            "2147483648H",
+            "-1h",
+            "+1h",
+            "1hh",
+        ];
         for case in cases.iter() {
             match try_parse_index(&case) {
                 Ok(_) => assert!(false, "expected an error"),
@@ -314,6 +392,10 @@ pub mod test {
             ("32", vec![32]),
             ("32'", vec![32 + BIP32_HARDEN]),
             ("0'/32/5/5/5", vec![BIP32_HARDEN, 32, 5, 5, 5]),
+            (
+                "m/84h/0'/0H",
+                vec![84 + BIP32_HARDEN, BIP32_HARDEN, BIP32_HARDEN],
+            ),
         ];
         for case in cases.iter() {
             match try_parse_path(&case.0) {
@@ -325,7 +407,18 @@ pub mod test {
 
     #[test]
     fn it_handles_malformatted_derivations() {
-        let cases = ["//", "m/", "-", "h", "toast", "憂鬱"];
+        let cases = [
+            "//",
+            "m/",
+            "-",
+            "h",
+            "toast",
+            "憂鬱",
+            "",
+            "m/0'/m/1",
+            "m/0'\\1",
+            "m/4294967295",
+        ];
         for case in cases.iter() {
             match try_parse_path(&case) {
                 Ok(_) => assert!(false, "expected an error"),
@@ -335,6 +428,36 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn it_displays_paths_with_either_marker() {
+        let path: DerivationPath = "m/44'/0'/0'/0/5".parse().unwrap();
+        assert_eq!(path.to_string(), "m/44'/0'/0'/0/5");
+        assert_eq!(path.to_string_with(HardenedMarker::H), "m/44h/0h/0h/0/5");
+        assert_eq!(
+            path.relative_string_with(HardenedMarker::Apostrophe),
+            "44'/0'/0'/0/5"
+        );
+        for marker in [HardenedMarker::Apostrophe, HardenedMarker::H].iter() {
+            let full = path.to_string_with(*marker);
+            assert_eq!(full.parse::<DerivationPath>().unwrap(), path);
+            let relative = path.relative_string_with(*marker);
+            assert_eq!(relative.parse::<DerivationPath>().unwrap(), path);
+        }
+        assert_eq!(DerivationPath::default().to_string(), "m");
+    }
+
+    #[test]
+    fn it_derefs_to_a_slice() {
+        let path: DerivationPath = "m/44'/0'/0'/0/5".parse().unwrap();
+        let indices: &[u32] = &path;
+        assert_eq!(
+            indices,
+            &[44 + BIP32_HARDEN, BIP32_HARDEN, BIP32_HARDEN, 0, 5][..]
+        );
+        assert_eq!(path.first(), Some(&(44 + BIP32_HARDEN)));
+        assert!("m".parse::<DerivationPath>().unwrap().is_empty());
+    }
+
     #[test]
     fn it_removes_prefixes_from_derivations() {
         // express each row in a separate instantiation syntax :)
@@ -375,6 +498,25 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn it_roundtrips_key_origin_bytes() {
+        let derivation = KeyDerivation {
+            root: KeyFingerprint([0xd3, 0x4d, 0xb3, 0x3f]),
+            path: "m/84'/0'/0'/1/7".parse().unwrap(),
+        };
+        let hex = derivation.serialize_hex();
+        assert_eq!(hex, "d34db33f5400008000000080000000800100000007000000");
+        assert_eq!(KeyDerivation::deserialize_hex(&hex).unwrap(), derivation);
+
+        let root_only = KeyDerivation::deserialize_hex("d34db33f").unwrap();
+        assert!(root_only.path.is_empty());
+
+        match KeyDerivation::deserialize_hex("d34db33f540000") {
+            Err(Bip32Error::InvalidBip32Path) => {}
+            _ => assert!(false, "expected InvalidBip32Path"),
+        }
+    }
+
     #[test]
     fn it_stringifies_derivation_paths() {
         let cases = [
@@ -1,8 +1,12 @@
-use crate::Bip32Error;
+use crate::{
+    enc::{Network, NetworkParams, VersionBytes},
+    Bip32Error,
+};
 use coins_core::ser::ByteFormat;
 use std::io::{Read, Write};
 
-/// We treat the bip32 xpub bip49 ypub and bip84 zpub convention as a hint regarding address type.
+/// We treat the bip32 xpub bip49 ypub and bip84 zpub convention, and the SLIP-0132 Ypub and Zpub
+/// multisig extensions, as a hint regarding address type.
 /// Downstream crates are free to follow or ignore these hints when generating addresses from
 /// extended keys.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -13,10 +17,43 @@ pub enum Hint {
     Compatibility,
     /// Bip32 + Bip84 hint for Native SegWit
     SegWit,
+    /// SLIP-0132 hint for multisig Witness-via-P2SH (P2WSH-in-P2SH)
+    CompatibilityMultisig,
+    /// SLIP-0132 hint for multisig Native SegWit (P2WSH)
+    SegWitMultisig,
+}
+
+impl Hint {
+    /// The multisig variant of this hint if `multisig` is true, otherwise the single-key
+    /// variant. `Legacy` has no multisig variant, and is returned unchanged.
+    pub fn with_multisig(self, multisig: bool) -> Self {
+        match (self, multisig) {
+            (Hint::Compatibility, true) | (Hint::CompatibilityMultisig, true) => {
+                Hint::CompatibilityMultisig
+            }
+            (Hint::SegWit, true) | (Hint::SegWitMultisig, true) => Hint::SegWitMultisig,
+            (Hint::CompatibilityMultisig, false) => Hint::Compatibility,
+            (Hint::SegWitMultisig, false) => Hint::SegWit,
+            (hint, _) => hint,
+        }
+    }
+
+    /// True for the SLIP-0132 multisig hints
+    pub fn is_multisig(self) -> bool {
+        matches!(self, Hint::CompatibilityMultisig | Hint::SegWitMultisig)
+    }
+
+    /// The SLIP-0132 xpub version bytes for this hint on network `P`, e.g. `zpub` for `SegWit`,
+    /// or `Zpub` for `SegWit` with `multisig`. Returns `NO_VERSION` if the network doesn't
+    /// define version bytes for the hint.
+    pub fn slip132_pub_version<P: NetworkParams>(self, multisig: bool) -> u32 {
+        VersionBytes::of::<P>().slip132_pub_version(self, multisig)
+    }
 }
 
 /// A 4-byte key fingerprint
-#[derive(Eq, PartialEq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyFingerprint(pub [u8; 4]);
 
 impl From<[u8; 4]> for KeyFingerprint {
@@ -87,6 +124,10 @@ pub struct XKeyInfo {
     pub chain_code: ChainCode,
     /// The key's stanadard output type preference
     pub hint: Hint,
+    /// The network the key was parsed from, if it is not the default `Encoder`'s network. The
+    /// key is then displayed and serialized with this network's version bytes. `None` for
+    /// other keys
+    pub network: Option<Network>,
 }
 
 #[cfg(test)]
@@ -5,6 +5,7 @@ use crate::{
     curve::model::{ScalarDeserialize, Secp256k1Backend},
     keys::{GenericPrivkey, GenericPubkey},
     model::*,
+    path::{DerivationPath, KeyDerivation},
     primitives::{ChainCode, Hint, KeyFingerprint, XKeyInfo},
     Bip32Error, BIP32_HARDEN, CURVE_ORDER,
 };
@@ -37,6 +38,17 @@ impl XPriv {
     pub fn root_from_seed(data: &[u8], hint: Option<Hint>) -> Result<XPriv, Bip32Error> {
         Self::custom_root_from_seed(data, hint, crate::curve::Secp256k1::static_ref())
     }
+
+    /// Generate a master node from some seed data, using `domain` as the hmac key instead of
+    /// `"Bitcoin seed"`. E.g. SLIP-0010 uses a different key per curve. This is `master_node`
+    /// with the seed first.
+    pub fn root_from_seed_with_domain(
+        data: &[u8],
+        domain: &[u8],
+        hint: Option<Hint>,
+    ) -> Result<XPriv, Bip32Error> {
+        Self::master_node(domain, data, hint)
+    }
 }
 
 /// A BIP32 Extended pubkey using the library's compiled-in secp256k1 backend. This defaults to
@@ -49,7 +61,7 @@ pub type XPub = GenericXPub<'static, crate::curve::Secp256k1<'static>>;
 /// Default BIP32
 pub const SEED: &[u8; 12] = b"Bitcoin seed";
 
-fn hmac_and_split(seed: &[u8], data: &[u8]) -> ([u8; 32], ChainCode) {
+pub(crate) fn hmac_and_split(seed: &[u8], data: &[u8]) -> ([u8; 32], ChainCode) {
     let mut mac = HmacSha512::new_varkey(seed).expect("key length is ok");
     mac.input(data);
     let result = mac.result().code();
@@ -100,6 +112,7 @@ impl<'a, T: Secp256k1Backend> GenericXPriv<'a, T> {
                 index: 0,
                 chain_code,
                 hint: hint.unwrap_or(Hint::SegWit),
+                network: None,
             },
             privkey: GenericPrivkey {
                 key: privkey,
@@ -129,6 +142,113 @@ impl<'a, T: Secp256k1Backend> GenericXPriv<'a, T> {
             pubkey: self.privkey.derive_verifying_key()?,
         })
     }
+
+    /// Derive the child at `index`, one level below this key. The child's depth, parent
+    /// fingerprint and chain code are set accordingly. Indices at or above `BIP32_HARDEN` are
+    /// hardened.
+    pub fn derive_child(&self, index: u32) -> Result<GenericXPriv<'a, T>, Bip32Error> {
+        self.derive_private_child(index)
+    }
+
+    /// The fingerprint of this key: the first 4 bytes of the hash160 of its compressed pubkey.
+    /// Children record it as their `parent()`. Unlike `GenericXPub::fingerprint`, this derives
+    /// the pubkey, so it fails if the key has no backend.
+    pub fn fingerprint(&self) -> Result<KeyFingerprint, Bip32Error> {
+        self.derive_fingerprint()
+    }
+
+    /// Produce the watch-only counterpart of this key. The xpub has the same `XKeyInfo`,
+    /// including its `Hint`, and shares this key's backend. This is an alias for `to_xpub`.
+    pub fn neuter(&self) -> Result<GenericXPub<'a, T>, Bip32Error> {
+        self.to_xpub()
+    }
+
+    /// Derive the xpub at `path`, treating this key as the root. Returns the root fingerprint,
+    /// the path, and the derived xpub. This is the key origin information used by output
+    /// descriptors, e.g. `[d34db33f/84'/0'/0']xpub...`.
+    pub fn origin_for_path(
+        &self,
+        path: &[u32],
+    ) -> Result<(KeyFingerprint, DerivationPath, GenericXPub<'a, T>), Bip32Error> {
+        let root = self.derive_fingerprint()?;
+        let xpub = self.derive_private_path(path)?.to_xpub()?;
+        Ok((root, path.into(), xpub))
+    }
+
+    /// Derive the hardened account key `m/purpose'/coin_type'/account'`, treating this key as
+    /// the root, and neuter it for export. Returns the account xpub with its key origin. This is
+    /// the export used to set up a watch-only wallet.
+    ///
+    /// The indices are given unhardened. Errors with `Bip32Error::InvalidBip32Path` if any index
+    /// is already hardened.
+    pub fn export_account(
+        &self,
+        purpose: u32,
+        coin_type: u32,
+        account: u32,
+    ) -> Result<(KeyDerivation, GenericXPub<'a, T>), Bip32Error> {
+        let indices = [purpose, coin_type, account];
+        if indices.iter().any(|i| *i >= BIP32_HARDEN) {
+            return Err(Bip32Error::InvalidBip32Path);
+        }
+        let path: Vec<u32> = indices.iter().map(|i| i + BIP32_HARDEN).collect();
+        let (root, path, xpub) = self.origin_for_path(&path)?;
+        Ok((KeyDerivation { root, path }, xpub))
+    }
+
+    /// Derive the key described by a key origin string, e.g. `[d34db33f/84'/0'/0']`, treating
+    /// this key as the root. This is the inverse of `origin_for_path`. The brackets are optional.
+    ///
+    /// ## Errors
+    ///
+    /// - `Bip32Error::FingerprintMismatch` if the origin fingerprint is not this key's
+    /// - `Bip32Error::MalformattedDerivation` if the origin can't be parsed
+    pub fn derive_from_origin_str(&self, origin: &str) -> Result<GenericXPriv<'a, T>, Bip32Error> {
+        let malformatted = || Bip32Error::MalformattedDerivation(origin.to_owned());
+        let trimmed = origin.trim_start_matches('[').trim_end_matches(']');
+        let mut parts = trimmed.splitn(2, '/');
+
+        let fingerprint = parts.next().unwrap_or_default();
+        if fingerprint.len() != 8 {
+            return Err(malformatted());
+        }
+        let got = u32::from_str_radix(fingerprint, 16)
+            .map(|f| KeyFingerprint(f.to_be_bytes()))
+            .map_err(|_| malformatted())?;
+        let expected = self.derive_fingerprint()?;
+        if got != expected {
+            return Err(Bip32Error::FingerprintMismatch { expected, got });
+        }
+
+        match parts.next() {
+            Some(path) => self.derive_private_path(&path.parse::<DerivationPath>()?),
+            None => Ok(self.clone()),
+        }
+    }
+
+    /// Parse a derivation path string, e.g. `m/44'/0'/0'/0/0`, and derive the key it describes,
+    /// treating this key as the root.
+    ///
+    /// ## Errors
+    ///
+    /// - `Bip32Error::MalformattedDerivation` if the path can't be parsed
+    /// - Any error encountered during derivation, e.g. `Bip32Error::InvalidKey`
+    ///
+    /// ```
+    /// use coins_bip32::{Bip32Error, XKey, XPriv};
+    ///
+    /// # fn main() -> Result<(), Bip32Error> {
+    /// let seed = [0x0fu8; 32];
+    /// let root = XPriv::root_from_seed(&seed, None)?;
+    /// let child = root.derive_path("m/44'/0'/0'/0/0")?;
+    /// assert_eq!(child.depth(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn derive_path(&self, path: &str) -> Result<GenericXPriv<'a, T>, Bip32Error> {
+        let path: DerivationPath = path.parse()?;
+        self.derive_private_path(&path)
+    }
 }
 
 impl<'a, T: Secp256k1Backend> HasXKeyInfo for GenericXPriv<'a, T> {
@@ -149,6 +269,10 @@ impl<'a, T: Secp256k1Backend> SigningKey<'a, T> for GenericXPriv<'a, T> {
 
 impl<'a, T: Secp256k1Backend> DerivePrivateChild<'a, T> for GenericXPriv<'a, T> {
     fn derive_private_child(&self, index: u32) -> Result<GenericXPriv<'a, T>, Bip32Error> {
+        let depth = self
+            .depth()
+            .checked_add(1)
+            .ok_or(Bip32Error::MaxDepthExceeded)?;
         let hardened = index >= BIP32_HARDEN;
 
         let mut data: Vec<u8> = vec![];
@@ -173,11 +297,12 @@ impl<'a, T: Secp256k1Backend> DerivePrivateChild<'a, T> for GenericXPriv<'a, T>
 
         Ok(GenericXPriv {
             info: XKeyInfo {
-                depth: self.depth() + 1,
+                depth,
                 parent: self.derive_fingerprint()?,
                 index,
                 chain_code,
                 hint: self.hint(),
+                network: self.network(),
             },
             privkey,
         })
@@ -201,6 +326,32 @@ impl<'a, T: Secp256k1Backend> GenericXPub<'a, T> {
     pub fn from_xpriv(xpriv: &GenericXPriv<'a, T>) -> Result<GenericXPub<'a, T>, Bip32Error> {
         xpriv.to_xpub()
     }
+
+    /// Derive the child at `index`, one level below this key. The child's depth, parent
+    /// fingerprint and chain code are set accordingly. Errors with
+    /// `Bip32Error::HardenedDerivationFailed` if `index` is hardened.
+    pub fn derive_child(&self, index: u32) -> Result<GenericXPub<'a, T>, Bip32Error> {
+        self.derive_public_child(index)
+    }
+
+    /// Derive the BIP44 receive (`0`) and change (`1`) branches of an account-level xpub.
+    /// Addresses are then derived from each branch by index.
+    pub fn branches(&self) -> Result<(GenericXPub<'a, T>, GenericXPub<'a, T>), Bip32Error> {
+        Ok((self.derive_public_child(0)?, self.derive_public_child(1)?))
+    }
+
+    /// Parse a derivation path string, e.g. `m/0/5`, and derive the key it describes, treating
+    /// this key as the root.
+    ///
+    /// ## Errors
+    ///
+    /// - `Bip32Error::MalformattedDerivation` if the path can't be parsed
+    /// - `Bip32Error::HardenedDerivationFailed` with the position of the first hardened index,
+    ///   if the path contains one
+    pub fn derive_path(&self, path: &str) -> Result<GenericXPub<'a, T>, Bip32Error> {
+        let path: DerivationPath = path.parse()?;
+        self.derive_public_path(&path)
+    }
 }
 
 impl<'a, T: Secp256k1Backend> HasXKeyInfo for GenericXPub<'a, T> {
@@ -216,8 +367,12 @@ impl<'a, T: Secp256k1Backend> VerifyingKey<'a, T> for GenericXPub<'a, T> {
 impl<'a, T: Secp256k1Backend> DerivePublicChild<'a, T> for GenericXPub<'a, T> {
     fn derive_public_child(&self, index: u32) -> Result<GenericXPub<'a, T>, Bip32Error> {
         if index >= BIP32_HARDEN {
-            return Err(Bip32Error::HardenedDerivationFailed);
+            return Err(Bip32Error::HardenedDerivationFailed { position: 0, index });
         }
+        let depth = self
+            .depth()
+            .checked_add(1)
+            .ok_or(Bip32Error::MaxDepthExceeded)?;
         let mut data: Vec<u8> = self.pubkey_bytes().to_vec();
         data.extend(&index.to_be_bytes());
 
@@ -238,11 +393,12 @@ impl<'a, T: Secp256k1Backend> DerivePublicChild<'a, T> for GenericXPub<'a, T> {
 
         Ok(Self {
             info: XKeyInfo {
-                depth: self.depth() + 1,
+                depth,
                 parent: self.fingerprint(),
                 index,
                 chain_code,
                 hint: self.hint(),
+                network: self.network(),
             },
             pubkey,
         })
@@ -258,7 +414,7 @@ mod test {
         keys::Pubkey,
         primitives::*,
     };
-    use coins_core::hashes::Hash256Digest;
+    use coins_core::hashes::{Hash256Digest, MarkedDigestOutput};
 
     use hex;
 
@@ -472,6 +628,277 @@ mod test {
         assert_eq!(&recovered.pubkey(), &child_xpub.pubkey());
     }
 
+    #[test]
+    fn it_propagates_hints_to_public_children() {
+        let seed: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        let hints = [Hint::Legacy, Hint::Compatibility, Hint::SegWit];
+        for hint in hints.iter() {
+            let xpub = XPriv::root_from_seed(&seed, Some(*hint))
+                .unwrap()
+                .to_xpub()
+                .unwrap();
+
+            let child = xpub.derive_public_child(3).unwrap();
+            assert_eq!(child.hint(), *hint);
+
+            let descendant = xpub.derive_public_path(&[0u32, 1, 2][..]).unwrap();
+            assert_eq!(descendant.hint(), *hint);
+            assert_eq!(descendant.depth(), 3);
+        }
+    }
+
+    #[test]
+    fn it_derives_account_branches() {
+        let backend = Secp256k1::static_ref();
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi".to_owned();
+        let xpriv = MainnetEncoder::xpriv_from_base58(&xpriv_str, Some(backend)).unwrap();
+        let account = xpriv
+            .derive_private_path(&[44 + BIP32_HARDEN, BIP32_HARDEN, BIP32_HARDEN][..])
+            .unwrap()
+            .to_xpub()
+            .unwrap();
+
+        let (receive, change) = account.branches().unwrap();
+        assert_eq!(receive, account.derive_public_child(0).unwrap());
+        assert_eq!(change, account.derive_public_child(1).unwrap());
+        assert_eq!(receive.index(), 0);
+        assert_eq!(change.index(), 1);
+        assert_eq!(change.parent(), account.fingerprint());
+    }
+
+    #[test]
+    fn it_generates_roots_with_custom_domains() {
+        let seed: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+        let standard = XPriv::root_from_seed_with_domain(&seed, SEED, None).unwrap();
+        assert_eq!(standard, XPriv::root_from_seed(&seed, None).unwrap());
+
+        // SLIP-0010 test vector 1 master key for nist256p1
+        let xpriv = XPriv::root_from_seed_with_domain(&seed, b"Nist256p1 seed", None).unwrap();
+        assert_eq!(
+            hex::encode(xpriv.privkey_bytes()),
+            "612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2"
+        );
+        assert_eq!(
+            hex::encode(xpriv.chain_code().0),
+            "beeb672fe4621673f722f38529c07392fecaa61015c80c34f29ce8b41b3cb6ea"
+        );
+    }
+
+    #[test]
+    fn it_produces_key_origins() {
+        let seed: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        let xpriv = XPriv::root_from_seed(&seed, Some(Hint::Legacy)).unwrap();
+        let path = [BIP32_HARDEN, 1, 2 + BIP32_HARDEN];
+
+        let (root, deriv, xpub) = xpriv.origin_for_path(&path).unwrap();
+        assert_eq!(root, xpriv.derive_fingerprint().unwrap());
+        assert_eq!(deriv.derivation_string(), "m/0'/1/2'");
+        assert_eq!(
+            MainnetEncoder::xpub_to_base58(&xpub).unwrap(),
+            "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"
+        );
+    }
+
+    #[test]
+    fn it_exports_accounts() {
+        let backend = Secp256k1::static_ref();
+        let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi".to_owned();
+        let xpriv = MainnetEncoder::xpriv_from_base58(&xpriv_str, Some(backend)).unwrap();
+        let expected = xpriv
+            .derive_private_path(&[44 + BIP32_HARDEN, BIP32_HARDEN, 3 + BIP32_HARDEN][..])
+            .unwrap()
+            .to_xpub()
+            .unwrap();
+
+        let (origin, xpub) = xpriv.export_account(44, 0, 3).unwrap();
+        assert_eq!(xpub, expected);
+        assert_eq!(origin.root, xpriv.derive_fingerprint().unwrap());
+        assert_eq!(origin.path.derivation_string(), "m/44'/0'/3'");
+
+        match xpriv.export_account(44 + BIP32_HARDEN, 0, 0) {
+            Err(Bip32Error::InvalidBip32Path) => {}
+            _ => assert!(false, "expected InvalidBip32Path"),
+        }
+    }
+
+    #[test]
+    fn it_derives_from_key_origin_strings() {
+        let seed: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        let xpriv = XPriv::root_from_seed(&seed, Some(Hint::Legacy)).unwrap();
+        let path = [BIP32_HARDEN, 1, 2 + BIP32_HARDEN];
+        let (root, deriv, xpub) = xpriv.origin_for_path(&path).unwrap();
+
+        let origin = format!(
+            "[{:08x}/{}]",
+            u32::from_be_bytes(root.0),
+            &deriv.derivation_string()[2..]
+        );
+        let derived = xpriv.derive_from_origin_str(&origin).unwrap();
+        assert_eq!(derived.to_xpub().unwrap(), xpub);
+        // brackets are optional, and an empty path is the root
+        let bare = &origin[1..origin.len() - 1];
+        assert_eq!(xpriv.derive_from_origin_str(bare).unwrap(), derived);
+        assert_eq!(
+            xpriv.derive_from_origin_str(&bare[..8]).unwrap(),
+            xpriv.clone()
+        );
+
+        match xpriv.derive_from_origin_str("[00000000/0'/1/2']") {
+            Err(Bip32Error::FingerprintMismatch { expected, got }) => {
+                assert_eq!(expected, root);
+                assert_eq!(got, KeyFingerprint([0; 4]));
+            }
+            _ => assert!(false, "expected FingerprintMismatch"),
+        }
+        match xpriv.derive_from_origin_str("[zzzzzzzz/0]") {
+            Err(Bip32Error::MalformattedDerivation(_)) => {}
+            _ => assert!(false, "expected MalformattedDerivation"),
+        }
+    }
+
+    #[test]
+    fn it_derives_paths_from_strings() {
+        let xpriv = XPriv::root_from_seed(&[0x0f; 32], None).unwrap();
+        let path = [44 + BIP32_HARDEN, BIP32_HARDEN, BIP32_HARDEN, 0, 0];
+        assert_eq!(
+            xpriv.derive_path("m/44'/0'/0'/0/0").unwrap(),
+            xpriv.derive_private_path(&path[..]).unwrap()
+        );
+        assert_eq!(xpriv.derive_path("m").unwrap(), xpriv);
+        match xpriv.derive_path("m/44'/x") {
+            Err(Bip32Error::MalformattedDerivation(_)) => {}
+            _ => assert!(false, "expected MalformattedDerivation"),
+        }
+    }
+
+    #[test]
+    fn it_computes_fingerprints() {
+        let xpriv = XPriv::root_from_seed(&[0x0f; 32], None).unwrap();
+        let xpub = xpriv.neuter().unwrap();
+        assert_eq!(xpriv.fingerprint().unwrap(), xpub.fingerprint());
+        assert_eq!(
+            xpriv.fingerprint().unwrap().0[..],
+            xpub.pubkey_hash160().as_slice()[..4]
+        );
+
+        let child = xpub.derive_child(3).unwrap();
+        assert_eq!(child.parent(), xpub.fingerprint());
+        assert_eq!(xpub.parent(), KeyFingerprint([0; 4]));
+    }
+
+    #[test]
+    fn it_neuters_xprivs() {
+        let xpriv = XPriv::root_from_seed(&[0x0f; 32], Some(Hint::SegWit))
+            .unwrap()
+            .derive_child(BIP32_HARDEN + 84)
+            .unwrap();
+        let xpub = xpriv.neuter().unwrap();
+        assert_eq!(xpub.info, xpriv.info);
+        assert_eq!(xpub.hint(), Hint::SegWit);
+        assert!(xpub.backend().is_ok());
+        assert_eq!(xpub.pubkey_bytes(), xpriv.derive_pubkey_bytes().unwrap());
+    }
+
+    #[test]
+    fn it_derives_single_children() {
+        let xpriv = XPriv::root_from_seed(&[0x0f; 32], None).unwrap();
+        let xpub = xpriv.to_xpub().unwrap();
+
+        let child = xpriv.derive_child(BIP32_HARDEN + 1).unwrap();
+        assert_eq!(child.depth(), 1);
+        assert_eq!(child.index(), BIP32_HARDEN + 1);
+        assert_eq!(child.parent(), xpriv.derive_fingerprint().unwrap());
+        assert_eq!(
+            child,
+            xpriv.derive_private_path(&[BIP32_HARDEN + 1][..]).unwrap()
+        );
+
+        let grandchild = child.derive_child(7).unwrap();
+        let public = child.to_xpub().unwrap().derive_child(7).unwrap();
+        assert_eq!(grandchild.depth(), 2);
+        assert_eq!(public, grandchild.to_xpub().unwrap());
+        assert_eq!(public.chain_code(), grandchild.chain_code());
+        assert_eq!(public.parent(), child.derive_fingerprint().unwrap());
+
+        match xpub.derive_child(BIP32_HARDEN) {
+            Err(Bip32Error::HardenedDerivationFailed {
+                position: 0,
+                index: BIP32_HARDEN,
+            }) => {}
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+    }
+
+    #[test]
+    fn it_refuses_to_exceed_max_depth() {
+        let xpriv = XPriv::root_from_seed(&[0x0f; 32], None).unwrap();
+        let xpub = xpriv.to_xpub().unwrap();
+
+        match xpriv.derive_private_path(&vec![0u32; 256][..]) {
+            Err(Bip32Error::MaxDepthExceeded) => {}
+            _ => assert!(false, "expected MaxDepthExceeded"),
+        }
+        match xpub.derive_public_path(&vec![0u32; 256][..]) {
+            Err(Bip32Error::MaxDepthExceeded) => {}
+            _ => assert!(false, "expected MaxDepthExceeded"),
+        }
+
+        let mut deep = xpriv;
+        deep.info.depth = 254;
+        let deepest = deep.derive_child(1).unwrap();
+        assert_eq!(deepest.depth(), 255);
+        match deepest.derive_child(1) {
+            Err(Bip32Error::MaxDepthExceeded) => {}
+            _ => assert!(false, "expected MaxDepthExceeded"),
+        }
+        match deepest.to_xpub().unwrap().derive_child(1) {
+            Err(Bip32Error::MaxDepthExceeded) => {}
+            _ => assert!(false, "expected MaxDepthExceeded"),
+        }
+        match deep.derive_private_path(&[1, 2][..]) {
+            Err(Bip32Error::MaxDepthExceeded) => {}
+            _ => assert!(false, "expected MaxDepthExceeded"),
+        }
+    }
+
+    #[test]
+    fn it_derives_public_paths_from_strings() {
+        let xpub = XPriv::root_from_seed(&[0x0f; 32], None)
+            .unwrap()
+            .to_xpub()
+            .unwrap();
+        assert_eq!(
+            xpub.derive_path("m/0/5").unwrap(),
+            xpub.derive_public_path(&[0, 5][..]).unwrap()
+        );
+        match xpub.derive_path("m/0/1'/2") {
+            Err(Bip32Error::HardenedDerivationFailed { position, index }) => {
+                assert_eq!(position, 1);
+                assert_eq!(index, 1 + BIP32_HARDEN);
+            }
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+        match xpub.derive_path("m/0'") {
+            Err(Bip32Error::HardenedDerivationFailed { position, index }) => {
+                assert_eq!(position, 0);
+                assert_eq!(index, BIP32_HARDEN);
+            }
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+        match xpub.derive_public_path(&[BIP32_HARDEN][..]) {
+            Err(Bip32Error::HardenedDerivationFailed { position, index }) => {
+                assert_eq!(position, 0);
+                assert_eq!(index, BIP32_HARDEN);
+            }
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+        match xpub.derive_path("m/0/x") {
+            Err(Bip32Error::MalformattedDerivation(_)) => {}
+            _ => assert!(false, "expected MalformattedDerivation"),
+        }
+    }
+
     #[test]
     fn it_can_read_keys_without_a_backend() {
         let xpriv_str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi".to_owned();
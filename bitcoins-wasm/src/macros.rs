@@ -358,6 +358,16 @@ macro_rules! impl_encoder {
                     .map_err(crate::types::errors::WasmError::from)
                     .map_err(JsValue::from)
             }
+
+            /// Encode each output of a transaction as an address string. Outputs with no
+            /// address, such as `OP_RETURN` outputs, are `null`.
+            pub fn output_addresses(tx: &crate::types::tx::BitcoinTx) -> js_sys::Array {
+                tx.inner()
+                    .output_addresses::<bitcoins::enc::$enc_name>()
+                    .into_iter()
+                    .map(|address| address.map(JsValue::from).unwrap_or(JsValue::NULL))
+                    .collect()
+            }
         }
     }
 }
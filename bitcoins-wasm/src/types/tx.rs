@@ -186,3 +186,22 @@ impl WitnessTx {
             .map_err(JsValue::from)
     }
 }
+
+#[wasm_bindgen]
+impl BitcoinTx {
+    /// Return the virtual size of the transaction in vbytes.
+    #[wasm_bindgen(method, getter)]
+    pub fn vsize(&self) -> usize {
+        self.0.vsize()
+    }
+
+    /// Calculate the fee rate of the transaction in sat/vbyte. `prevouts` must contain the
+    /// output spent by each input, in the same order as the vin.
+    #[wasm_bindgen]
+    pub fn fee_rate(&self, prevouts: Vout) -> Result<f64, JsValue> {
+        self.0
+            .fee_rate(&prevouts.inner())
+            .map_err(WasmError::from)
+            .map_err(JsValue::from)
+    }
+}
@@ -8,25 +8,57 @@
 //! The builder is best accessed via the preconstructed network objects in `nets.rs`.
 
 use std::marker::PhantomData;
+use thiserror::Error;
 
 use coins_core::{
     builder::TxBuilder,
-    enc::{AddressEncoder, EncodingResult},
+    enc::{AddressEncoder, EncodingError, EncodingResult},
+    ser::{self, SerError},
     types::tx::Transaction,
 };
 
 use crate::{
     enc::encoder::{Address, BitcoinEncoderMarker},
+    select::{
+        self, output_vsize, BranchAndBound, CoinSelector, LargestFirst, SegwitFirst, Selection,
+        SelectionError, SelectionTarget, SpendType, DUST_LIMIT, MAX_STANDARD_TX_WEIGHT,
+        TX_OVERHEAD_VSIZE,
+    },
     types::{
         legacy::LegacyTx,
-        script::{ScriptPubkey, ScriptSig, Witness},
-        tx::{BitcoinTransaction, BitcoinTx},
+        script::{ScriptPubkey, ScriptSig, ScriptType, Witness},
+        tx::{BitcoinTransaction, BitcoinTx, TxError, TxResult},
         txin::{BitcoinOutpoint, BitcoinTxIn},
         txout::TxOut,
+        utxo::UTXO,
         witness::{WitnessTransaction, WitnessTx},
     },
 };
 
+/// The highest sequence number that is not final. Inputs with this sequence enable the
+/// transaction's locktime without signaling BIP125 replaceability
+pub const MAX_NON_FINAL_SEQUENCE: u32 = 0xffff_fffe;
+
+/// Errors produced by `BitcoinTxBuilder::build_batched` and `BitcoinTxBuilder::fund_cheapest`
+#[derive(Debug, Error)]
+pub enum BatchError {
+    /// Bubbled up from transaction construction
+    #[error(transparent)]
+    TxError(#[from] TxError),
+
+    /// Bubbled up from address decoding
+    #[error(transparent)]
+    EncodingError(#[from] EncodingError),
+
+    /// Bubbled up from coin selection
+    #[error(transparent)]
+    SelectionError(#[from] SelectionError),
+
+    /// The payment at this index can't be funded by a transaction under the standard weight
+    #[error("Payment {0} can't be funded within the standard weight limit")]
+    PaymentTooLarge(usize),
+}
+
 /// This is a generic builder for Bitcoin transactions. It allows you to easily build legacy and
 /// witness transactions.
 ///
@@ -69,6 +101,105 @@ where
         }
     }
 
+    /// Set the sequence number of the input spending `outpoint`. Unlike index-based setters,
+    /// this is unaffected by input reordering.
+    ///
+    /// # Errors
+    ///
+    /// - `TxError::MissingInput` if no input spends `outpoint`
+    pub fn set_sequence_for(mut self, outpoint: &BitcoinOutpoint, sequence: u32) -> TxResult<Self> {
+        let input = self
+            .vin
+            .iter_mut()
+            .find(|i| i.outpoint == *outpoint)
+            .ok_or(TxError::MissingInput(*outpoint))?;
+        input.sequence = sequence;
+        Ok(self)
+    }
+
+    /// Set the locktime for a refund transaction, and ensure it is enforced. The locktime is
+    /// ignored if every input has a final sequence (`0xffffffff`). In that case the first
+    /// input's sequence is set to `MAX_NON_FINAL_SEQUENCE`. Call this after adding inputs.
+    pub fn with_refund_locktime(mut self, locktime: u32) -> Self {
+        self.locktime = locktime;
+        if self.vin.iter().all(|i| i.sequence == 0xffff_ffff) {
+            if let Some(input) = self.vin.first_mut() {
+                input.sequence = MAX_NON_FINAL_SEQUENCE;
+            }
+        }
+        self
+    }
+
+    /// Serialize the complete builder state, so that an unfinished transaction can be resumed
+    /// later with `from_state_bytes`. Unlike serializing the built transaction, this keeps
+    /// witnesses that don't yet cover every input, and whether a witness tx will be produced.
+    ///
+    /// The format is the version, a witness mode byte, the prefixed vin and vout, the number of
+    /// witnesses followed by each prefixed witness, and the locktime.
+    pub fn to_state_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.write_state(&mut buf).expect("No error on heap write");
+        buf
+    }
+
+    fn write_state(&self, buf: &mut Vec<u8>) -> TxResult<()> {
+        ser::write_u32_le(buf, self.version)?;
+        buf.push(self.produce_witness as u8);
+        ser::write_prefix_vec(buf, &self.vin)?;
+        ser::write_prefix_vec(buf, &self.vout)?;
+        ser::write_compact_int(buf, self.witnesses.len() as u64)?;
+        for witness in self.witnesses.iter() {
+            ser::write_prefix_vec(buf, witness)?;
+        }
+        ser::write_u32_le(buf, self.locktime)?;
+        Ok(())
+    }
+
+    /// Restore a builder from the output of `to_state_bytes`. Errors if the bytes are
+    /// truncated, have trailing data, or have an invalid witness mode byte.
+    pub fn from_state_bytes(bytes: &[u8]) -> TxResult<Self> {
+        let mut reader = bytes;
+        let version = ser::read_u32_le(&mut reader)?;
+
+        let mut mode = [0u8; 1];
+        std::io::Read::read_exact(&mut reader, &mut mode)?;
+        let produce_witness = match mode[0] {
+            0 => false,
+            1 => true,
+            m => {
+                return Err(
+                    SerError::ComponentError(format!("invalid witness mode byte {}", m)).into(),
+                )
+            }
+        };
+
+        let vin = ser::read_prefix_vec(&mut reader)?;
+        let vout = ser::read_prefix_vec(&mut reader)?;
+        let mut witnesses = vec![];
+        for _ in 0..ser::read_compact_int(&mut reader)? {
+            witnesses.push(ser::read_prefix_vec(&mut reader)?);
+        }
+        let locktime = ser::read_u32_le(&mut reader)?;
+
+        if !reader.is_empty() {
+            return Err(SerError::ComponentError(format!(
+                "{} trailing bytes after builder state",
+                reader.len()
+            ))
+            .into());
+        }
+
+        Ok(Self {
+            version,
+            vin,
+            vout,
+            locktime,
+            witnesses,
+            produce_witness,
+            encoder: PhantomData,
+        })
+    }
+
     /// Consume self, produce a legacy tx. Discard any witness information in the builder
     pub fn build_legacy(self) -> Result<LegacyTx, <LegacyTx as Transaction>::TxError> {
         LegacyTx::new(self.version, self.vin, self.vout, self.locktime)
@@ -91,6 +222,287 @@ where
         self.vout.push(output);
         self
     }
+
+    /// Insert an input spending `outpoint` at `index`. Later inputs, and their witnesses if any,
+    /// shift to later indices. Use this when a counterparty dictates input positions, e.g. in
+    /// PayJoin. Unlike `insert_input`, which appends when the index is too large, this errors.
+    ///
+    /// # Errors
+    ///
+    /// - `TxError::IndexOutOfBounds` if `index` is greater than the number of inputs
+    pub fn insert_spend(
+        mut self,
+        index: usize,
+        outpoint: BitcoinOutpoint,
+        sequence: u32,
+    ) -> TxResult<Self> {
+        if index > self.vin.len() {
+            return Err(TxError::IndexOutOfBounds {
+                index,
+                len: self.vin.len(),
+            });
+        }
+        self.vin.insert(
+            index,
+            BitcoinTxIn::new(outpoint, ScriptSig::default(), sequence),
+        );
+        if index <= self.witnesses.len() && !self.witnesses.is_empty() {
+            self.witnesses.insert(index, Witness::default());
+        }
+        Ok(self)
+    }
+
+    /// Insert an output paying `value` to `script_pubkey` at `index`. Later outputs shift to
+    /// later indices. Unlike `insert_output`, which appends when the index is too large, this
+    /// errors.
+    ///
+    /// # Errors
+    ///
+    /// - `TxError::IndexOutOfBounds` if `index` is greater than the number of outputs
+    pub fn insert_script_pubkey(
+        mut self,
+        index: usize,
+        value: u64,
+        script_pubkey: ScriptPubkey,
+    ) -> TxResult<Self> {
+        if index > self.vout.len() {
+            return Err(TxError::IndexOutOfBounds {
+                index,
+                len: self.vout.len(),
+            });
+        }
+        self.vout.insert(index, TxOut::new(value, script_pubkey));
+        Ok(self)
+    }
+
+    /// Bump the fee of the transaction by spending an additional UTXO. The output at
+    /// `change_index` is adjusted so that the transaction pays `new_fee_rate` sat/vbyte. Use this
+    /// when the change output alone can't cover the new fee.
+    ///
+    /// `prevouts` must contain the output spent by each existing input, in vin order. The size
+    /// of the existing inputs is taken from their current script sigs and witnesses, so the
+    /// builder should contain the signed transaction. The size of the new input is estimated,
+    /// and it must be P2PKH or P2WPKH.
+    ///
+    /// Adding an input invalidates existing signatures, so all script sigs and witnesses are
+    /// removed. Every input must be signed again.
+    ///
+    /// # Errors
+    ///
+    /// - `TxError::PrevoutsLengthMismatch` if `prevouts` does not match the vin
+    /// - `TxError::UnestimatedInput` if `extra_utxo` is not P2PKH or P2WPKH
+    /// - `TxError::MissingOutput` if there is no output at `change_index`
+    /// - `TxError::InsufficientFeeBump` if the change would fall below the dust limit
+    /// - `TxError::ValueOverflow` if the input values, output values, or fee overflow a `u64`
+    pub fn bump_fee_adding_input(
+        mut self,
+        prevouts: &[TxOut],
+        extra_utxo: UTXO,
+        new_fee_rate: u64,
+        change_index: usize,
+    ) -> TxResult<Self> {
+        if prevouts.len() != self.vin.len() {
+            return Err(TxError::PrevoutsLengthMismatch {
+                tx_ins: self.vin.len(),
+                prevouts: prevouts.len(),
+            });
+        }
+        let input_vsize = select::input_vsize(&extra_utxo)
+            .ok_or(TxError::UnestimatedInput(extra_utxo.outpoint))?;
+        if change_index >= self.vout.len() {
+            return Err(TxError::MissingOutput(change_index));
+        }
+        let is_witness_input = matches!(extra_utxo.standard_type(), ScriptType::WPKH(_));
+
+        let current = self.clone().build()?;
+        let mut weight = current.weight() as u64 + input_vsize * 4;
+        if !current.is_witness() && is_witness_input {
+            // The segwit marker and flag, and an empty witness for each existing input
+            weight += 2 + self.vin.len() as u64;
+        }
+        let fee = ((weight + 3) / 4)
+            .checked_mul(new_fee_rate)
+            .ok_or(TxError::ValueOverflow)?;
+
+        let in_value = prevouts
+            .iter()
+            .try_fold(extra_utxo.value, |acc, p| acc.checked_add(p.value))
+            .ok_or(TxError::ValueOverflow)?;
+        let other_outputs = self
+            .vout
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != change_index)
+            .try_fold(0u64, |acc, (_, o)| acc.checked_add(o.value))
+            .ok_or(TxError::ValueOverflow)?;
+        let available = in_value.saturating_sub(other_outputs);
+        let required = fee.checked_add(DUST_LIMIT).ok_or(TxError::ValueOverflow)?;
+        if available < required {
+            return Err(TxError::InsufficientFeeBump {
+                available,
+                required,
+            });
+        }
+
+        self.vout[change_index].value = available - fee;
+        self.vin.iter_mut().for_each(|i| *i = i.unsigned());
+        self.witnesses.clear();
+        self.produce_witness = current.is_witness() || is_witness_input;
+        Ok(self.spend(extra_utxo.outpoint, 0xffff_fffd))
+    }
+
+    /// The total input value needed to fund the builder's outputs at `fee_rate` sat/vbyte, if
+    /// inputs of `estimated_input_types` are added. This is the output value plus the estimated
+    /// fee. Inputs already in the builder, and any change output, are not counted.
+    ///
+    /// Errors with `TxError::ValueOverflow` if the value exceeds `u64::MAX`.
+    pub fn required_input_value(
+        &self,
+        fee_rate: u64,
+        estimated_input_types: &[SpendType],
+    ) -> TxResult<u64> {
+        let vsize = TX_OVERHEAD_VSIZE
+            + self.vout.iter().map(output_vsize).sum::<u64>()
+            + estimated_input_types.iter().map(|t| t.vsize()).sum::<u64>();
+        self.vout
+            .iter()
+            .try_fold(0u64, |acc, o| acc.checked_add(o.value))
+            .and_then(|value| value.checked_add(vsize.checked_mul(fee_rate)?))
+            .ok_or(TxError::ValueOverflow)
+    }
+
+    /// Fund the builder's outputs from `utxos`, minimizing the fee. Returns the funded builder
+    /// and the fee it pays. `fee_rate` is in sat/vbyte.
+    ///
+    /// A changeless input set from `BranchAndBound` is used if one exists and is no more
+    /// expensive than `SegwitFirst` selection. Otherwise inputs are selected segwit-first, and
+    /// any change above the dust limit is paid to `change`.
+    ///
+    /// Inputs already in the builder are not counted toward the payment. Fees are estimated as
+    /// in the `select` module, so only P2PKH and P2WPKH UTXOs are used.
+    ///
+    /// # Errors
+    ///
+    /// - `BatchError::SelectionError` if the UTXOs can't fund the outputs, or with
+    ///   `SelectionError::ValueOverflow` if the fee or the UTXOs total more than `u64::MAX`
+    /// - `BatchError::TxError` with `TxError::ValueOverflow` if the outputs total more than
+    ///   `u64::MAX`
+    pub fn fund_cheapest(
+        self,
+        utxos: &[UTXO],
+        fee_rate: u64,
+        change: &Address,
+    ) -> Result<(Self, u64), BatchError> {
+        let change_script = T::decode_address(change)?;
+        let value = self
+            .vout
+            .iter()
+            .try_fold(0u64, |acc, o| acc.checked_add(o.value))
+            .ok_or(TxError::ValueOverflow)?;
+        let target = SelectionTarget {
+            value,
+            fee_rate,
+            base_vsize: TX_OVERHEAD_VSIZE + self.vout.iter().map(output_vsize).sum::<u64>(),
+            change_vsize: output_vsize(&TxOut::new(0, change_script.clone())),
+        };
+
+        let segwit_first = SegwitFirst.select(utxos, &target);
+        let selection = match (BranchAndBound.select(utxos, &target), segwit_first) {
+            (Ok(changeless), Ok(other)) if other.fee < changeless.fee => other,
+            (Ok(changeless), _) => changeless,
+            (Err(_), other) => other?,
+        };
+
+        let mut builder = self;
+        for utxo in selection.utxos.iter() {
+            builder = builder.spend(utxo.outpoint, 0xffff_fffd);
+        }
+        if let Some(change_value) = selection.change {
+            builder = builder.pay_script_pubkey(change_value, change_script);
+        }
+        Ok((builder, selection.fee))
+    }
+
+    /// Split `payments` across as many transactions as needed to keep each under
+    /// `MAX_STANDARD_TX_WEIGHT`. Payments keep their order, and each transaction pays a
+    /// contiguous run of them. Inputs are selected largest-first from `utxos`, and no UTXO is
+    /// spent twice. Any change above the dust limit is paid to `change`. `fee_rate` is in
+    /// sat/vbyte.
+    ///
+    /// Transactions are returned unsigned. Fees are estimated as in the `select` module, so only
+    /// P2PKH and P2WPKH UTXOs are used.
+    ///
+    /// # Errors
+    ///
+    /// - `BatchError::SelectionError` if the UTXOs can't fund every payment
+    /// - `BatchError::PaymentTooLarge` if a payment can't be funded by a standard transaction
+    /// - `BatchError::TxError` with `TxError::ValueOverflow` if a batch's payments total more
+    ///   than `u64::MAX`
+    pub fn build_batched(
+        utxos: &[UTXO],
+        payments: &[(Address, u64)],
+        fee_rate: u64,
+        change: &Address,
+    ) -> Result<Vec<BitcoinTx>, BatchError> {
+        let outputs = payments
+            .iter()
+            .map(|(address, value)| Ok(TxOut::new(*value, T::decode_address(address)?)))
+            .collect::<EncodingResult<Vec<_>>>()?;
+        let change_script = T::decode_address(change)?;
+        let change_vsize = output_vsize(&TxOut::new(0, change_script.clone()));
+
+        let mut remaining = utxos.to_vec();
+        let mut txns = vec![];
+        let mut start = 0;
+        while start < outputs.len() {
+            // Grow the batch one payment at a time until the funded tx is too heavy
+            let mut batch: Option<Selection> = None;
+            let mut end = start;
+            let mut target = SelectionTarget {
+                value: 0,
+                fee_rate,
+                // Allow for a 3-byte output count, as batches are usually large
+                base_vsize: TX_OVERHEAD_VSIZE + 2,
+                change_vsize,
+            };
+            while end < outputs.len() {
+                target.value = target
+                    .value
+                    .checked_add(outputs[end].value)
+                    .ok_or(TxError::ValueOverflow)?;
+                target.base_vsize += output_vsize(&outputs[end]);
+                let selection = LargestFirst.select(&remaining, &target)?;
+                let vsize = target.base_vsize
+                    + selection.change.map_or(0, |_| change_vsize)
+                    + selection
+                        .utxos
+                        .iter()
+                        .filter_map(select::input_vsize)
+                        .sum::<u64>();
+                if vsize * 4 > MAX_STANDARD_TX_WEIGHT {
+                    break;
+                }
+                batch = Some(selection);
+                end += 1;
+            }
+            let selection = batch.ok_or(BatchError::PaymentTooLarge(start))?;
+
+            let mut builder = Self::new()
+                .version(2)
+                .extend_outputs(outputs[start..end].iter().cloned());
+            for utxo in selection.utxos.iter() {
+                builder = builder.spend(utxo.outpoint, 0xffff_fffd);
+            }
+            if let Some(change_value) = selection.change {
+                builder = builder.pay_script_pubkey(change_value, change_script.clone());
+            }
+            txns.push(builder.build()?);
+
+            remaining.retain(|u| !selection.utxos.iter().any(|s| s.outpoint == u.outpoint));
+            start = end;
+        }
+        Ok(txns)
+    }
 }
 
 impl<T> TxBuilder for BitcoinTxBuilder<T>
@@ -214,3 +626,349 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        enc::encoder::MainnetEncoder,
+        hashes::TXID,
+        types::{SpendScript, WitnessStackItem},
+    };
+
+    #[test]
+    fn it_bumps_fees_by_adding_an_input() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let prevouts = vec![TxOut::new(100_000, spk.clone())];
+        let builder = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .version(2)
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_fffd)
+            .pay_script_pubkey(80_000, spk.clone())
+            .pay_script_pubkey(19_000, spk.clone());
+        let extra = UTXO::new(
+            BitcoinOutpoint::new(TXID::default(), 1),
+            50_000,
+            spk.clone(),
+            SpendScript::None,
+        );
+
+        let tx = builder
+            .clone()
+            .bump_fee_adding_input(&prevouts, extra.clone(), 20, 1)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(tx.is_witness());
+        assert_eq!(tx.inputs().len(), 2);
+        assert_eq!(tx.inputs()[1].outpoint, extra.outpoint);
+        assert_eq!(tx.outputs()[0].value, 80_000);
+        // 113-byte legacy tx, plus a 68 vbyte input, plus marker, flag, and 1 empty witness
+        // (452 + 272 + 3) weight units, rounded up to 182 vbytes
+        assert_eq!(tx.outputs()[1].value, 150_000 - 80_000 - 182 * 20);
+
+        match builder
+            .clone()
+            .bump_fee_adding_input(&prevouts, extra.clone(), 400, 1)
+        {
+            Err(TxError::InsufficientFeeBump { available, .. }) => {
+                assert_eq!(available, 70_000)
+            }
+            _ => assert!(false, "expected InsufficientFeeBump"),
+        }
+        let huge = vec![TxOut::new(u64::MAX, spk.clone())];
+        match builder
+            .clone()
+            .bump_fee_adding_input(&huge, extra.clone(), 20, 1)
+        {
+            Err(TxError::ValueOverflow) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+        match builder.bump_fee_adding_input(&[], extra, 20, 1) {
+            Err(TxError::PrevoutsLengthMismatch { .. }) => {}
+            _ => assert!(false, "expected PrevoutsLengthMismatch"),
+        }
+    }
+
+    #[test]
+    fn it_sets_sequences_by_outpoint() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let outpoints: Vec<_> = (0..3)
+            .map(|i| BitcoinOutpoint::new(TXID::default(), i))
+            .collect();
+        let builder = outpoints
+            .iter()
+            .fold(BitcoinTxBuilder::<MainnetEncoder>::new(), |b, o| {
+                b.spend(*o, 0xffff_ffff)
+            })
+            .pay_script_pubkey(10_000, spk);
+
+        let tx = builder
+            .clone()
+            .set_sequence_for(&outpoints[1], 144)
+            .unwrap()
+            .build()
+            .unwrap();
+        let sequences: Vec<u32> = tx.inputs().iter().map(|i| i.sequence).collect();
+        assert_eq!(sequences, vec![0xffff_ffff, 144, 0xffff_ffff]);
+
+        let missing = BitcoinOutpoint::new(TXID::default(), 3);
+        match builder.set_sequence_for(&missing, 144) {
+            Err(TxError::MissingInput(o)) => assert_eq!(o, missing),
+            _ => assert!(false, "expected MissingInput"),
+        }
+    }
+
+    #[test]
+    fn it_enforces_refund_locktimes() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let builder = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_ffff)
+            .spend(BitcoinOutpoint::new(TXID::default(), 1), 0xffff_ffff)
+            .pay_script_pubkey(10_000, spk);
+
+        let tx = builder
+            .clone()
+            .with_refund_locktime(700_000)
+            .build()
+            .unwrap();
+        let sequences: Vec<u32> = tx.inputs().iter().map(|i| i.sequence).collect();
+        assert_eq!(tx.locktime(), 700_000);
+        assert_eq!(sequences, vec![MAX_NON_FINAL_SEQUENCE, 0xffff_ffff]);
+
+        // an existing non-final input already enables the locktime
+        let tx = builder
+            .set_sequence_for(&BitcoinOutpoint::new(TXID::default(), 1), 144)
+            .unwrap()
+            .with_refund_locktime(700_000)
+            .build()
+            .unwrap();
+        let sequences: Vec<u32> = tx.inputs().iter().map(|i| i.sequence).collect();
+        assert_eq!(sequences, vec![0xffff_ffff, 144]);
+    }
+
+    #[test]
+    fn it_roundtrips_builder_state() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        // more inputs than witnesses, as in a partially signed transaction
+        let builder = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .version(2)
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_fffd)
+            .spend(BitcoinOutpoint::new(TXID::default(), 1), 0xffff_ffff)
+            .pay_script_pubkey(10_000, spk)
+            .extend_witnesses(vec![vec![vec![0x30; 71].into(), vec![0x02; 33].into()]])
+            .locktime(700_000);
+
+        let bytes = builder.to_state_bytes();
+        let restored = BitcoinTxBuilder::<MainnetEncoder>::from_state_bytes(&bytes).unwrap();
+        assert_eq!(restored, builder);
+
+        let legacy = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_ffff);
+        let restored =
+            BitcoinTxBuilder::<MainnetEncoder>::from_state_bytes(&legacy.to_state_bytes()).unwrap();
+        assert_eq!(restored, legacy);
+
+        assert!(
+            BitcoinTxBuilder::<MainnetEncoder>::from_state_bytes(&bytes[..bytes.len() - 1])
+                .is_err()
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(BitcoinTxBuilder::<MainnetEncoder>::from_state_bytes(&trailing).is_err());
+        let mut bad_mode = bytes;
+        bad_mode[4] = 2;
+        assert!(BitcoinTxBuilder::<MainnetEncoder>::from_state_bytes(&bad_mode).is_err());
+    }
+
+    #[test]
+    fn it_inserts_at_exact_positions() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let outpoint = |idx| BitcoinOutpoint::new(TXID::default(), idx);
+        let builder = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .version(2)
+            .spend(outpoint(0), 0xffff_fffd)
+            .spend(outpoint(1), 0xffff_fffd)
+            .pay_script_pubkey(1_000, spk.clone())
+            .pay_script_pubkey(2_000, spk.clone());
+
+        let tx = builder
+            .clone()
+            .insert_spend(1, outpoint(2), 0xffff_fffe)
+            .unwrap()
+            .insert_script_pubkey(0, 3_000, spk.clone())
+            .unwrap()
+            .insert_script_pubkey(3, 4_000, spk.clone())
+            .unwrap()
+            .build()
+            .unwrap();
+        let idxs: Vec<u32> = tx.inputs().iter().map(|i| i.outpoint.idx).collect();
+        assert_eq!(idxs, vec![0, 2, 1]);
+        assert_eq!(tx.inputs()[1].sequence, 0xffff_fffe);
+        let values: Vec<u64> = tx.outputs().iter().map(|o| o.value).collect();
+        assert_eq!(values, vec![3_000, 1_000, 2_000, 4_000]);
+
+        // witnesses stay aligned with their inputs
+        let witness = vec![WitnessStackItem::new(vec![1])];
+        let tx = builder
+            .clone()
+            .extend_witnesses(vec![witness.clone(), witness.clone()])
+            .insert_spend(0, outpoint(2), 0xffff_fffd)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(tx.witnesses(), &[vec![], witness.clone(), witness][..]);
+
+        match builder.clone().insert_spend(3, outpoint(2), 0) {
+            Err(TxError::IndexOutOfBounds { index: 3, len: 2 }) => {}
+            _ => assert!(false, "expected IndexOutOfBounds"),
+        }
+        match builder.insert_script_pubkey(3, 0, spk) {
+            Err(TxError::IndexOutOfBounds { index: 3, len: 2 }) => {}
+            _ => assert!(false, "expected IndexOutOfBounds"),
+        }
+    }
+
+    #[test]
+    fn it_funds_with_the_cheapest_inputs() {
+        let wpkh = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let pkh = ScriptPubkey::from(
+            hex::decode("76a914758ce550380d964051086798d6546bebdca27a7388ac").unwrap(),
+        );
+        let address = MainnetEncoder::encode_address(&wpkh).unwrap();
+        let utxo = |idx, value, spk: &ScriptPubkey| {
+            UTXO::new(
+                BitcoinOutpoint::new(TXID::default(), idx),
+                value,
+                spk.clone(),
+                SpendScript::None,
+            )
+        };
+        let utxos = vec![
+            utxo(0, 50_000, &wpkh),
+            utxo(1, 30_000, &wpkh),
+            utxo(2, 80_000, &pkh),
+            utxo(3, 20_000, &wpkh),
+        ];
+
+        // a single segwit input funds this exactly, so no change is made
+        let (builder, fee) = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(49_700, wpkh.clone())
+            .fund_cheapest(&utxos, 2, &address)
+            .unwrap();
+        let tx = builder.build().unwrap();
+        assert_eq!(fee, 300);
+        assert_eq!(tx.inputs().len(), 1);
+        assert_eq!(tx.inputs()[0].outpoint.idx, 0);
+        assert_eq!(tx.outputs().len(), 1);
+
+        // two segwit inputs are cheaper than the single legacy input
+        let (builder, fee) = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(60_000, wpkh.clone())
+            .fund_cheapest(&utxos, 2, &address)
+            .unwrap();
+        let tx = builder.build().unwrap();
+        let idxs: Vec<u32> = tx.inputs().iter().map(|i| i.outpoint.idx).collect();
+        assert_eq!(idxs, vec![0, 1]);
+        // 11 + 31 + 31 + 2 * 68 vbytes at 2 sat/vbyte
+        assert_eq!(fee, 418);
+        assert_eq!(tx.outputs()[1].value, 80_000 - 60_000 - 418);
+
+        // the same inputs, without the change output's 31 vbytes
+        let required = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(60_000, wpkh.clone())
+            .required_input_value(2, &[SpendType::P2WPKH, SpendType::P2WPKH])
+            .unwrap();
+        assert_eq!(required, 60_000 + fee - 31 * 2);
+        match BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(u64::MAX, wpkh.clone())
+            .required_input_value(2, &[SpendType::P2WPKH])
+        {
+            Err(TxError::ValueOverflow) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+
+        match BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(200_000, wpkh.clone())
+            .fund_cheapest(&utxos, 2, &address)
+        {
+            Err(BatchError::SelectionError(SelectionError::InsufficientFunds { .. })) => {}
+            _ => assert!(false, "expected InsufficientFunds"),
+        }
+
+        match BitcoinTxBuilder::<MainnetEncoder>::new()
+            .pay_script_pubkey(u64::MAX, wpkh.clone())
+            .pay_script_pubkey(1, wpkh)
+            .fund_cheapest(&utxos, 2, &address)
+        {
+            Err(BatchError::TxError(TxError::ValueOverflow)) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+    }
+
+    #[test]
+    fn it_splits_large_payouts_into_batches() {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        let address = MainnetEncoder::encode_address(&spk).unwrap();
+        let utxos: Vec<UTXO> = (0..3)
+            .map(|i| {
+                UTXO::new(
+                    BitcoinOutpoint::new(TXID::default(), i),
+                    10_000_000,
+                    spk.clone(),
+                    SpendScript::None,
+                )
+            })
+            .collect();
+        // 31 vbytes each, so too many for a single standard tx
+        let payments = vec![(address.clone(), 1_000); 5000];
+
+        let txns =
+            BitcoinTxBuilder::<MainnetEncoder>::build_batched(&utxos, &payments, 1, &address)
+                .unwrap();
+        assert_eq!(txns.len(), 2);
+
+        let mut paid = 0;
+        let mut spent = vec![];
+        for tx in txns.iter() {
+            // inputs are unsigned, so add the marker, flag, and estimated witness size
+            let vsize = tx.vsize() as u64 + 28 * tx.inputs().len() as u64;
+            assert!(vsize * 4 <= MAX_STANDARD_TX_WEIGHT);
+
+            let in_value = tx.inputs().len() as u64 * 10_000_000;
+            let out_value = tx.outputs().iter().map(|o| o.value).sum::<u64>();
+            assert!(in_value - out_value >= vsize);
+
+            paid += tx.outputs().iter().filter(|o| o.value == 1_000).count();
+            spent.extend(tx.inputs().iter().map(|i| i.outpoint));
+        }
+        assert_eq!(paid, 5000);
+        spent.dedup();
+        assert_eq!(spent.len(), 2);
+
+        match BitcoinTxBuilder::<MainnetEncoder>::build_batched(&utxos[..1], &payments, 1, &address)
+        {
+            Err(BatchError::SelectionError(SelectionError::InsufficientFunds { .. })) => {}
+            _ => assert!(false, "expected InsufficientFunds"),
+        }
+
+        let overflowing = vec![(address.clone(), 1_000), (address.clone(), u64::MAX)];
+        match BitcoinTxBuilder::<MainnetEncoder>::build_batched(&utxos, &overflowing, 1, &address) {
+            Err(BatchError::TxError(TxError::ValueOverflow)) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+    }
+}
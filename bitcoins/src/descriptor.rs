@@ -0,0 +1,1430 @@
+//! Output script descriptors, as used by Bitcoin Core's `importdescriptors` and
+//! `scantxoutset` RPCs.
+//!
+//! The `addr()` and `raw()` fragments describe a single fixed script pubkey. The `pkh()`,
+//! `wpkh()`, `sh(wpkh())` and `tr()` fragments describe the script of a key. `tr()` is
+//! supported for key-path spends only, with the BIP86 tweak, and may not have a script tree.
+//! A key may be a hex pubkey, or an extended pubkey followed by unhardened derivation steps. An extended key
+//! ending in `/*` makes the descriptor ranged: it derives a different script at each index.
+//! One step of the path may be a BIP389 multipath element, e.g. `/<0;1>/*`. The descriptor
+//! then has one derivation branch per element, and derives one script per branch at each index.
+//! Descriptors may carry a BIP380 checksum after a `#`. It is verified when present, and always
+//! included when displaying a descriptor.
+//!
+//! A `DescriptorSet` groups the descriptors of a wallet, and can recognize transaction inputs
+//! that spend the wallet's scripts.
+
+use std::{
+    collections::HashSet,
+    ops::{Range, RangeInclusive},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use thiserror::Error;
+
+use coins_bip32::{
+    curve::{PointDeserialize, PointSerialize, Pubkey, Secp256k1, Secp256k1Backend},
+    enc::decode_any_xpub,
+    path::{DerivationPath, KeyDerivation},
+    Bip32Error, DerivePublicChild, HasPubkey, KeyFingerprint, XKey, XPub, BIP32_HARDEN,
+};
+use coins_core::{
+    enc::EncodingError,
+    hashes::{Hash160, MarkedDigest, MarkedDigestOutput},
+    types::tx::Transaction,
+};
+
+use crate::{
+    enc::encoder::{Address, BitcoinEncoderMarker},
+    types::{
+        asm::next_op,
+        script::{Script, ScriptPubkey, WitnessStackItem},
+        taproot::tagged_hash,
+        tx::{BitcoinTransaction, BitcoinTx},
+        txout::TxOut,
+    },
+};
+
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u64; 5] = [
+    0xf5_dee5_1989,
+    0xa9_fdca_3312,
+    0x1b_ab10_e32d,
+    0x37_06b1_677a,
+    0x64_4d62_6ffd,
+];
+
+/// Errors produced when parsing descriptors
+#[derive(Debug, Error)]
+pub enum DescriptorError {
+    /// The descriptor contains a character outside the descriptor character set
+    #[error("Invalid descriptor character {0:?}")]
+    InvalidCharacter(char),
+
+    /// The checksum does not match the descriptor
+    #[error("Bad descriptor checksum. Expected {expected}. Got {got}")]
+    BadChecksum {
+        /// The checksum of the descriptor
+        expected: String,
+        /// The checksum provided
+        got: String,
+    },
+
+    /// The descriptor is not a supported fragment
+    #[error("Unsupported descriptor: {0}")]
+    Unsupported(String),
+
+    /// The key expression is malformed, or the key can't be used in its fragment
+    #[error("Invalid descriptor key: {0}")]
+    InvalidKey(String),
+
+    /// The `raw()` hex is not a well-formed script
+    #[error("Malformed script in raw descriptor")]
+    MalformedScript,
+
+    /// Bubbled up from address parsing
+    #[error(transparent)]
+    EncodingError(#[from] EncodingError),
+
+    /// Bubbled up from hex decoding
+    #[error(transparent)]
+    HexError(#[from] hex::FromHexError),
+
+    /// A multipath descriptor was asked for a single script. Use `derive_scripts` instead
+    #[error("Multipath descriptor has one script per branch at each index")]
+    MultipathDerivation,
+
+    /// Bubbled up from key parsing and derivation
+    #[error(transparent)]
+    Bip32Error(#[from] Bip32Error),
+}
+
+/// Type alias for results with a `DescriptorError`
+pub type DescriptorResult<T> = Result<T, DescriptorError>;
+
+fn polymod(symbols: impl Iterator<Item = u64>) -> u64 {
+    symbols.fold(1, |chk, value| {
+        let top = chk >> 35;
+        let chk = (chk & 0x7_ffff_ffff) << 5 ^ value;
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+/// Compute the 8-character BIP380 checksum of a descriptor, without its `#`.
+pub fn descriptor_checksum(descriptor: &str) -> DescriptorResult<String> {
+    let mut symbols = vec![];
+    let mut groups = vec![];
+    for c in descriptor.chars() {
+        let value = INPUT_CHARSET
+            .find(c)
+            .ok_or(DescriptorError::InvalidCharacter(c))? as u64;
+        symbols.push(value & 31);
+        groups.push(value >> 5);
+        if groups.len() == 3 {
+            symbols.push(groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    match groups.as_slice() {
+        [a] => symbols.push(*a),
+        [a, b] => symbols.push(a * 3 + b),
+        _ => {}
+    }
+
+    let checksum = polymod(symbols.into_iter().chain(std::iter::repeat(0).take(8))) ^ 1;
+    Ok((0..8)
+        .map(|i| CHECKSUM_CHARSET[((checksum >> (5 * (7 - i))) & 31) as usize] as char)
+        .collect())
+}
+
+/// The public key of a descriptor key expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum DescriptorPubkey {
+    /// A hex pubkey: compressed, uncompressed, or x-only
+    Single(Vec<u8>),
+    /// An extended pubkey
+    Extended {
+        /// The extended pubkey, derived along the fixed steps of the expression's path. A
+        /// multipath expression has one branch per element of its `<a;b;...>` step, in order
+        branches: Vec<XPub>,
+        /// True if the expression ends in `/*`. The key at each index is then the child of
+        /// each branch at that index
+        wildcard: bool,
+    },
+}
+
+/// A descriptor key expression: an optional key origin, then a hex pubkey or an extended pubkey
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescriptorKey {
+    /// The key origin, `[fingerprint/path]`, if present
+    pub origin: Option<KeyDerivation>,
+    /// The public key
+    pub pubkey: DescriptorPubkey,
+    /// The expression, as parsed
+    expression: String,
+}
+
+impl Eq for DescriptorKey {}
+
+impl DescriptorKey {
+    /// Parse a key expression. Extended keys may be from any network in the default bip32
+    /// `NetworkRegistry`. Their path may contain only unhardened steps, and may end in an
+    /// unhardened `/*`. One step may be a multipath element of two or more distinct indices,
+    /// `<a;b;...>`.
+    pub fn parse(expression: &str) -> DescriptorResult<Self> {
+        let invalid = || DescriptorError::InvalidKey(expression.to_owned());
+
+        let (origin, key) = match expression.strip_prefix('[') {
+            Some(rest) => {
+                let end = rest.find(']').ok_or_else(invalid)?;
+                (
+                    Some(parse_origin(&rest[..end]).ok_or_else(invalid)?),
+                    &rest[end + 1..],
+                )
+            }
+            None => (None, expression),
+        };
+
+        let pubkey = if let Ok(bytes) = hex::decode(key) {
+            match bytes.len() {
+                32 => {
+                    let mut buf = [0x02; 33];
+                    buf[1..].copy_from_slice(&bytes);
+                    Pubkey::from_pubkey_array(buf)?;
+                }
+                33 => {
+                    let mut buf = [0u8; 33];
+                    buf.copy_from_slice(&bytes);
+                    Pubkey::from_pubkey_array(buf)?;
+                }
+                65 => {
+                    let mut buf = [0u8; 65];
+                    buf.copy_from_slice(&bytes);
+                    Pubkey::from_pubkey_array_uncompressed(buf)?;
+                }
+                _ => return Err(invalid()),
+            }
+            DescriptorPubkey::Single(bytes)
+        } else {
+            let (xpub, path) = match key.find('/') {
+                Some(idx) => (&key[..idx], &key[idx + 1..]),
+                None => (key, ""),
+            };
+            let (path, wildcard) = match path.strip_suffix('*') {
+                Some(prefix) if prefix.is_empty() => ("", true),
+                Some(prefix) => (prefix.strip_suffix('/').ok_or_else(invalid)?, true),
+                None if path.ends_with("*'") || path.ends_with("*h") => {
+                    return Err(DescriptorError::Unsupported(expression.to_owned()))
+                }
+                None => (path, false),
+            };
+            let paths = expand_multipath(path).ok_or_else(invalid)?;
+
+            let (_, _, xpub) = decode_any_xpub(xpub, Some(Secp256k1::static_ref()))?;
+            let mut branches = vec![];
+            for path in paths.iter() {
+                let path: DerivationPath = if path.is_empty() {
+                    Default::default()
+                } else {
+                    path.parse()?
+                };
+                let branch = xpub.derive_public_path(&path)?;
+                if wildcard && branch.depth() == u8::MAX {
+                    return Err(Bip32Error::MaxDepthExceeded.into());
+                }
+                branches.push(branch);
+            }
+            DescriptorPubkey::Extended { branches, wildcard }
+        };
+
+        Ok(Self {
+            origin,
+            pubkey,
+            expression: expression.to_owned(),
+        })
+    }
+
+    /// True if the key differs at each index
+    pub fn is_ranged(&self) -> bool {
+        matches!(
+            self.pubkey,
+            DescriptorPubkey::Extended { wildcard: true, .. }
+        )
+    }
+
+    /// True if the key has more than one derivation branch
+    pub fn is_multipath(&self) -> bool {
+        matches!(&self.pubkey, DescriptorPubkey::Extended { branches, .. } if branches.len() > 1)
+    }
+
+    /// The length of the serialized pubkey. 32 for x-only keys, 33 for compressed keys, and 65
+    /// for uncompressed keys. Extended keys are always compressed
+    pub fn pubkey_length(&self) -> usize {
+        match &self.pubkey {
+            DescriptorPubkey::Single(bytes) => bytes.len(),
+            DescriptorPubkey::Extended { .. } => 33,
+        }
+    }
+
+    /// The serialized pubkey at `index`. Keys that are not ranged ignore the index.
+    ///
+    /// ## Errors
+    ///
+    /// - `DescriptorError::MultipathDerivation` if the key has more than one branch
+    /// - `DescriptorError::Bip32Error` if a ranged key can't derive a child at `index`, e.g.
+    ///   because it is hardened
+    pub fn derive_pubkey(&self, index: u32) -> DescriptorResult<Vec<u8>> {
+        if self.is_multipath() {
+            return Err(DescriptorError::MultipathDerivation);
+        }
+        Ok(self.derive_pubkeys(index)?.remove(0))
+    }
+
+    /// The serialized pubkey of each branch at `index`. Keys that are not ranged ignore the
+    /// index. Errors if a ranged key can't derive a child at `index`, e.g. because it is
+    /// hardened.
+    pub fn derive_pubkeys(&self, index: u32) -> DescriptorResult<Vec<Vec<u8>>> {
+        match &self.pubkey {
+            DescriptorPubkey::Single(bytes) => Ok(vec![bytes.clone()]),
+            DescriptorPubkey::Extended {
+                branches,
+                wildcard: false,
+            } => Ok(branches
+                .iter()
+                .map(|branch| branch.pubkey_bytes().to_vec())
+                .collect()),
+            DescriptorPubkey::Extended {
+                branches,
+                wildcard: true,
+            } => branches
+                .iter()
+                .map(|branch| -> DescriptorResult<Vec<u8>> {
+                    Ok(branch.derive_child(index)?.pubkey_bytes().to_vec())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for DescriptorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+/// Expand the `<a;b;...>` step of a path into one path per element. A path without one
+/// expands to itself. Returns `None` if the step is malformed, has fewer than two elements or
+/// repeats an element, or if the path has more than one such step.
+fn expand_multipath(path: &str) -> Option<Vec<String>> {
+    let start = match path.find('<') {
+        Some(start) => start,
+        None => return Some(vec![path.to_owned()]),
+    };
+    let end = start + path[start..].find('>')?;
+    let (prefix, suffix) = (&path[..start], &path[end + 1..]);
+    if (!prefix.is_empty() && !prefix.ends_with('/'))
+        || (!suffix.is_empty() && !suffix.starts_with('/'))
+        || suffix.contains('<')
+    {
+        return None;
+    }
+
+    let elements: Vec<&str> = path[start + 1..end].split(';').collect();
+    let distinct: HashSet<&str> = elements.iter().copied().collect();
+    if elements.len() < 2 || distinct.len() != elements.len() {
+        return None;
+    }
+    Some(
+        elements
+            .iter()
+            .map(|element| format!("{}{}{}", prefix, element, suffix))
+            .collect(),
+    )
+}
+
+/// Parse the inside of a key origin, `fingerprint/path`
+fn parse_origin(origin: &str) -> Option<KeyDerivation> {
+    let (fingerprint, path) = match origin.find('/') {
+        Some(idx) => (&origin[..idx], &origin[idx + 1..]),
+        None => (origin, ""),
+    };
+    let mut root = [0u8; 4];
+    hex::decode_to_slice(fingerprint, &mut root).ok()?;
+    let path = if path.is_empty() {
+        Default::default()
+    } else {
+        path.parse().ok()?
+    };
+    Some(KeyDerivation {
+        root: KeyFingerprint(root),
+        path,
+    })
+}
+
+/// An output script descriptor
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Descriptor {
+    /// `addr(ADDRESS)`. A fixed address
+    Addr {
+        /// The address
+        address: Address,
+        /// The address's script pubkey
+        script_pubkey: ScriptPubkey,
+    },
+    /// `raw(HEX)`. A fixed script pubkey
+    Raw(ScriptPubkey),
+    /// `pkh(KEY)`. Pay to the key's pubkey hash
+    Pkh(DescriptorKey),
+    /// `wpkh(KEY)`. Pay to the key's witness pubkey hash. The key must be compressed
+    Wpkh(DescriptorKey),
+    /// `sh(wpkh(KEY))`. Pay to the key's witness pubkey hash, nested in P2SH. The key must be
+    /// compressed
+    ShWpkh(DescriptorKey),
+    /// `tr(KEY)`. Pay to the BIP86 taproot output key of the key, with no script tree. The key
+    /// must be compressed or x-only
+    Tr(DescriptorKey),
+}
+
+impl Descriptor {
+    /// Parse a descriptor, verifying its checksum if present. Addresses are validated for the
+    /// network of encoder `E`, and raw scripts must contain only well-formed pushes. See
+    /// `DescriptorKey::parse` for the accepted key expressions.
+    pub fn parse<E: BitcoinEncoderMarker>(descriptor: &str) -> DescriptorResult<Self> {
+        let body = match descriptor.rfind('#') {
+            Some(idx) => {
+                let (body, got) = (&descriptor[..idx], &descriptor[idx + 1..]);
+                let expected = descriptor_checksum(body)?;
+                if expected != got {
+                    return Err(DescriptorError::BadChecksum {
+                        expected,
+                        got: got.to_owned(),
+                    });
+                }
+                body
+            }
+            None => descriptor,
+        };
+
+        if let Some(address) = fragment(body, "addr") {
+            let address = E::string_to_address(address)?;
+            let script_pubkey = E::decode_address(&address)?;
+            Ok(Descriptor::Addr {
+                address,
+                script_pubkey,
+            })
+        } else if let Some(raw) = fragment(body, "raw") {
+            let script = hex::decode(raw)?;
+            let mut cursor = 0;
+            while cursor < script.len() {
+                let (_, _, next) =
+                    next_op(&script, cursor).ok_or(DescriptorError::MalformedScript)?;
+                cursor = next;
+            }
+            Ok(Descriptor::Raw(script.into()))
+        } else if let Some(key) = fragment(body, "pkh") {
+            Ok(Descriptor::Pkh(fragment_key(key, &[33, 65])?))
+        } else if let Some(key) = fragment(body, "wpkh") {
+            Ok(Descriptor::Wpkh(fragment_key(key, &[33])?))
+        } else if let Some(key) = fragment(body, "sh").and_then(|inner| fragment(inner, "wpkh")) {
+            Ok(Descriptor::ShWpkh(fragment_key(key, &[33])?))
+        } else if let Some(key) = fragment(body, "tr") {
+            if key.contains(',') {
+                // script trees are not supported
+                return Err(DescriptorError::Unsupported(body.to_owned()));
+            }
+            Ok(Descriptor::Tr(fragment_key(key, &[32, 33])?))
+        } else {
+            Err(DescriptorError::Unsupported(body.to_owned()))
+        }
+    }
+
+    /// The fixed script pubkey of an `addr()` or `raw()` descriptor. `None` for descriptors
+    /// of a key
+    pub fn script_pubkey(&self) -> Option<&ScriptPubkey> {
+        match self {
+            Descriptor::Addr { script_pubkey, .. } => Some(script_pubkey),
+            Descriptor::Raw(script_pubkey) => Some(script_pubkey),
+            _ => None,
+        }
+    }
+
+    /// The key of a `pkh()`, `wpkh()`, `sh(wpkh())` or `tr()` descriptor
+    pub fn key(&self) -> Option<&DescriptorKey> {
+        match self {
+            Descriptor::Pkh(key)
+            | Descriptor::Wpkh(key)
+            | Descriptor::ShWpkh(key)
+            | Descriptor::Tr(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// True if the descriptor derives a different script at each index, i.e. its key is an
+    /// extended key ending in `/*`
+    pub fn is_ranged(&self) -> bool {
+        matches!(self.key(), Some(key) if key.is_ranged())
+    }
+
+    /// True if the descriptor's key has more than one derivation branch
+    pub fn is_multipath(&self) -> bool {
+        matches!(self.key(), Some(key) if key.is_multipath())
+    }
+
+    /// Derive the script pubkey at `index`. Descriptors that are not ranged ignore the index.
+    ///
+    /// ## Errors
+    ///
+    /// - `DescriptorError::MultipathDerivation` if the descriptor is multipath
+    /// - `DescriptorError::Bip32Error` if a ranged key can't derive a child at `index`, e.g.
+    ///   because it is hardened
+    pub fn derive_script(&self, index: u32) -> DescriptorResult<ScriptPubkey> {
+        if self.is_multipath() {
+            return Err(DescriptorError::MultipathDerivation);
+        }
+        Ok(self.derive_scripts(index)?.remove(0))
+    }
+
+    /// Derive the script pubkey of each branch at `index`. Only multipath descriptors have more
+    /// than one. Descriptors that are not ranged ignore the index. Errors if a ranged key
+    /// can't derive a child at `index`, e.g. because it is hardened.
+    pub fn derive_scripts(&self, index: u32) -> DescriptorResult<Vec<ScriptPubkey>> {
+        Ok(match self {
+            Descriptor::Addr { script_pubkey, .. } | Descriptor::Raw(script_pubkey) => {
+                vec![script_pubkey.clone()]
+            }
+            Descriptor::Pkh(key) => key
+                .derive_pubkeys(index)?
+                .iter()
+                .map(|pubkey| pkh_script(pubkey).into())
+                .collect(),
+            Descriptor::Wpkh(key) => key
+                .derive_pubkeys(index)?
+                .iter()
+                .map(|pubkey| wpkh_script(pubkey).into())
+                .collect(),
+            Descriptor::ShWpkh(key) => key
+                .derive_pubkeys(index)?
+                .iter()
+                .map(|pubkey| ScriptPubkey::p2sh(&Script::from(wpkh_script(pubkey))))
+                .collect(),
+            Descriptor::Tr(key) => key
+                .derive_pubkeys(index)?
+                .iter()
+                .map(|pubkey| tr_script(pubkey).map(Into::into))
+                .collect::<DescriptorResult<Vec<ScriptPubkey>>>()?,
+        })
+    }
+
+    /// The distinct scripts derived at the indices in `range`, on every branch. Descriptors
+    /// that are not ranged produce their scripts once, unless `range` is empty. Indices that
+    /// can't be derived, i.e. hardened indices, are skipped.
+    pub fn script_set(&self, range: Range<u32>) -> HashSet<ScriptPubkey> {
+        let count = if self.is_ranged() { range.len() } else { 1 };
+        range
+            .take(count)
+            .filter_map(|i| self.derive_scripts(i).ok())
+            .flatten()
+            .collect()
+    }
+
+    /// Scan the scripts derived at indices `0..=max_index` for the address `addr`, parsed with
+    /// encoder `E`. Returns the first index that produces it on any branch, or `None` if the
+    /// address is not derived in that range or can't be parsed. Descriptors that are not ranged
+    /// only check index 0.
+    pub fn contains_address<E: BitcoinEncoderMarker>(
+        &self,
+        addr: &str,
+        max_index: u32,
+    ) -> Option<u32> {
+        let target = E::decode_address(&E::string_to_address(addr).ok()?).ok()?;
+        self.find_script(&target, max_index)
+    }
+
+    /// True if `addr`, parsed with encoder `E`, encodes the script derived at `index`. Use this
+    /// to confirm the receive address a hardware wallet displays. Unlike `contains_address`, only
+    /// `index` is checked, and parse and derivation failures are returned as errors. Multipath
+    /// descriptors match the script of any branch at `index`.
+    pub fn verify_address<E: BitcoinEncoderMarker>(
+        &self,
+        index: u32,
+        addr: &str,
+    ) -> DescriptorResult<bool> {
+        let target = E::decode_address(&E::string_to_address(addr)?)?;
+        Ok(self.derive_scripts(index)?.contains(&target))
+    }
+
+    /// The first index in `0..=max_index` that derives `script_pubkey` on any branch
+    fn find_script(&self, script_pubkey: &ScriptPubkey, max_index: u32) -> Option<u32> {
+        self.indices(max_index).find(|index| {
+            self.derive_scripts(*index)
+                .map(|scripts| scripts.contains(script_pubkey))
+                .unwrap_or(false)
+        })
+    }
+
+    /// The indices to scan when searching up to `max_index`. Ranged descriptors stop at the
+    /// last unhardened index
+    fn indices(&self, max_index: u32) -> RangeInclusive<u32> {
+        0..=if self.is_ranged() {
+            max_index.min(BIP32_HARDEN - 1)
+        } else {
+            0
+        }
+    }
+}
+
+/// Parse the key of a fragment that accepts pubkeys of the given `lengths`
+fn fragment_key(expression: &str, lengths: &[usize]) -> DescriptorResult<DescriptorKey> {
+    let key = DescriptorKey::parse(expression)?;
+    if !lengths.contains(&key.pubkey_length()) {
+        return Err(DescriptorError::InvalidKey(expression.to_owned()));
+    }
+    Ok(key)
+}
+
+/// A P2PKH script pubkey paying to `pubkey`
+fn pkh_script(pubkey: &[u8]) -> Vec<u8> {
+    let mut pkh = vec![0x76, 0xa9, 0x14];
+    pkh.extend(Hash160::digest_marked(pubkey).as_slice());
+    pkh.extend(&[0x88, 0xac]);
+    pkh
+}
+
+/// A P2WPKH script pubkey paying to `pubkey`
+fn wpkh_script(pubkey: &[u8]) -> Vec<u8> {
+    let mut wpkh = vec![0x00, 0x14];
+    wpkh.extend(Hash160::digest_marked(pubkey).as_slice());
+    wpkh
+}
+
+/// A P2TR script pubkey paying to the BIP86 output key of `pubkey`, which may be x-only or
+/// compressed. The internal key is the even-y point with the same x coordinate, and is tweaked
+/// by `tagged_hash("TapTweak", x)`, committing to an empty script tree.
+fn tr_script(pubkey: &[u8]) -> DescriptorResult<Vec<u8>> {
+    let mut internal = [0x02; 33];
+    internal[1..].copy_from_slice(&pubkey[pubkey.len() - 32..]);
+    let tweak = tagged_hash("TapTweak", &internal[1..]);
+    let output =
+        Secp256k1::static_ref().tweak_pubkey(&Pubkey::from_pubkey_array(internal)?, tweak)?;
+
+    let mut tr = vec![0x51, 0x20];
+    tr.extend(&output.pubkey_array()[1..]);
+    Ok(tr)
+}
+
+/// The descriptors of a wallet.
+///
+/// The scripts the set derives up to a `max_index` are cached, so that repeated lookups with the
+/// same `max_index`, e.g. while scanning the outputs of many transactions, derive each script
+/// only once. Looking up a different `max_index` replaces the cache.
+#[derive(Debug, Default)]
+pub struct DescriptorSet {
+    descriptors: Vec<Descriptor>,
+    cache: Mutex<Option<(u32, Arc<HashSet<ScriptPubkey>>)>>,
+}
+
+impl Clone for DescriptorSet {
+    fn clone(&self) -> Self {
+        Self {
+            descriptors: self.descriptors.clone(),
+            cache: Mutex::new(self.lock_cache().clone()),
+        }
+    }
+}
+
+impl PartialEq for DescriptorSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.descriptors == other.descriptors
+    }
+}
+
+impl Eq for DescriptorSet {}
+
+impl From<Vec<Descriptor>> for DescriptorSet {
+    fn from(descriptors: Vec<Descriptor>) -> Self {
+        Self::new(descriptors)
+    }
+}
+
+impl DescriptorSet {
+    /// Instantiate a set of descriptors
+    pub fn new(descriptors: Vec<Descriptor>) -> Self {
+        Self {
+            descriptors,
+            cache: Default::default(),
+        }
+    }
+
+    /// The descriptors in the set
+    pub fn descriptors(&self) -> &[Descriptor] {
+        &self.descriptors
+    }
+
+    /// Add a descriptor to the set. This clears the cached scripts
+    pub fn push(&mut self, descriptor: Descriptor) {
+        self.descriptors.push(descriptor);
+        *self.cache.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// The scripts derived by every descriptor at indices `0..=max_index`, on every branch.
+    /// Descriptors that are not ranged contribute only their index 0 scripts. The set is
+    /// derived on the first call with a given `max_index`, and cached.
+    pub fn scripts(&self, max_index: u32) -> Arc<HashSet<ScriptPubkey>> {
+        let mut cache = self.lock_cache();
+        match cache.as_ref() {
+            Some((cached, scripts)) if *cached == max_index => scripts.clone(),
+            _ => {
+                let scripts = Arc::new(self.derive_all(max_index));
+                *cache = Some((max_index, scripts.clone()));
+                scripts
+            }
+        }
+    }
+
+    /// True if any descriptor in the set derives `script_pubkey` at an index in
+    /// `0..=max_index`. Uses the cached scripts, see `scripts`.
+    pub fn contains(&self, script_pubkey: &ScriptPubkey, max_index: u32) -> bool {
+        self.scripts(max_index).contains(script_pubkey)
+    }
+
+    /// For each input of `tx`, the script pubkey of the output it spends, if that script is
+    /// derived by this set at indices `0..=max_index`.
+    ///
+    /// Inputs don't commit to their prevout scripts. Instead, the pubkey or script revealed by
+    /// the input's script sig or witness is hashed into P2PKH, P2SH, P2WPKH and P2WSH
+    /// candidates, which are compared to the derived scripts. Inputs that can't be matched,
+    /// e.g. taproot or bare script spends, produce `None`.
+    pub fn resolve_prevouts(&self, tx: &BitcoinTx, max_index: u32) -> Vec<Option<ScriptPubkey>> {
+        let scripts = self.scripts(max_index);
+        tx.inputs()
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let witness = tx.witnesses().get(i).map(Vec::as_slice).unwrap_or(&[]);
+                spend_candidates(input.script_sig.items(), witness)
+                    .into_iter()
+                    .find(|candidate| scripts.contains(candidate))
+            })
+            .collect()
+    }
+
+    /// Derive the scripts of every descriptor, bypassing the cache
+    fn derive_all(&self, max_index: u32) -> HashSet<ScriptPubkey> {
+        self.descriptors
+            .iter()
+            .flat_map(|d| {
+                d.indices(max_index)
+                    .filter_map(move |i| d.derive_scripts(i).ok())
+                    .flatten()
+            })
+            .collect()
+    }
+
+    /// Lock the cache. A panic while deriving leaves at most a stale entry for a `max_index`,
+    /// which is still correct, so a poisoned lock is recovered
+    fn lock_cache(&self) -> MutexGuard<'_, Option<(u32, Arc<HashSet<ScriptPubkey>>)>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The fee rate of `tx` in sat/vbyte, rounded down, when every input spends an output of one
+/// of the wallet's `known` transactions, and that output pays a script derived by
+/// `descriptors` at indices `0..=max_index`. Returns `None` if any input can't be resolved this
+/// way, or if the outputs exceed the inputs.
+///
+/// Descriptors describe scripts, not values, so they can't price an input on their own. The
+/// `known` transactions supply the value of each prevout, and the descriptors confirm that the
+/// prevout belongs to the wallet. The descriptors' scripts are cached by the set, so estimating
+/// many transactions with the same `max_index` derives them once.
+pub fn estimate_fee_from_descriptor(
+    tx: &BitcoinTx,
+    descriptors: &DescriptorSet,
+    known: &[BitcoinTx],
+    max_index: u32,
+) -> Option<u64> {
+    let scripts = descriptors.scripts(max_index);
+    let prevouts = tx
+        .inputs()
+        .iter()
+        .map(|input| {
+            known
+                .iter()
+                .find(|k| k.txid() == input.outpoint.txid)?
+                .outputs()
+                .get(input.outpoint.idx as usize)
+                .filter(|output| scripts.contains(&output.script_pubkey))
+                .cloned()
+        })
+        .collect::<Option<Vec<TxOut>>>()?;
+    Some(tx.fee(&prevouts).ok()? / tx.vsize() as u64)
+}
+
+/// The script pubkeys an input may be spending, based on the last push of its script sig and
+/// the last item of its witness
+fn spend_candidates(script_sig: &[u8], witness: &[WitnessStackItem]) -> Vec<ScriptPubkey> {
+    let mut last_push = None;
+    let mut cursor = 0;
+    while let Some((opcode, data, next)) = next_op(script_sig, cursor) {
+        if opcode > 0x4e {
+            break;
+        }
+        last_push = Some(data);
+        cursor = next;
+    }
+
+    let mut candidates = vec![];
+    if let Some(push) = last_push {
+        candidates.push(pkh_script(push).into());
+        candidates.push(ScriptPubkey::p2sh(&Script::from(push)));
+    }
+    if let Some(item) = witness.last() {
+        candidates.push(wpkh_script(item.items()).into());
+        candidates.push(ScriptPubkey::p2wsh(&Script::from(item)));
+    }
+    candidates
+}
+
+/// The argument of `name(...)`, if `s` is that fragment
+fn fragment<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?.strip_prefix('(')?.strip_suffix(')')
+}
+
+impl std::fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let body = match self {
+            Descriptor::Addr { address, .. } => address.to_descriptor(),
+            Descriptor::Raw(script_pubkey) => {
+                format!("raw({})", hex::encode(script_pubkey.items()))
+            }
+            Descriptor::Pkh(key) => format!("pkh({})", key),
+            Descriptor::Wpkh(key) => format!("wpkh({})", key),
+            Descriptor::ShWpkh(key) => format!("sh(wpkh({}))", key),
+            Descriptor::Tr(key) => format!("tr({})", key),
+        };
+        // parsed descriptors are always in the descriptor charset
+        let checksum = descriptor_checksum(&body).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}#{}", body, checksum)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        enc::encoder::{MainnetEncoder, TestnetEncoder},
+        hashes::TXID,
+        types::{
+            BitcoinOutpoint, BitcoinTxIn, LegacyTx, ScriptSig, Witness, WitnessTransaction,
+            WitnessTx,
+        },
+    };
+    use coins_core::enc::AddressEncoder;
+
+    // the BIP84 and BIP49 receive branches of the "abandon ... about" mnemonic
+    const WPKH_RECEIVE: &str = "wpkh([73c5da0a/84h/0h/0h]xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)";
+    const SH_WPKH_RECEIVE: &str = "sh(wpkh(xpub6C6nQwHaWbSrzs5tZ1q7m5R9cPK9eYpNMFesiXsYrgc1P8bvLLAet9JfHjYXKjToD8cBRswJXXbbFpXgwsswVPAZzKMa1jUp2kVkGVUaJa7/0/*))";
+
+    fn script_of(address: &str) -> ScriptPubkey {
+        MainnetEncoder::decode_address(&MainnetEncoder::string_to_address(address).unwrap())
+            .unwrap()
+    }
+
+    fn wpkh_receive() -> Descriptor {
+        Descriptor::parse::<MainnetEncoder>(WPKH_RECEIVE).unwrap()
+    }
+
+    fn ranged_set() -> DescriptorSet {
+        DescriptorSet::new(vec![
+            wpkh_receive(),
+            Descriptor::parse::<MainnetEncoder>(SH_WPKH_RECEIVE).unwrap(),
+        ])
+    }
+
+    fn input(outpoint: BitcoinOutpoint, script_sig: Vec<u8>) -> BitcoinTxIn {
+        BitcoinTxIn::new(outpoint, ScriptSig::from(script_sig), 0xffff_ffff)
+    }
+
+    fn witness(items: Vec<Vec<u8>>) -> Witness {
+        items.into_iter().map(Into::into).collect()
+    }
+
+    // a legacy transaction if there are no witnesses
+    fn tx(vin: Vec<BitcoinTxIn>, vout: Vec<TxOut>, witnesses: Vec<Witness>) -> BitcoinTx {
+        if witnesses.is_empty() {
+            LegacyTx::new(1, vin, vout, 0).unwrap().into()
+        } else {
+            <WitnessTx as WitnessTransaction>::new(2, vin, vout, witnesses, 0)
+                .unwrap()
+                .into()
+        }
+    }
+
+    #[test]
+    fn it_computes_checksums() {
+        // BIP380 test vector
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        match descriptor_checksum("raw(dé)") {
+            Err(DescriptorError::InvalidCharacter('é')) => {}
+            _ => assert!(false, "expected InvalidCharacter"),
+        }
+    }
+
+    #[test]
+    fn it_parses_addr_and_raw_descriptors() {
+        let cases = [
+            (
+                "addr(bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh)#rmnzvfky",
+                "00141bf8a1831db5443b42a44f30a121d1b616d011ab",
+            ),
+            (
+                "addr(1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH)#45hf9yxk",
+                "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+            ),
+            ("raw(6a0568656c6c6f)#yktqjuxu", "6a0568656c6c6f"),
+        ];
+        for (descriptor, script) in cases.iter() {
+            let parsed = Descriptor::parse::<MainnetEncoder>(descriptor).unwrap();
+            assert!(!parsed.is_ranged());
+            assert_eq!(
+                parsed.derive_script(0).unwrap(),
+                parsed.derive_script(7).unwrap()
+            );
+            assert_eq!(
+                hex::encode(parsed.derive_script(3).unwrap().items()),
+                *script
+            );
+            assert_eq!(parsed.to_string(), *descriptor);
+
+            // the checksum is optional
+            let body = descriptor.split('#').next().unwrap();
+            assert_eq!(Descriptor::parse::<MainnetEncoder>(body).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn it_parses_key_descriptors() {
+        // BIP44, BIP49 and BIP84 account xpubs of the "abandon ... about" mnemonic, and the
+        // address at receive index 0 of each
+        let cases = [
+            (
+                "pkh(xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj/0/*)#t3qu2qap",
+                "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA",
+            ),
+            (
+                "sh(wpkh(xpub6C6nQwHaWbSrzs5tZ1q7m5R9cPK9eYpNMFesiXsYrgc1P8bvLLAet9JfHjYXKjToD8cBRswJXXbbFpXgwsswVPAZzKMa1jUp2kVkGVUaJa7/0/*))#f2dndegj",
+                "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf",
+            ),
+            (
+                "wpkh([73c5da0a/84h/0h/0h]xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)#afwvtk2s",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+        ];
+        for (descriptor, address) in cases.iter() {
+            let parsed = Descriptor::parse::<MainnetEncoder>(descriptor).unwrap();
+            assert!(parsed.is_ranged());
+            assert!(parsed.script_pubkey().is_none());
+            assert_eq!(parsed.to_string(), *descriptor);
+            assert_eq!(
+                MainnetEncoder::encode_address(&parsed.derive_script(0).unwrap()).unwrap(),
+                MainnetEncoder::string_to_address(address).unwrap()
+            );
+            assert_ne!(
+                parsed.derive_script(0).unwrap(),
+                parsed.derive_script(1).unwrap()
+            );
+        }
+
+        let wpkh = Descriptor::parse::<MainnetEncoder>(cases[2].0).unwrap();
+        let origin = wpkh.key().unwrap().origin.as_ref().unwrap();
+        assert_eq!(origin.root, KeyFingerprint([0x73, 0xc5, 0xda, 0x0a]));
+        assert_eq!(
+            origin.path,
+            "m/84'/0'/0'".parse::<DerivationPath>().unwrap()
+        );
+        match wpkh.derive_script(BIP32_HARDEN) {
+            Err(DescriptorError::Bip32Error(Bip32Error::HardenedDerivationFailed { .. })) => {}
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+
+        // a fixed extended key is not ranged. This is the key at change index 0
+        let change = Descriptor::parse::<MainnetEncoder>(
+            "wpkh(xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/1/0)#rhhrnhkp",
+        )
+        .unwrap();
+        assert!(!change.is_ranged());
+        assert_eq!(
+            change.derive_script(0).unwrap(),
+            change.derive_script(9).unwrap()
+        );
+        assert_eq!(
+            MainnetEncoder::encode_address(&change.derive_script(0).unwrap()).unwrap(),
+            MainnetEncoder::string_to_address("bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_tr_descriptors() {
+        // BIP86 test vectors: the account xpub of the "abandon ... about" mnemonic, and its
+        // first receive and change addresses
+        let descriptor = "tr([73c5da0a/86h/0h/0h]xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ/0/*)#se42yddx";
+        let receive = Descriptor::parse::<MainnetEncoder>(descriptor).unwrap();
+        assert!(receive.is_ranged());
+        assert_eq!(receive.to_string(), descriptor);
+        assert_eq!(
+            hex::encode(receive.derive_script(0).unwrap().items()),
+            "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"
+        );
+        assert!(receive
+            .verify_address::<MainnetEncoder>(
+                0,
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+            )
+            .unwrap());
+        assert!(receive
+            .verify_address::<MainnetEncoder>(
+                1,
+                "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh"
+            )
+            .unwrap());
+
+        let change =
+            Descriptor::parse::<MainnetEncoder>(&descriptor.replace("/0/*)#se42yddx", "/1/*)"))
+                .unwrap();
+        assert!(change
+            .verify_address::<MainnetEncoder>(
+                0,
+                "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7"
+            )
+            .unwrap());
+
+        // the x-only internal key at receive index 0 has an odd y-coordinate
+        let x_only = Descriptor::parse::<MainnetEncoder>(
+            "tr(cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115)#7s05a9nk",
+        )
+        .unwrap();
+        assert!(!x_only.is_ranged());
+        assert_eq!(
+            x_only.derive_script(0).unwrap(),
+            receive.derive_script(0).unwrap()
+        );
+        let compressed = Descriptor::parse::<MainnetEncoder>(
+            "tr(03cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115)",
+        )
+        .unwrap();
+        assert_eq!(
+            compressed.derive_script(0).unwrap(),
+            receive.derive_script(0).unwrap()
+        );
+
+        // script trees are not supported, and x-only keys are only valid in tr()
+        match Descriptor::parse::<MainnetEncoder>(
+            "tr(cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115,raw(51))",
+        ) {
+            Err(DescriptorError::Unsupported(_)) => {}
+            _ => assert!(false, "expected Unsupported"),
+        }
+        match Descriptor::parse::<MainnetEncoder>(
+            "wpkh(cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115)",
+        ) {
+            Err(DescriptorError::InvalidKey(_)) => {}
+            _ => assert!(false, "expected InvalidKey"),
+        }
+    }
+
+    #[test]
+    fn it_parses_multipath_descriptors() {
+        let descriptor = "wpkh([73c5da0a/84h/0h/0h]xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/<0;1>/*)#qf45pmyh";
+        let multipath = Descriptor::parse::<MainnetEncoder>(descriptor).unwrap();
+        assert!(multipath.is_ranged());
+        assert!(multipath.is_multipath());
+        assert_eq!(multipath.to_string(), descriptor);
+
+        // receive and change index 0, then receive index 1
+        let receive = script_of("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        let change = script_of("bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el");
+        let second = "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g";
+        assert_eq!(
+            multipath.derive_scripts(0).unwrap(),
+            vec![receive.clone(), change.clone()]
+        );
+        match multipath.derive_script(0) {
+            Err(DescriptorError::MultipathDerivation) => {}
+            _ => assert!(false, "expected MultipathDerivation"),
+        }
+
+        let scripts = multipath.script_set(0..3);
+        assert_eq!(scripts.len(), 6);
+        assert!(scripts.contains(&receive));
+        assert!(scripts.contains(&change));
+        assert!(DescriptorSet::new(vec![multipath.clone()]).contains(&change, 0));
+        assert_eq!(
+            multipath.contains_address::<MainnetEncoder>(second, 10),
+            Some(1)
+        );
+        assert!(multipath
+            .verify_address::<MainnetEncoder>(0, "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el")
+            .unwrap());
+        assert!(!multipath
+            .verify_address::<MainnetEncoder>(0, second)
+            .unwrap());
+
+        let account = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        for path in ["<0>/*", "<0;0>/*", "<0;1>/<2;3>/*", "0<0;1>/*", "<0;1/*"].iter() {
+            match Descriptor::parse::<MainnetEncoder>(&format!("wpkh({}/{})", account, path)) {
+                Err(DescriptorError::InvalidKey(_)) => {}
+                _ => assert!(false, "expected InvalidKey for {}", path),
+            }
+        }
+        // three branches are allowed
+        let three =
+            Descriptor::parse::<MainnetEncoder>(&format!("wpkh({}/<0;1;2>/*)", account)).unwrap();
+        assert_eq!(three.derive_scripts(0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn it_finds_addresses_in_descriptors() {
+        let descriptor =
+            Descriptor::parse::<MainnetEncoder>("addr(1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH)")
+                .unwrap();
+        assert_eq!(
+            descriptor
+                .contains_address::<MainnetEncoder>("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", 100),
+            Some(0)
+        );
+        assert_eq!(
+            descriptor.contains_address::<MainnetEncoder>(
+                "bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh",
+                100
+            ),
+            None
+        );
+        assert_eq!(
+            descriptor.contains_address::<MainnetEncoder>("not an address", 100),
+            None
+        );
+
+        assert!(descriptor
+            .verify_address::<MainnetEncoder>(0, "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH")
+            .unwrap());
+        assert!(!descriptor
+            .verify_address::<MainnetEncoder>(0, "bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh")
+            .unwrap());
+        match descriptor.verify_address::<MainnetEncoder>(0, "not an address") {
+            Err(DescriptorError::EncodingError(_)) => {}
+            _ => assert!(false, "expected EncodingError"),
+        }
+    }
+
+    #[test]
+    fn it_finds_addresses_in_ranged_descriptors() {
+        let wpkh = wpkh_receive();
+        let sixth = "bc1qnpzzqjzet8gd5gl8l6gzhuc4s9xv0djt0rlu7a";
+
+        assert_eq!(wpkh.contains_address::<MainnetEncoder>(sixth, 100), Some(5));
+        assert_eq!(wpkh.contains_address::<MainnetEncoder>(sixth, 5), Some(5));
+        // the scan is bounded by max_index
+        assert_eq!(wpkh.contains_address::<MainnetEncoder>(sixth, 4), None);
+        // a change address is foreign to the receive branch
+        assert_eq!(
+            wpkh.contains_address::<MainnetEncoder>(
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+                100
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn it_verifies_ranged_addresses() {
+        let wpkh = wpkh_receive();
+        let second = "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g";
+
+        assert!(wpkh.verify_address::<MainnetEncoder>(1, second).unwrap());
+        assert!(!wpkh.verify_address::<MainnetEncoder>(0, second).unwrap());
+        assert!(!wpkh.verify_address::<MainnetEncoder>(2, second).unwrap());
+        // the change address at index 0
+        assert!(!wpkh
+            .verify_address::<MainnetEncoder>(0, "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el")
+            .unwrap());
+        match wpkh.verify_address::<MainnetEncoder>(BIP32_HARDEN, second) {
+            Err(DescriptorError::Bip32Error(Bip32Error::HardenedDerivationFailed { .. })) => {}
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+    }
+
+    #[test]
+    fn it_builds_script_sets() {
+        let raw = Descriptor::Raw(ScriptPubkey::from(vec![0x51]));
+        let addr = Descriptor::parse::<MainnetEncoder>("addr(1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH)")
+            .unwrap();
+
+        let raw_script = raw.script_pubkey().unwrap();
+        let addr_script = addr.script_pubkey().unwrap();
+
+        let scripts = raw.script_set(0..1000);
+        assert_eq!(scripts.len(), 1);
+        assert!(scripts.contains(raw_script));
+        assert!(raw.script_set(5..5).is_empty());
+
+        let set = DescriptorSet::new(vec![raw.clone(), addr.clone()]);
+        assert!(set.contains(raw_script, 0));
+        assert!(set.contains(addr_script, 0));
+        assert!(!set.contains(&ScriptPubkey::from(vec![0x52]), 0));
+        assert!(!DescriptorSet::default().contains(raw_script, 0));
+
+        // receive indices 0, 1 and 5
+        let wpkh = wpkh_receive();
+        let first = script_of("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        let second = script_of("bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g");
+        let sixth = script_of("bc1qnpzzqjzet8gd5gl8l6gzhuc4s9xv0djt0rlu7a");
+
+        let scripts = wpkh.script_set(0..5);
+        assert_eq!(scripts.len(), 5);
+        assert!(scripts.contains(&first));
+        assert!(scripts.contains(&second));
+        assert!(!scripts.contains(&sixth));
+        assert!(wpkh.script_set(5..6).contains(&sixth));
+        // hardened indices are skipped
+        assert_eq!(wpkh.script_set(BIP32_HARDEN - 1..BIP32_HARDEN + 1).len(), 1);
+
+        let mut set = DescriptorSet::new(vec![raw.clone(), wpkh]);
+        assert_eq!(set.scripts(5).len(), 7);
+        assert!(set.contains(&sixth, 5));
+        assert!(!set.contains(&sixth, 4));
+        assert!(set.contains(raw_script, 0));
+
+        // lookups with the same max_index share one derived set
+        assert!(Arc::ptr_eq(&set.scripts(5), &set.scripts(5)));
+        assert!(!set.contains(addr_script, 5));
+        set.push(addr.clone());
+        assert!(set.contains(addr_script, 5));
+        assert_eq!(set.clone(), set);
+    }
+
+    #[test]
+    fn it_resolves_prevouts_from_descriptors() {
+        let pubkey = |b: u8| [vec![0x02], vec![b; 32]].concat();
+        let hash = |data: &[u8]| Hash160::digest_marked(data).as_slice().to_vec();
+        let push = |data: &[u8]| [&[data.len() as u8][..], data].concat();
+        let sig = vec![0x30; 71];
+
+        let wpkh: ScriptPubkey = [&[0x00, 0x14][..], &hash(&pubkey(1))].concat().into();
+        let pkh: ScriptPubkey = [&[0x76, 0xa9, 0x14][..], &hash(&pubkey(2)), &[0x88, 0xac]]
+            .concat()
+            .into();
+        let sh = ScriptPubkey::p2sh(&Script::from(vec![0x51]));
+        let set = DescriptorSet::new(vec![
+            Descriptor::Raw(wpkh.clone()),
+            Descriptor::Raw(pkh.clone()),
+            Descriptor::Raw(sh.clone()),
+        ]);
+        assert_eq!(set.scripts(10).len(), 3);
+
+        let outpoint = |idx| BitcoinOutpoint::new(TXID::default(), idx);
+        let tx = tx(
+            vec![
+                input(outpoint(0), vec![]),
+                input(outpoint(1), [push(&sig), push(&pubkey(2))].concat()),
+                input(outpoint(2), push(&[0x51])),
+                input(outpoint(3), vec![]),
+            ],
+            vec![TxOut::new(1000, wpkh.clone())],
+            vec![
+                witness(vec![sig.clone(), pubkey(1)]),
+                vec![],
+                vec![],
+                witness(vec![sig, pubkey(3)]),
+            ],
+        );
+
+        assert_eq!(
+            set.resolve_prevouts(&tx, 10),
+            vec![Some(wpkh), Some(pkh), Some(sh), None]
+        );
+    }
+
+    #[test]
+    fn it_resolves_prevouts_from_ranged_descriptors() {
+        // the pubkeys at BIP84 receive indices 1 and 5, and BIP49 receive index 0
+        let wpkh_1 =
+            hex::decode("03e775fd51f0dfb8cd865d9ff1cca2a158cf651fe997fdc9fee9c1d3b5e995ea77")
+                .unwrap();
+        let wpkh_5 =
+            hex::decode("0284ae0efbe5cb35b24036d48d49d3c55a84fa75552bd162fc3a2a55e2997dd459")
+                .unwrap();
+        let sh_wpkh_0 =
+            hex::decode("039b3b694b8fc5b5e07fb069c783cac754f5d38c3e08bed1960e31fdb1dda35c24")
+                .unwrap();
+        let redeem_script = hex::decode("0014f990679acafe25c27615373b40bf22446d24ff44").unwrap();
+        let sig = vec![0x30; 71];
+
+        let outpoint = |idx| BitcoinOutpoint::new(TXID::default(), idx);
+        let tx = tx(
+            vec![
+                input(outpoint(0), vec![]),
+                input(outpoint(1), [&[0x16][..], &redeem_script].concat()),
+                input(outpoint(2), vec![]),
+            ],
+            vec![TxOut::new(1000, ScriptPubkey::from(vec![0x51]))],
+            vec![
+                witness(vec![sig.clone(), wpkh_1]),
+                witness(vec![sig.clone(), sh_wpkh_0]),
+                witness(vec![sig, wpkh_5]),
+            ],
+        );
+
+        let set = ranged_set();
+        let wpkh_1_script = script_of("bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g");
+        let sh_wpkh_0_script = script_of("37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf");
+        let wpkh_5_script = script_of("bc1qnpzzqjzet8gd5gl8l6gzhuc4s9xv0djt0rlu7a");
+        assert_eq!(
+            set.resolve_prevouts(&tx, 5),
+            vec![
+                Some(wpkh_1_script.clone()),
+                Some(sh_wpkh_0_script.clone()),
+                Some(wpkh_5_script)
+            ]
+        );
+        // index 5 is outside the scanned range
+        assert_eq!(
+            set.resolve_prevouts(&tx, 4),
+            vec![Some(wpkh_1_script), Some(sh_wpkh_0_script), None]
+        );
+    }
+
+    #[test]
+    fn it_estimates_fees_from_descriptors() {
+        let mine = ScriptPubkey::from(vec![0x51]);
+        let other = ScriptPubkey::from(vec![0x52]);
+        let set = DescriptorSet::new(vec![Descriptor::Raw(mine.clone())]);
+
+        let funding = tx(
+            vec![input(BitcoinOutpoint::new(TXID::default(), 0), vec![])],
+            vec![TxOut::new(10_000, mine.clone()), TxOut::new(5_000, other)],
+            vec![],
+        );
+        let txid = funding.txid();
+        let known = vec![funding];
+
+        let spend = |idx: u32| {
+            tx(
+                vec![input(BitcoinOutpoint::new(txid, idx), vec![])],
+                vec![TxOut::new(4_000, mine.clone())],
+                vec![],
+            )
+        };
+        // 6000 sats over a 61 vbyte transaction
+        assert_eq!(spend(0).vsize(), 61);
+        assert_eq!(
+            estimate_fee_from_descriptor(&spend(0), &set, &known, 10),
+            Some(98)
+        );
+        // a foreign output, a missing output, and an unknown transaction
+        assert_eq!(
+            estimate_fee_from_descriptor(&spend(1), &set, &known, 10),
+            None
+        );
+        assert_eq!(
+            estimate_fee_from_descriptor(&spend(2), &set, &known, 10),
+            None
+        );
+        assert_eq!(estimate_fee_from_descriptor(&spend(0), &set, &[], 10), None);
+    }
+
+    #[test]
+    fn it_estimates_fees_from_ranged_descriptors() {
+        // receive index 5
+        let mine = script_of("bc1qnpzzqjzet8gd5gl8l6gzhuc4s9xv0djt0rlu7a");
+        let funding = tx(
+            vec![input(BitcoinOutpoint::new(TXID::default(), 0), vec![])],
+            vec![TxOut::new(10_000, mine.clone())],
+            vec![],
+        );
+        let spend = tx(
+            vec![input(BitcoinOutpoint::new(funding.txid(), 0), vec![])],
+            vec![TxOut::new(4_000, mine)],
+            vec![],
+        );
+        let known = vec![funding];
+
+        let set = ranged_set();
+        // 6000 sats over an 82 vbyte transaction
+        assert_eq!(spend.vsize(), 82);
+        assert_eq!(
+            estimate_fee_from_descriptor(&spend, &set, &known, 5),
+            Some(73)
+        );
+        // index 5 is outside the scanned range
+        assert_eq!(estimate_fee_from_descriptor(&spend, &set, &known, 4), None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_descriptors() {
+        match Descriptor::parse::<MainnetEncoder>("raw(deadbeef)#89f8spxn") {
+            Err(DescriptorError::BadChecksum { expected, .. }) => assert_eq!(expected, "89f8spxm"),
+            _ => assert!(false, "expected BadChecksum"),
+        }
+        match Descriptor::parse::<TestnetEncoder>(
+            "addr(bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh)",
+        ) {
+            Err(DescriptorError::EncodingError(_)) => {}
+            _ => assert!(false, "expected EncodingError"),
+        }
+        match Descriptor::parse::<MainnetEncoder>("raw(4c)") {
+            Err(DescriptorError::MalformedScript) => {}
+            _ => assert!(false, "expected MalformedScript"),
+        }
+        match Descriptor::parse::<MainnetEncoder>("raw(zz)") {
+            Err(DescriptorError::HexError(_)) => {}
+            _ => assert!(false, "expected HexError"),
+        }
+        match Descriptor::parse::<MainnetEncoder>("wsh(raw(51))") {
+            Err(DescriptorError::Unsupported(_)) => {}
+            _ => assert!(false, "expected Unsupported"),
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_key_descriptors() {
+        let account = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        // a generator point with an uncompressed encoding
+        let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+        for descriptor in [
+            "pkh(02aa)".to_owned(),
+            format!("wpkh({})", uncompressed),
+            format!("wpkh([73c5da0a/84h{}/0/*)", account),
+            format!("wpkh({}/0*)", account),
+        ]
+        .iter()
+        {
+            match Descriptor::parse::<MainnetEncoder>(descriptor) {
+                Err(DescriptorError::InvalidKey(_)) => {}
+                _ => assert!(false, "expected InvalidKey for {}", descriptor),
+            }
+        }
+        match Descriptor::parse::<MainnetEncoder>(&format!("wpkh({}/0/*')", account)) {
+            Err(DescriptorError::Unsupported(_)) => {}
+            _ => assert!(false, "expected Unsupported"),
+        }
+        match Descriptor::parse::<MainnetEncoder>(&format!("wpkh({}/0h/*)", account)) {
+            Err(DescriptorError::Bip32Error(Bip32Error::HardenedDerivationFailed { .. })) => {}
+            _ => assert!(false, "expected HardenedDerivationFailed"),
+        }
+        match Descriptor::parse::<MainnetEncoder>("wpkh(xpub/0/*)") {
+            Err(DescriptorError::Bip32Error(_)) => {}
+            _ => assert!(false, "expected Bip32Error"),
+        }
+
+        // uncompressed keys are allowed in pkh
+        let pkh = Descriptor::parse::<MainnetEncoder>(&format!("pkh({})", uncompressed)).unwrap();
+        assert!(pkh
+            .verify_address::<MainnetEncoder>(0, "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm")
+            .unwrap());
+    }
+}
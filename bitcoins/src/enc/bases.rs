@@ -2,36 +2,28 @@
 //! addresses.
 
 use bech32::Error as BechError;
-use coins_core::enc::{
-    decode_bech32 as core_decode_bech32, encode_bech32 as core_encode_bech32, EncodingError,
-    EncodingResult,
-};
+use coins_core::enc::{EncodingError, EncodingResult};
 
-/// Encode a byte vector to bech32. This function expects `v` to be a witness program, and will
-/// return an `UnknownScriptType` if it does not meet the witness program format.
+use crate::types::program::WitnessProgram;
+
+/// Encode a byte vector to bech32 or bech32m, as appropriate for its witness version. This
+/// function expects `v` to be a witness program script pubkey, and will return an
+/// `UnknownScriptType` if it does not meet the witness program format.
 pub fn encode_bech32(hrp: &str, v: &[u8]) -> EncodingResult<String> {
-    if v.len() < 2 || v.len() > 40 {
+    if v.len() < 2 || v.len() > 42 {
         return Err(BechError::InvalidLength.into());
     }
 
-    let (version_and_len, payload) = v.split_at(2);
-    if version_and_len[0] > 16 || version_and_len[1] as usize != payload.len() {
-        return Err(EncodingError::UnknownScriptType);
-    };
-
-    core_encode_bech32(hrp, version_and_len[0], &payload)
+    WitnessProgram::from_script_pubkey(v)
+        .ok_or(EncodingError::UnknownScriptType)?
+        .encode(hrp)
 }
 
-/// Decode a witness program from a bech32 string. Caller specifies an expected HRP. If a
-/// different HRP is found, returns `WrongHRP`.
+/// Decode a witness program script pubkey from a bech32 or bech32m string. Caller specifies an
+/// expected HRP. If a different HRP is found, returns `WrongHRP`.
 pub fn decode_bech32(expected_hrp: &str, s: &str) -> EncodingResult<Vec<u8>> {
-    let (version, data) = core_decode_bech32(expected_hrp, s)?;
-
-    // Encode as witness program: witness version 0, then len(payload), then payload.
-    let mut s: Vec<u8> = vec![version, data.len() as u8];
-    s.extend(&data);
-
-    Ok(s)
+    let program = WitnessProgram::decode(expected_hrp, s)?;
+    Ok(program.to_script_pubkey().items().to_vec())
 }
 
 #[cfg(test)]
@@ -58,4 +50,30 @@ mod test {
             assert_eq!(*addr, reencoded);
         }
     }
+
+    #[test]
+    fn it_should_encode_and_decode_bech32m() {
+        let hrp = "bc";
+        let cases = [
+            (
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            ),
+            ("6002751e", "bc1sw50qgdz25j"),
+        ];
+        for (script, addr) in cases.iter() {
+            let script = hex::decode(script).unwrap();
+            assert_eq!(encode_bech32(&hrp, &script).unwrap(), *addr);
+            assert_eq!(decode_bech32(&hrp, addr).unwrap(), script);
+        }
+
+        // v1 program with a bech32 checksum
+        match decode_bech32(
+            &hrp,
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+        ) {
+            Err(EncodingError::SegwitVersionError(1)) => {}
+            _ => assert!(false, "expected SegwitVersionError"),
+        }
+    }
 }
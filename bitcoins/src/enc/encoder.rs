@@ -1,4 +1,4 @@
-//! Defines parameterized Bitcoin encoders for Mainnet, Testnet, and Signet.
+//! Defines parameterized Bitcoin encoders for Mainnet, Testnet, Testnet4, and Signet.
 
 use std::marker::PhantomData;
 
@@ -10,7 +10,10 @@ use coins_core::{
 
 use crate::{
     enc::bases::{decode_bech32, encode_bech32},
-    types::script::{ScriptPubkey, ScriptType},
+    types::{
+        program::WitnessProgram,
+        script::{ScriptPubkey, ScriptType},
+    },
 };
 
 /// The available Bitcoin Address types, implemented as a type enum around strings.
@@ -24,6 +27,8 @@ pub enum Address {
     WPKH(String),
     /// Witness Pay to Scripthash
     WSH(String),
+    /// A witness program of version 1 or above, e.g. Taproot. Encoded as bech32m
+    Witness(String),
 }
 
 impl AsRef<str> for Address {
@@ -33,6 +38,7 @@ impl AsRef<str> for Address {
             Address::SH(s) => &s,
             Address::WPKH(s) => &s,
             Address::WSH(s) => &s,
+            Address::Witness(s) => &s,
         }
     }
 }
@@ -45,6 +51,7 @@ impl Address {
             Address::SH(s) => s.clone(),
             Address::WPKH(s) => s.clone(),
             Address::WSH(s) => s.clone(),
+            Address::Witness(s) => s.clone(),
         }
     }
 
@@ -77,62 +84,97 @@ pub trait BitcoinEncoderMarker:
 #[derive(Debug, Clone, PartialEq)]
 pub struct BitcoinEncoder<P: NetworkParams>(PhantomData<fn(P) -> P>);
 
+fn encode_with_params(
+    hrp: &str,
+    pkh_version: u8,
+    sh_version: u8,
+    s: &ScriptPubkey,
+) -> EncodingResult<Address> {
+    match s.standard_type() {
+        ScriptType::PKH(payload) => {
+            // s.items contains the op codes. we want only the pkh
+            Ok(Address::PKH(encode_base58(pkh_version, payload.as_slice())))
+        }
+        ScriptType::SH(payload) => {
+            // s.items contains the op codes. we want only the sh
+            Ok(Address::SH(encode_base58(sh_version, payload.as_slice())))
+        }
+        ScriptType::WSH(_) => Ok(Address::WSH(encode_bech32(hrp, &s.items())?)),
+        ScriptType::WPKH(_) => Ok(Address::WPKH(encode_bech32(hrp, &s.items())?)),
+        ScriptType::OP_RETURN(_) => Err(EncodingError::NullDataScript),
+        ScriptType::NonStandard => match s.witness_program() {
+            Some(program) => Ok(Address::Witness(program.encode(hrp)?)),
+            None => Err(EncodingError::UnknownScriptType),
+        },
+    }
+}
+
+fn decode_with_params(
+    hrp: &str,
+    pkh_version: u8,
+    sh_version: u8,
+    addr: &Address,
+) -> EncodingResult<ScriptPubkey> {
+    match &addr {
+        Address::PKH(s) => decode_base58(pkh_version, s).map(|v| v.into()),
+        Address::SH(s) => decode_base58(sh_version, s).map(|v| v.into()),
+        Address::WPKH(s) | Address::WSH(s) | Address::Witness(s) => {
+            decode_bech32(hrp, &s).map(|v| v.into())
+        }
+    }
+}
+
+fn parse_with_params(
+    hrp: &str,
+    pkh_version: u8,
+    sh_version: u8,
+    string: &str,
+) -> EncodingResult<Address> {
+    let s = string.to_owned();
+    if s.starts_with(hrp) {
+        let program = match WitnessProgram::decode(hrp, &s) {
+            Ok(program) => program,
+            Err(EncodingError::SegwitVersionError(_)) | Err(EncodingError::InvalidSizeError) => {
+                return Err(EncodingError::UnknownScriptType)
+            }
+            Err(e) => return Err(e),
+        };
+        match (program.version(), program.program().len()) {
+            (0, 20) => Ok(Address::WPKH(s)),
+            (0, 32) => Ok(Address::WSH(s)),
+            _ => Ok(Address::Witness(s)),
+        }
+    } else if decode_base58(pkh_version, &s).is_ok() {
+        Ok(Address::PKH(s))
+    } else if decode_base58(sh_version, &s).is_ok() {
+        Ok(Address::SH(s))
+    } else {
+        Err(EncodingError::UnknownScriptType)
+    }
+}
+
 impl<P: NetworkParams> AddressEncoder for BitcoinEncoder<P> {
     type Address = Address;
     type Error = EncodingError;
     type RecipientIdentifier = ScriptPubkey;
 
     fn encode_address(s: &ScriptPubkey) -> EncodingResult<Address> {
-        match s.standard_type() {
-            ScriptType::PKH(payload) => {
-                // s.items contains the op codes. we want only the pkh
-                Ok(Address::PKH(encode_base58(
-                    P::PKH_VERSION,
-                    payload.as_slice(),
-                )))
-            }
-            ScriptType::SH(payload) => {
-                // s.items contains the op codes. we want only the sh
-                Ok(Address::SH(encode_base58(P::SH_VERSION, payload.as_slice())))
-            }
-            ScriptType::WSH(_) => Ok(Address::WSH(encode_bech32(P::HRP, &s.items())?)),
-            ScriptType::WPKH(_) => Ok(Address::WPKH(encode_bech32(P::HRP, &s.items())?)),
-            ScriptType::OP_RETURN(_) => Err(EncodingError::NullDataScript),
-            ScriptType::NonStandard => Err(EncodingError::UnknownScriptType),
-        }
+        encode_with_params(P::HRP, P::PKH_VERSION, P::SH_VERSION, s)
     }
 
     fn decode_address(addr: &Address) -> EncodingResult<ScriptPubkey> {
-        match &addr {
-            Address::PKH(s) => decode_base58(P::PKH_VERSION, s).map(|v| v.into()),
-            Address::SH(s) => decode_base58(P::SH_VERSION, s).map(|v| v.into()),
-            Address::WPKH(s) | Address::WSH(s) => decode_bech32(P::HRP, &s).map(|v| v.into()),
-        }
+        decode_with_params(P::HRP, P::PKH_VERSION, P::SH_VERSION, addr)
     }
 
     fn string_to_address(string: &str) -> EncodingResult<Address> {
-        let s = string.to_owned();
-        if s.starts_with(P::HRP) {
-            let result = decode_bech32(P::HRP, &s)?;
-            match result.len() {
-                22 => Ok(Address::WPKH(s)),
-                34 => Ok(Address::WSH(s)),
-                _ => Err(EncodingError::UnknownScriptType),
-            }
-        } else if decode_base58(P::PKH_VERSION, &s).is_ok() {
-            Ok(Address::PKH(s))
-        } else if decode_base58(P::SH_VERSION, &s).is_ok() {
-            Ok(Address::SH(s))
-        } else {
-            Err(EncodingError::UnknownScriptType)
-        }
+        parse_with_params(P::HRP, P::PKH_VERSION, P::SH_VERSION, string)
     }
 }
 
 impl<P: NetworkParams> BitcoinEncoderMarker for BitcoinEncoder<P> {}
 
 /// A param struct for Bitcoin Mainnet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Main;
 
 impl NetworkParams for Main {
@@ -142,7 +184,7 @@ impl NetworkParams for Main {
 }
 
 /// A param struct for Bitcoin Tesnet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Test;
 
 impl NetworkParams for Test {
@@ -151,8 +193,20 @@ impl NetworkParams for Test {
     const SH_VERSION: u8 = 0xc4;
 }
 
+/// A param struct for Bitcoin Testnet4. Addresses are encoded exactly as on testnet3, with the
+/// same version bytes and `tb` HRP. It is a distinct type so that tooling can target testnet4
+/// explicitly and tell the networks apart, e.g. when choosing defaults for a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test4;
+
+impl NetworkParams for Test4 {
+    const HRP: &'static str = Test::HRP;
+    const PKH_VERSION: u8 = Test::PKH_VERSION;
+    const SH_VERSION: u8 = Test::SH_VERSION;
+}
+
 /// A param struct for Bitcoin Signet
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Sig;
 
 impl NetworkParams for Sig {
@@ -161,12 +215,58 @@ impl NetworkParams for Sig {
     const SH_VERSION: u8 = 0x57;
 }
 
+/// Address parameters for a bitcoin-like network, supplied at runtime. Use these in place of a
+/// `NetworkParams` type when a network's params are only known at runtime, e.g. read from
+/// config. Encoding and validation behave exactly as in `BitcoinEncoder`.
+///
+/// `AddressEncoder` is implemented with associated functions, so runtime params can't implement
+/// it. Instead this type exposes the same operations as methods.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomParams {
+    /// The BECH32 HRP. "bc" for mainnet.
+    pub bech32_hrp: String,
+    /// The Legacy PKH base58check version byte. 0x00 for mainnet.
+    pub p2pkh_version: u8,
+    /// The Legacy SH base58check version byte. 0x05 for mainnet.
+    pub p2sh_version: u8,
+}
+
+impl CustomParams {
+    /// Encode a script as an address using these params.
+    pub fn encode_address(&self, s: &ScriptPubkey) -> EncodingResult<Address> {
+        encode_with_params(&self.bech32_hrp, self.p2pkh_version, self.p2sh_version, s)
+    }
+
+    /// Decode a script from an address using these params.
+    pub fn decode_address(&self, addr: &Address) -> EncodingResult<ScriptPubkey> {
+        decode_with_params(
+            &self.bech32_hrp,
+            self.p2pkh_version,
+            self.p2sh_version,
+            addr,
+        )
+    }
+
+    /// Attempt to convert a string into an address using these params.
+    pub fn string_to_address(&self, string: &str) -> EncodingResult<Address> {
+        parse_with_params(
+            &self.bech32_hrp,
+            self.p2pkh_version,
+            self.p2sh_version,
+            string,
+        )
+    }
+}
+
 /// An encoder for Bitcoin Mainnet
 pub type MainnetEncoder = BitcoinEncoder<Main>;
 
 /// An encoder for Bitcoin Tesnet
 pub type TestnetEncoder = BitcoinEncoder<Test>;
 
+/// An encoder for Bitcoin Testnet4. Produces the same addresses as `TestnetEncoder`
+pub type Testnet4Encoder = BitcoinEncoder<Test4>;
+
 /// An encoder for Bitcoin Signet
 pub type SignetEncoder = BitcoinEncoder<Sig>;
 
@@ -195,6 +295,12 @@ mod test {
                 "3HXNFmJpxjgTVFN35Y9f6Waje5YFsLEQZ2".to_owned(),
                 Address::SH("3HXNFmJpxjgTVFN35Y9f6Waje5YFsLEQZ2".to_owned()),
             ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0".to_owned(),
+                Address::Witness(
+                    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0".to_owned(),
+                ),
+            ),
         ];
         for case in cases.iter() {
             assert_eq!(MainnetEncoder::string_to_address(&case.0).unwrap(), case.1);
@@ -204,6 +310,7 @@ mod test {
             "hello",
             "this isn't a real address",
             "bc10pu8s7rc0pu8s7rc0putt44am", // valid bech32, bad length
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd", // v1 with bech32 checksum
         ];
         for case in errors.iter() {
             match MainnetEncoder::string_to_address(case) {
@@ -245,10 +352,26 @@ mod test {
                 ),
                 Address::WPKH("bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh".to_owned()),
             ),
+            (
+                ScriptPubkey::new(
+                    hex::decode(
+                        "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                    )
+                    .unwrap(),
+                ),
+                Address::Witness(
+                    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0".to_owned(),
+                ),
+            ),
         ];
         for case in cases.iter() {
             assert_eq!(MainnetEncoder::encode_address(&case.0).unwrap(), case.1);
         }
+        let taproot = &cases[cases.len() - 1];
+        assert_eq!(
+            MainnetEncoder::decode_address(&taproot.1).unwrap(),
+            taproot.0
+        );
         let errors = [
             (ScriptPubkey::new(hex::decode("01201bf8a1831db5443b42a44f30a121d1b616d011ab15df62b588722a845864cc99").unwrap())), // wrong witness program version
             (ScriptPubkey::new(hex::decode("a914e88869b88866281ab166541ad8aafba8f8aba47a89").unwrap())), // wrong last byte
@@ -268,6 +391,61 @@ mod test {
         }
     }
 
+    #[test]
+    fn it_encodes_with_custom_params() {
+        let mainnet = CustomParams {
+            bech32_hrp: Main::HRP.to_owned(),
+            p2pkh_version: Main::PKH_VERSION,
+            p2sh_version: Main::SH_VERSION,
+        };
+        let scripts = [
+            "76a9140e5c3c8d420c7f11e88d76f7b860d471e6517a4488ac",
+            "a914e88869b88866281ab166541ad8aafba8f8aba47a87",
+            "00141bf8a1831db5443b42a44f30a121d1b616d011ab",
+        ];
+        for script in scripts.iter() {
+            let script = ScriptPubkey::new(hex::decode(script).unwrap());
+            let address = mainnet.encode_address(&script).unwrap();
+            assert_eq!(address, MainnetEncoder::encode_address(&script).unwrap());
+            assert_eq!(
+                mainnet.string_to_address(address.as_ref()).unwrap(),
+                address
+            );
+            assert_eq!(mainnet.decode_address(&address).unwrap(), script);
+        }
+
+        // Litecoin params reject bitcoin addresses
+        let litecoin = CustomParams {
+            bech32_hrp: "ltc".to_owned(),
+            p2pkh_version: 0x30,
+            p2sh_version: 0x32,
+        };
+        let address = Address::PKH("12JvxPk4mT4PKMVHuHc1aQGBZpotQWQwF6".to_owned());
+        assert!(litecoin.decode_address(&address).is_err());
+        assert!(litecoin.string_to_address(address.as_ref()).is_err());
+        let address = Address::WPKH("bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh".to_owned());
+        assert!(litecoin.decode_address(&address).is_err());
+    }
+
+    #[test]
+    fn it_encodes_testnet4_addresses_as_testnet() {
+        let scripts = [
+            "76a9140e5c3c8d420c7f11e88d76f7b860d471e6517a4488ac",
+            "a914e88869b88866281ab166541ad8aafba8f8aba47a87",
+            "00141bf8a1831db5443b42a44f30a121d1b616d011ab",
+        ];
+        for script in scripts.iter() {
+            let script = ScriptPubkey::new(hex::decode(script).unwrap());
+            let address = Testnet4Encoder::encode_address(&script).unwrap();
+            assert_eq!(address, TestnetEncoder::encode_address(&script).unwrap());
+            assert_eq!(
+                Testnet4Encoder::string_to_address(address.as_ref()).unwrap(),
+                address
+            );
+            assert_eq!(Testnet4Encoder::decode_address(&address).unwrap(), script);
+        }
+    }
+
     #[test]
     fn it_allows_you_to_unwrap_strings_from_addresses() {
         let cases = [
@@ -289,6 +467,17 @@ mod test {
                 "bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh".to_owned(),
                 Address::WPKH("bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh".to_owned()),
             ),
+            (
+                ScriptPubkey::new(
+                    hex::decode(
+                        "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                    )
+                    .unwrap(),
+                ),
+                Address::Witness(
+                    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0".to_owned(),
+                ),
+            ),
         ];
         for case in cases.iter() {
             assert_eq!(case.1.as_string(), case.0);
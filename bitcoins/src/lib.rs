@@ -6,9 +6,16 @@
 #![warn(unused_extern_crates)]
 
 pub mod builder;
+pub mod descriptor;
 pub mod enc;
 pub mod hashes;
+pub mod merkle;
 pub mod nets;
+pub mod package;
+pub mod payjoin;
+pub mod quick;
+pub mod rbf;
+pub mod select;
 pub mod types;
 
 /// Common re-exports
@@ -0,0 +1,74 @@
+//! Merkle trees over transaction ids, as used in block headers and BIP141 witness commitments.
+//!
+//! All digests are in internal byte order, i.e. as serialized, not as displayed by block
+//! explorers.
+
+use coins_core::hashes::{Digest, Hash256};
+
+/// The 4-byte header that precedes the witness commitment in the coinbase `OP_RETURN` output
+pub const WITNESS_COMMITMENT_HEADER: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Hash256::new().chain(left).chain(right).finalize());
+    digest
+}
+
+/// Calculate the merkle root of a list of leaves. Levels with an odd number of nodes pair the
+/// last node with itself. The root of an empty list is all zeros.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.last().expect("chunks are not empty")))
+            .collect();
+    }
+    level[0]
+}
+
+/// Calculate the BIP141 witness commitment of a block, given the wtxids of its transactions.
+///
+/// The coinbase wtxid is defined to be all zeros, so the first entry of `txids_with_witness`
+/// is ignored and may hold any value. `witness_reserved_value` is the single item of the
+/// coinbase input's witness. The commitment is `Hash256(witness_root || reserved_value)`, and is
+/// placed in a coinbase output after `WITNESS_COMMITMENT_HEADER`.
+pub fn compute_witness_commitment(
+    txids_with_witness: &[[u8; 32]],
+    witness_reserved_value: [u8; 32],
+) -> [u8; 32] {
+    let mut leaves = txids_with_witness.to_vec();
+    if let Some(coinbase) = leaves.first_mut() {
+        *coinbase = [0u8; 32];
+    }
+    hash_pair(&merkle_root(&leaves), &witness_reserved_value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_computes_witness_commitments() {
+        // Every block containing only a coinbase, with a zero reserved value, commits to this.
+        // e.g. the coinbase of regtest blocks and of empty mainnet blocks
+        let commitment = compute_witness_commitment(&[[0xff; 32]], [0u8; 32]);
+        assert_eq!(
+            hex::encode(commitment),
+            "e2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9"
+        );
+
+        let wtxids = [[0xff; 32], [1; 32], [2; 32]];
+        assert_eq!(
+            hex::encode(merkle_root(&[[0; 32], [1; 32], [2; 32]])),
+            "d6384640762f797ede7e7f13839222f9452272809932cc6089f701331df4552d"
+        );
+        assert_eq!(
+            hex::encode(compute_witness_commitment(&wtxids, [7; 32])),
+            "51a15c95361d4b4133f8cf1b8ac7dc9a2fdbf8dd4658d4c59fde169292173e71"
+        );
+    }
+}
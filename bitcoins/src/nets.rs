@@ -36,7 +36,10 @@ use coins_core::{
 
 use crate::{
     builder::BitcoinTxBuilder,
-    enc::encoder::{Address, BitcoinEncoderMarker, MainnetEncoder, SignetEncoder, TestnetEncoder},
+    enc::encoder::{
+        Address, BitcoinEncoderMarker, MainnetEncoder, SignetEncoder, Testnet4Encoder,
+        TestnetEncoder,
+    },
     types::{
         BitcoinTransaction, BitcoinTx, BitcoinTxIn, ScriptPubkey, TxOut, WitnessTransaction,
         WitnessTx,
@@ -51,7 +54,7 @@ pub trait BitcoinNetwork: Network {
 }
 
 /// A newtype for Bitcoin networks, parameterized by an encoder. We change the encoder to
-/// differentiate between main, test, and signet.
+/// differentiate between main, test, testnet4, and signet.
 #[derive(Debug)]
 pub struct Bitcoin<T: AddressEncoder>(PhantomData<fn(T) -> T>);
 
@@ -82,6 +85,9 @@ pub type BitcoinMainnet = Bitcoin<MainnetEncoder>;
 /// A fully-parameterized BitcoinTestnet. This is the main interface for accessing the library.
 pub type BitcoinTestnet = Bitcoin<TestnetEncoder>;
 
+/// A fully-parameterized BitcoinTestnet4. Its addresses are identical to testnet3's.
+pub type BitcoinTestnet4 = Bitcoin<Testnet4Encoder>;
+
 /// A fully-parameterized BitcoinSignet. This is the main interface for accessing the library.
 pub type BitcoinSignet = Bitcoin<SignetEncoder>;
 
@@ -0,0 +1,95 @@
+//! Package fee rates. Bitcoin Core's mempool evaluates an unconfirmed transaction together with
+//! its unconfirmed ancestors, so a high-fee child can pull a low-fee parent into a block (CPFP).
+//! The package fee rate is the total fee divided by the total vsize.
+
+use crate::types::{BitcoinTransaction, BitcoinTx, TxError, TxResult};
+
+/// The aggregate fee and size of a package of related transactions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PackageInfo {
+    /// The number of transactions in the package
+    pub count: usize,
+    /// The total fee paid by the package
+    pub fee: u64,
+    /// The total vsize of the package
+    pub vsize: u64,
+}
+
+impl PackageInfo {
+    /// Aggregate a package from transactions and the fees they pay. Transactions must be
+    /// signed, or vsizes will be underestimated.
+    ///
+    /// Errors with `TxError::ValueOverflow` if the total fee exceeds `u64::MAX`.
+    pub fn new(txs: &[(BitcoinTx, u64)]) -> TxResult<Self> {
+        txs.iter().try_fold(Self::default(), |info, (tx, fee)| {
+            Ok(Self {
+                count: info.count + 1,
+                fee: info.fee.checked_add(*fee).ok_or(TxError::ValueOverflow)?,
+                vsize: info.vsize + tx.vsize() as u64,
+            })
+        })
+    }
+
+    /// The package fee rate in sat/vbyte. 0 for an empty package
+    pub fn fee_rate(&self) -> f64 {
+        if self.vsize == 0 {
+            return 0.0;
+        }
+        self.fee as f64 / self.vsize as f64
+    }
+}
+
+/// The fee rate, in sat/vbyte, of a package of transactions and the fees they pay. Errors with
+/// `TxError::ValueOverflow` if the total fee exceeds `u64::MAX`.
+pub fn package_fee_rate(txs: &[(BitcoinTx, u64)]) -> TxResult<f64> {
+    Ok(PackageInfo::new(txs)?.fee_rate())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        hashes::TXID,
+        types::{BitcoinOutpoint, BitcoinTxIn, ScriptPubkey, ScriptSig, TxOut},
+    };
+    use coins_core::types::tx::Transaction;
+
+    fn tx(outputs: usize) -> BitcoinTx {
+        let vin = vec![BitcoinTxIn::new(
+            BitcoinOutpoint::new(TXID::default(), 0),
+            ScriptSig::null(),
+            0xffff_fffd,
+        )];
+        let vout = vec![TxOut::new(1000, ScriptPubkey::new(vec![0x51; 22])); outputs];
+        BitcoinTx::new(2, vin, vout, 0).unwrap()
+    }
+
+    #[test]
+    fn it_computes_package_fee_rates() {
+        let parent = tx(1);
+        let child = tx(3);
+        let parent_vsize = parent.vsize() as u64;
+        let child_vsize = child.vsize() as u64;
+
+        // a parent paying 1 sat/vbyte is pulled up by a child paying 9
+        let package = vec![(parent, parent_vsize), (child, 9 * child_vsize)];
+        let info = PackageInfo::new(&package).unwrap();
+        assert_eq!(info.count, 2);
+        assert_eq!(info.fee, parent_vsize + 9 * child_vsize);
+        assert_eq!(info.vsize, parent_vsize + child_vsize);
+
+        let expected =
+            (parent_vsize + 9 * child_vsize) as f64 / (parent_vsize + child_vsize) as f64;
+        assert!((package_fee_rate(&package).unwrap() - expected).abs() < 1e-9);
+        assert!(package_fee_rate(&package).unwrap() > 1.0);
+        assert!(package_fee_rate(&package[1..]).unwrap() > package_fee_rate(&package).unwrap());
+
+        assert_eq!(package_fee_rate(&[]).unwrap(), 0.0);
+
+        let overflowing = vec![(tx(1), u64::MAX), (tx(1), 1)];
+        match PackageInfo::new(&overflowing) {
+            Err(TxError::ValueOverflow) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+    }
+}
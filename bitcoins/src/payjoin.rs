@@ -0,0 +1,217 @@
+//! Sender-side checks for PayJoin (BIP78) proposals.
+//!
+//! In a PayJoin, the sender gives the receiver a signed original transaction. The receiver
+//! returns a proposal that adds their own inputs, and may reduce the sender's outputs to cover
+//! the extra fee. Before signing the proposal, the sender must check that the receiver hasn't
+//! changed anything else.
+//!
+//! These checks cover the transaction structure only. Checking the proposal's fee rate requires
+//! the receiver's prevouts, and is left to the caller.
+
+use thiserror::Error;
+
+use crate::types::{BitcoinOutpoint, BitcoinTx};
+use coins_core::types::tx::Transaction;
+
+/// Ways a PayJoin proposal may improperly modify the original transaction
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PayJoinError {
+    /// The proposal changed the transaction version
+    #[error("Proposal changed the version from {original} to {proposal}")]
+    VersionChanged {
+        /// The original version
+        original: u32,
+        /// The proposal version
+        proposal: u32,
+    },
+
+    /// The proposal changed the transaction locktime
+    #[error("Proposal changed the locktime from {original} to {proposal}")]
+    LocktimeChanged {
+        /// The original locktime
+        original: u32,
+        /// The proposal locktime
+        proposal: u32,
+    },
+
+    /// The proposal dropped one of the sender's inputs
+    #[error("Proposal is missing sender input {0:?}")]
+    MissingSenderInput(BitcoinOutpoint),
+
+    /// The proposal changed the sequence number of one of the sender's inputs
+    #[error("Proposal changed the sequence of sender input {0:?}")]
+    SequenceChanged(BitcoinOutpoint),
+
+    /// The receiver added an input that belongs to the sender
+    #[error("Receiver added sender input {0:?}")]
+    SenderInputAdded(BitcoinOutpoint),
+
+    /// The proposal dropped one of the original outputs
+    #[error("Proposal is missing original output {0}")]
+    MissingOutput(usize),
+
+    /// The proposal reduced the sender's outputs by more than the sender agreed to contribute
+    #[error("Proposal takes {contributed} sats from sender outputs. Max is {max}")]
+    FeeContributionExceeded {
+        /// The total reduction in original output values
+        contributed: u64,
+        /// The maximum the sender agreed to contribute
+        max: u64,
+    },
+}
+
+/// Type alias for results with a `PayJoinError`
+pub type PayJoinResult<T> = Result<T, PayJoinError>;
+
+/// Check that a PayJoin `proposal` preserves the sender's part of `original`.
+///
+/// `sender_inputs` lists every outpoint the sender owns, not only those spent by `original`.
+/// This lets us detect a receiver sneaking another sender UTXO into the proposal. Original
+/// outputs are matched to proposal outputs by script pubkey. They may decrease in value, but
+/// the total decrease must not exceed `max_fee_contribution`. This is BIP78's
+/// `maxadditionalfeecontribution`.
+///
+/// Outputs may be reordered, and the receiver may add outputs and inputs of their own.
+pub fn validate_payjoin_modification(
+    original: &BitcoinTx,
+    proposal: &BitcoinTx,
+    sender_inputs: &[BitcoinOutpoint],
+    max_fee_contribution: u64,
+) -> PayJoinResult<()> {
+    if original.version() != proposal.version() {
+        return Err(PayJoinError::VersionChanged {
+            original: original.version(),
+            proposal: proposal.version(),
+        });
+    }
+    if original.locktime() != proposal.locktime() {
+        return Err(PayJoinError::LocktimeChanged {
+            original: original.locktime(),
+            proposal: proposal.locktime(),
+        });
+    }
+
+    for input in original.inputs().iter() {
+        match proposal
+            .inputs()
+            .iter()
+            .find(|i| i.outpoint == input.outpoint)
+        {
+            None => return Err(PayJoinError::MissingSenderInput(input.outpoint)),
+            Some(i) if i.sequence != input.sequence => {
+                return Err(PayJoinError::SequenceChanged(input.outpoint))
+            }
+            _ => {}
+        }
+    }
+    for input in proposal.inputs().iter() {
+        let in_original = original
+            .inputs()
+            .iter()
+            .any(|i| i.outpoint == input.outpoint);
+        if !in_original && sender_inputs.contains(&input.outpoint) {
+            return Err(PayJoinError::SenderInputAdded(input.outpoint));
+        }
+    }
+
+    let mut matched = vec![false; proposal.outputs().len()];
+    let mut contributed = 0u64;
+    for (i, output) in original.outputs().iter().enumerate() {
+        let found = proposal
+            .outputs()
+            .iter()
+            .enumerate()
+            .find(|(j, o)| !matched[*j] && o.script_pubkey == output.script_pubkey);
+        match found {
+            Some((j, o)) => {
+                matched[j] = true;
+                contributed += output.value.saturating_sub(o.value);
+            }
+            None => return Err(PayJoinError::MissingOutput(i)),
+        }
+    }
+    if contributed > max_fee_contribution {
+        return Err(PayJoinError::FeeContributionExceeded {
+            contributed,
+            max: max_fee_contribution,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        hashes::TXID,
+        types::{BitcoinTxIn, ScriptPubkey, ScriptSig, TxOut},
+    };
+
+    fn tx(inputs: &[(u32, u32)], outputs: &[(u8, u64)]) -> BitcoinTx {
+        let vin: Vec<BitcoinTxIn> = inputs
+            .iter()
+            .map(|(idx, sequence)| {
+                BitcoinTxIn::new(
+                    BitcoinOutpoint::new(TXID::default(), *idx),
+                    ScriptSig::null(),
+                    *sequence,
+                )
+            })
+            .collect();
+        let vout: Vec<TxOut> = outputs
+            .iter()
+            .map(|(tag, value)| TxOut::new(*value, ScriptPubkey::new(vec![*tag; 22])))
+            .collect();
+        BitcoinTx::new(2, vin, vout, 0).unwrap()
+    }
+
+    #[test]
+    fn it_validates_payjoin_proposals() {
+        let sender = vec![
+            BitcoinOutpoint::new(TXID::default(), 0),
+            BitcoinOutpoint::new(TXID::default(), 1),
+        ];
+        // payment to the receiver, then sender change
+        let original = tx(&[(0, 0xffff_fffd)], &[(1, 50_000), (2, 40_000)]);
+
+        // receiver adds an input, increases their output, and takes 300 sats from change
+        let proposal = tx(
+            &[(5, 0xffff_fffd), (0, 0xffff_fffd)],
+            &[(2, 39_700), (1, 70_000)],
+        );
+        validate_payjoin_modification(&original, &proposal, &sender, 500).unwrap();
+        assert_eq!(
+            validate_payjoin_modification(&original, &proposal, &sender, 200),
+            Err(PayJoinError::FeeContributionExceeded {
+                contributed: 300,
+                max: 200
+            })
+        );
+
+        let cases = vec![
+            (
+                tx(&[(5, 0xffff_fffd)], &[(1, 70_000), (2, 40_000)]),
+                PayJoinError::MissingSenderInput(sender[0]),
+            ),
+            (
+                tx(&[(0, 0xffff_ffff), (5, 0)], &[(1, 70_000), (2, 40_000)]),
+                PayJoinError::SequenceChanged(sender[0]),
+            ),
+            (
+                tx(&[(0, 0xffff_fffd), (1, 0)], &[(1, 70_000), (2, 40_000)]),
+                PayJoinError::SenderInputAdded(sender[1]),
+            ),
+            (
+                tx(&[(0, 0xffff_fffd), (5, 0)], &[(1, 70_000), (3, 40_000)]),
+                PayJoinError::MissingOutput(1),
+            ),
+        ];
+        for (proposal, err) in cases.into_iter() {
+            assert_eq!(
+                validate_payjoin_modification(&original, &proposal, &sender, 500),
+                Err(err)
+            );
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! One-shot transaction construction for the simplest spending case.
+//!
+//! `quick_send` selects from a set of P2WPKH UTXOs, builds a transaction paying a single
+//! destination, adds change if it is worth creating, and signs every input. Anything more
+//! complex should use the `BitcoinTxBuilder` and a signer directly.
+
+use thiserror::Error;
+
+use coins_bip32::{
+    curve::{Secp256k1Backend, SigSerialize},
+    keys::GenericPrivkey,
+    model::{HasPubkey, SigningKey},
+    Bip32Error,
+};
+use coins_core::{
+    builder::TxBuilder,
+    enc::{AddressEncoder, EncodingError},
+    types::tx::Transaction,
+};
+
+use crate::{
+    builder::BitcoinTxBuilder,
+    enc::encoder::{Address, BitcoinEncoderMarker},
+    select::{
+        output_vsize, CoinSelector, LargestFirst, SelectionError, SelectionTarget,
+        TX_OVERHEAD_VSIZE,
+    },
+    types::{BitcoinOutpoint, BitcoinTx, ScriptType, Sighash, TxError, TxOut, Witness, UTXO},
+};
+
+/// Errors produced by `quick_send`
+#[derive(Debug, Error)]
+pub enum QuickSendError {
+    /// Bubbled up from transaction construction or sighash calculation
+    #[error(transparent)]
+    TxError(#[from] TxError),
+
+    /// Bubbled up from address decoding
+    #[error(transparent)]
+    EncodingError(#[from] EncodingError),
+
+    /// Bubbled up from signing
+    #[error(transparent)]
+    Bip32Error(#[from] Bip32Error),
+
+    /// The P2WPKH UTXOs do not cover the payment and fee
+    #[error("Insufficient funds. Have {available} sats in P2WPKH UTXOs. Need {required}")]
+    InsufficientFunds {
+        /// The total value of the spendable UTXOs
+        available: u64,
+        /// The payment value plus the estimated fee
+        required: u64,
+    },
+
+    /// Bubbled up from coin selection, for failures other than insufficient funds
+    #[error(transparent)]
+    SelectionError(#[from] SelectionError),
+
+    /// None of the keys can sign for the selected UTXO
+    #[error("No key provided for input spending {0:?}")]
+    MissingKey(BitcoinOutpoint),
+}
+
+/// Build and sign a transaction paying `value` to `destination`. UTXOs are selected
+/// largest-first from the P2WPKH entries in `utxos`. Any change above the dust limit is paid to
+/// `change`. `fee_rate` is in sat/vbyte.
+///
+/// Every selected input is signed with `SIGHASH_ALL` by the key in `keys` whose pubkey hash
+/// matches the UTXO. Keys must have a backend attached.
+///
+/// # Errors
+///
+/// - `QuickSendError::InsufficientFunds` if the P2WPKH UTXOs can't cover the value and fee
+/// - `QuickSendError::MissingKey` if no key matches a selected UTXO
+pub fn quick_send<'a, T, B>(
+    utxos: &[UTXO],
+    keys: &[GenericPrivkey<'a, B>],
+    destination: &Address,
+    value: u64,
+    change: &Address,
+    fee_rate: u64,
+) -> Result<BitcoinTx, QuickSendError>
+where
+    T: BitcoinEncoderMarker,
+    B: Secp256k1Backend,
+{
+    let payment = TxOut::new(value, T::decode_address(destination)?);
+    let change_script = T::decode_address(change)?;
+
+    let candidates: Vec<UTXO> = utxos
+        .iter()
+        .filter(|u| matches!(u.standard_type(), ScriptType::WPKH(_)))
+        .cloned()
+        .collect();
+
+    let target = SelectionTarget {
+        value,
+        fee_rate,
+        base_vsize: TX_OVERHEAD_VSIZE + output_vsize(&payment),
+        change_vsize: output_vsize(&TxOut::new(0, change_script.clone())),
+    };
+    let selection = LargestFirst
+        .select(&candidates, &target)
+        .map_err(|e| match e {
+            SelectionError::InsufficientFunds {
+                available,
+                required,
+            } => QuickSendError::InsufficientFunds {
+                available,
+                required,
+            },
+            e => e.into(),
+        })?;
+    let selected = selection.utxos;
+
+    let mut builder = BitcoinTxBuilder::<T>::new()
+        .version(2)
+        .insert_output(0, payment);
+    for utxo in selected.iter() {
+        builder = builder.spend(utxo.outpoint, 0xffff_fffd);
+    }
+    if let Some(change_value) = selection.change {
+        builder = builder.pay_script_pubkey(change_value, change_script);
+    }
+    let tx = builder.build_witness()?;
+
+    let mut witnesses = vec![];
+    for (i, utxo) in selected.iter().enumerate() {
+        let pkh = match utxo.standard_type() {
+            ScriptType::WPKH(pkh) => pkh,
+            _ => unreachable!("filtered to WPKH above"),
+        };
+        let mut key = None;
+        for k in keys.iter() {
+            let pubkey = k.derive_verifying_key()?;
+            if pubkey.pubkey_hash160() == pkh {
+                key = Some((k, pubkey));
+                break;
+            }
+        }
+        let (privkey, pubkey) = key.ok_or(QuickSendError::MissingKey(utxo.outpoint))?;
+
+        let args = utxo
+            .witness_sighash_args(i, Sighash::All)
+            .expect("WPKH always has a signing script");
+        let sighash = tx.sighash(&args)?;
+        let signature = privkey.sign_digest(sighash.into())?;
+
+        let mut sig_bytes = signature.to_der();
+        sig_bytes.push(Sighash::All.to_u8());
+
+        let mut witness = Witness::default();
+        witness.push(sig_bytes.into());
+        witness.push(pubkey.pubkey_bytes().as_ref().into());
+        witnesses.push(witness);
+    }
+
+    let signed = BitcoinTxBuilder::<T>::from_tx(tx.into())
+        .extend_witnesses(witnesses)
+        .build_witness()?;
+    Ok(signed.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        enc::encoder::MainnetEncoder,
+        hashes::TXID,
+        types::{BitcoinTransaction, ScriptPubkey, SpendScript},
+    };
+    use coins_bip32::{
+        curve::{Privkey, ScalarDeserialize, Secp256k1},
+        model::VerifyingKey,
+    };
+
+    #[test]
+    fn it_builds_and_signs_p2wpkh_spends() {
+        let backend = Secp256k1::static_ref();
+        let key = GenericPrivkey {
+            key: Privkey::from_privkey_array([1u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let pubkey = key.derive_verifying_key().unwrap();
+        let spk = ScriptPubkey::p2wpkh(&pubkey);
+        let address = MainnetEncoder::encode_address(&spk).unwrap();
+
+        let utxos = vec![
+            UTXO::new(
+                BitcoinOutpoint::new(TXID::default(), 0),
+                50_000,
+                spk.clone(),
+                SpendScript::None,
+            ),
+            UTXO::new(
+                BitcoinOutpoint::new(TXID::default(), 1),
+                100_000,
+                spk.clone(),
+                SpendScript::None,
+            ),
+        ];
+
+        let tx = quick_send::<MainnetEncoder, _>(&utxos, &[key], &address, 80_000, &address, 10)
+            .unwrap();
+
+        // largest first, so only the 100k UTXO is needed
+        assert_eq!(tx.inputs().len(), 1);
+        assert_eq!(tx.inputs()[0].outpoint.idx, 1);
+        assert_eq!(tx.outputs().len(), 2);
+        assert_eq!(tx.outputs()[0].value, 80_000);
+        let prevout = TxOut::new(utxos[1].value, utxos[1].script_pubkey.clone());
+        let fee = tx.fee(&[prevout]).unwrap();
+        assert_eq!(fee, (TX_OVERHEAD_VSIZE + 31 + 31 + 68) * 10);
+
+        let witness_tx = tx.into_witness();
+        let witness = &witness_tx.witnesses()[0];
+        let sig_bytes = witness[0].items();
+        let sig = coins_bip32::Signature::try_from_der(&sig_bytes[..sig_bytes.len() - 1]).unwrap();
+        let args = utxos[1].witness_sighash_args(0, Sighash::All).unwrap();
+        let sighash = witness_tx.sighash(&args).unwrap();
+        pubkey.verify_digest(sighash.into(), &sig).unwrap();
+    }
+
+    #[test]
+    fn it_errors_on_insufficient_funds_and_missing_keys() {
+        let backend = Secp256k1::static_ref();
+        let key = GenericPrivkey {
+            key: Privkey::from_privkey_array([1u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let other = GenericPrivkey {
+            key: Privkey::from_privkey_array([2u8; 32]).unwrap(),
+            backend: Some(backend),
+        };
+        let spk = ScriptPubkey::p2wpkh(&key.derive_verifying_key().unwrap());
+        let address = MainnetEncoder::encode_address(&spk).unwrap();
+        let utxos = vec![UTXO::new(
+            BitcoinOutpoint::new(TXID::default(), 0),
+            50_000,
+            spk,
+            SpendScript::None,
+        )];
+
+        match quick_send::<MainnetEncoder, _>(&utxos, &[key], &address, 60_000, &address, 1) {
+            Err(QuickSendError::InsufficientFunds { available, .. }) => {
+                assert_eq!(available, 50_000)
+            }
+            _ => assert!(false, "expected InsufficientFunds"),
+        }
+        match quick_send::<MainnetEncoder, _>(&utxos, &[other], &address, 10_000, &address, 1) {
+            Err(QuickSendError::MissingKey(_)) => {}
+            _ => assert!(false, "expected MissingKey"),
+        }
+    }
+}
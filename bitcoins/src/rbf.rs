@@ -0,0 +1,210 @@
+//! Replace-by-fee policy checks. These mirror the BIP125 rules enforced by Bitcoin Core's
+//! mempool, so that a wallet can check a replacement before broadcasting it.
+//!
+//! Only rules that can be checked from the two transactions and their fees are implemented.
+//! Rules that depend on mempool state (unconfirmed inputs, the number of evicted transactions,
+//! and fees paid by descendants of the original) are not checked.
+
+use thiserror::Error;
+
+use crate::types::{BitcoinTransaction, BitcoinTx};
+use coins_core::types::tx::Transaction;
+
+/// Inputs with a sequence number at or below this value signal replaceability
+pub const MAX_BIP125_RBF_SEQUENCE: u32 = 0xffff_fffd;
+
+/// Bitcoin Core's default incremental relay fee, in sat/vbyte
+pub const DEFAULT_INCREMENTAL_RELAY_FEE: u64 = 1;
+
+/// The BIP125 rule violated by a replacement
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum RbfError {
+    /// Rule 1. No input of the original signals replaceability
+    #[error("Original transaction does not signal replaceability")]
+    NotSignaling,
+
+    /// The replacement does not spend any of the original's inputs, so it replaces nothing
+    #[error("Replacement does not conflict with the original transaction")]
+    NoConflict,
+
+    /// Rule 3. The replacement pays a lower absolute fee than the original
+    #[error("Replacement fee {replacement} is below the original fee {original}")]
+    InsufficientFee {
+        /// The fee paid by the original
+        original: u64,
+        /// The fee paid by the replacement
+        replacement: u64,
+    },
+
+    /// Rule 4. The additional fee does not pay for the replacement's relay bandwidth
+    #[error("Replacement adds {additional} sats in fees. Need {required} for relay")]
+    InsufficientRelayFee {
+        /// The replacement fee less the original fee
+        additional: u64,
+        /// The replacement vsize multiplied by the incremental relay fee
+        required: u64,
+    },
+
+    /// Rule 6. The replacement's fee rate is not higher than the original's
+    #[error("Replacement fee rate does not exceed the original fee rate")]
+    InsufficientFeeRate,
+}
+
+/// Type alias for results with an `RbfError`
+pub type RbfResult<T> = Result<T, RbfError>;
+
+/// A configurable replacement policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RbfPolicy {
+    /// The fee rate, in sat/vbyte, that the replacement must pay for its own vsize on top of
+    /// the original fee
+    pub incremental_relay_fee: u64,
+    /// Whether the original must signal replaceability. Nodes running with `mempoolfullrbf`
+    /// accept replacements of non-signaling transactions
+    pub require_signaling: bool,
+}
+
+impl Default for RbfPolicy {
+    fn default() -> Self {
+        Self {
+            incremental_relay_fee: DEFAULT_INCREMENTAL_RELAY_FEE,
+            require_signaling: true,
+        }
+    }
+}
+
+/// True if any input of `tx` signals replaceability
+pub fn signals_rbf(tx: &BitcoinTx) -> bool {
+    tx.inputs()
+        .iter()
+        .any(|input| input.sequence <= MAX_BIP125_RBF_SEQUENCE)
+}
+
+impl RbfPolicy {
+    /// Check that `replacement` may replace `original` under this policy. Fees are in sats.
+    /// Returns the first rule violated.
+    pub fn check(
+        &self,
+        original: &BitcoinTx,
+        original_fee: u64,
+        replacement: &BitcoinTx,
+        replacement_fee: u64,
+    ) -> RbfResult<()> {
+        if self.require_signaling && !signals_rbf(original) {
+            return Err(RbfError::NotSignaling);
+        }
+
+        let conflicts = replacement.inputs().iter().any(|input| {
+            original
+                .inputs()
+                .iter()
+                .any(|o| o.outpoint == input.outpoint)
+        });
+        if !conflicts {
+            return Err(RbfError::NoConflict);
+        }
+
+        if replacement_fee < original_fee {
+            return Err(RbfError::InsufficientFee {
+                original: original_fee,
+                replacement: replacement_fee,
+            });
+        }
+
+        let additional = replacement_fee - original_fee;
+        let required = replacement.vsize() as u64 * self.incremental_relay_fee;
+        if additional < required {
+            return Err(RbfError::InsufficientRelayFee {
+                additional,
+                required,
+            });
+        }
+
+        // compare fee / vsize without division
+        let original_rate = original_fee as u128 * replacement.vsize() as u128;
+        let replacement_rate = replacement_fee as u128 * original.vsize() as u128;
+        if replacement_rate <= original_rate {
+            return Err(RbfError::InsufficientFeeRate);
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that `replacement` may replace `original` under the default policy. Fees are in sats.
+/// Returns the first rule violated.
+pub fn check_rbf_replacement(
+    original: &BitcoinTx,
+    original_fee: u64,
+    replacement: &BitcoinTx,
+    replacement_fee: u64,
+) -> RbfResult<()> {
+    RbfPolicy::default().check(original, original_fee, replacement, replacement_fee)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        hashes::TXID,
+        types::{BitcoinOutpoint, BitcoinTxIn, ScriptPubkey, ScriptSig, TxOut},
+    };
+
+    fn tx(sequence: u32, idx: u32, outputs: usize) -> BitcoinTx {
+        let vin = vec![BitcoinTxIn::new(
+            BitcoinOutpoint::new(TXID::default(), idx),
+            ScriptSig::null(),
+            sequence,
+        )];
+        let vout = vec![TxOut::new(1000, ScriptPubkey::new(vec![0x51; 22])); outputs];
+        BitcoinTx::new(2, vin, vout, 0).unwrap()
+    }
+
+    #[test]
+    fn it_checks_bip125_rules() {
+        let original = tx(MAX_BIP125_RBF_SEQUENCE, 0, 1);
+        let replacement = tx(MAX_BIP125_RBF_SEQUENCE, 0, 1);
+        let vsize = replacement.vsize() as u64;
+
+        check_rbf_replacement(&original, 1000, &replacement, 1000 + vsize).unwrap();
+
+        assert_eq!(
+            check_rbf_replacement(&tx(0xffff_fffe, 0, 1), 1000, &replacement, 2000),
+            Err(RbfError::NotSignaling)
+        );
+        assert_eq!(
+            check_rbf_replacement(&original, 1000, &tx(0xffff_fffe, 1, 1), 2000),
+            Err(RbfError::NoConflict)
+        );
+        assert_eq!(
+            check_rbf_replacement(&original, 1000, &replacement, 999),
+            Err(RbfError::InsufficientFee {
+                original: 1000,
+                replacement: 999
+            })
+        );
+        assert_eq!(
+            check_rbf_replacement(&original, 1000, &replacement, 1000 + vsize - 1),
+            Err(RbfError::InsufficientRelayFee {
+                additional: vsize - 1,
+                required: vsize
+            })
+        );
+
+        // a much larger replacement pays more in total but at a lower rate
+        let large = tx(MAX_BIP125_RBF_SEQUENCE, 0, 20);
+        let large_vsize = large.vsize() as u64;
+        assert_eq!(
+            check_rbf_replacement(&original, 10 * vsize, &large, 10 * vsize + large_vsize),
+            Err(RbfError::InsufficientFeeRate)
+        );
+
+        let full_rbf = RbfPolicy {
+            require_signaling: false,
+            ..Default::default()
+        };
+        full_rbf
+            .check(&tx(0xffff_ffff, 0, 1), 1000, &replacement, 1000 + vsize)
+            .unwrap();
+    }
+}
@@ -0,0 +1,606 @@
+//! Coin selection. A `CoinSelector` chooses which UTXOs fund a payment, and reports the fee and
+//! change that result.
+//!
+//! Selectors only consider UTXOs whose spend size can be estimated: P2PKH and P2WPKH. Other
+//! UTXOs are ignored.
+
+use rand::{seq::SliceRandom, RngCore};
+use thiserror::Error;
+
+use crate::types::{ScriptType, TxOut, UTXO};
+
+/// Estimated vsize of the fixed portion of a witness transaction, rounded up.
+pub const TX_OVERHEAD_VSIZE: u64 = 11;
+
+/// Estimated vsize of a signed P2WPKH input, assuming a 72-byte signature.
+pub const P2WPKH_INPUT_VSIZE: u64 = 68;
+
+/// Estimated vsize of a signed P2PKH input, assuming a 72-byte signature and a compressed key.
+pub const P2PKH_INPUT_VSIZE: u64 = 148;
+
+/// Change outputs below this value are not created. Their value goes to fees instead.
+pub const DUST_LIMIT: u64 = 546;
+
+/// The vsize of a P2WPKH output.
+pub const P2WPKH_OUTPUT_VSIZE: u64 = 31;
+
+/// The largest transaction weight Bitcoin Core will relay.
+pub const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+
+/// The number of branches `BranchAndBound` explores before giving up.
+pub const BNB_MAX_TRIES: usize = 100_000;
+
+/// Errors produced by coin selectors
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SelectionError {
+    /// The eligible UTXOs do not cover the payment and fee
+    #[error("Insufficient funds. Have {available} sats in eligible UTXOs. Need {required}")]
+    InsufficientFunds {
+        /// The total value of the eligible UTXOs
+        available: u64,
+        /// The payment value plus the estimated fee
+        required: u64,
+    },
+
+    /// No input set funds the payment without change, within the search limit
+    #[error("No changeless input set found")]
+    NoChangelessSolution,
+
+    /// The payment, the fee, or the total value of the UTXOs exceeds `u64::MAX`
+    #[error("Value overflow while selecting UTXOs")]
+    ValueOverflow,
+}
+
+/// Type alias for results with a `SelectionError`
+pub type SelectionResult<T> = Result<T, SelectionError>;
+
+/// The input types whose spend size can be estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendType {
+    /// A P2PKH input with a compressed key
+    P2PKH,
+    /// A P2WPKH input
+    P2WPKH,
+}
+
+impl SpendType {
+    /// The spend type of `utxo`. Returns `None` if the UTXO type is not supported.
+    pub fn of(utxo: &UTXO) -> Option<Self> {
+        match utxo.standard_type() {
+            ScriptType::WPKH(_) => Some(SpendType::P2WPKH),
+            ScriptType::PKH(_) => Some(SpendType::P2PKH),
+            _ => None,
+        }
+    }
+
+    /// The estimated vsize of a signed input of this type
+    pub fn vsize(self) -> u64 {
+        match self {
+            SpendType::P2PKH => P2PKH_INPUT_VSIZE,
+            SpendType::P2WPKH => P2WPKH_INPUT_VSIZE,
+        }
+    }
+}
+
+/// Estimate the vsize of spending `utxo`. Returns `None` if the UTXO type is not supported.
+pub fn input_vsize(utxo: &UTXO) -> Option<u64> {
+    SpendType::of(utxo).map(SpendType::vsize)
+}
+
+/// The vsize of `output`. This is exact, as outputs have no witness data.
+pub fn output_vsize(output: &TxOut) -> u64 {
+    // 8-byte value, 1-byte length prefix, script
+    9 + output.script_pubkey.len() as u64
+}
+
+/// The payment a selector must fund.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionTarget {
+    /// The total value of the payment outputs
+    pub value: u64,
+    /// The fee rate in sat/vbyte
+    pub fee_rate: u64,
+    /// The vsize of the transaction without inputs or change. I.e. the overhead plus the
+    /// payment outputs
+    pub base_vsize: u64,
+    /// The vsize of the change output, if one is created
+    pub change_vsize: u64,
+}
+
+/// The outcome of coin selection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// The selected UTXOs, in the order they should be spent
+    pub utxos: Vec<UTXO>,
+    /// The fee paid by the transaction
+    pub fee: u64,
+    /// The value of the change output. `None` if change would be dust, in which case the excess
+    /// is paid as fee
+    pub change: Option<u64>,
+}
+
+/// A strategy for selecting UTXOs to fund a payment
+pub trait CoinSelector {
+    /// Select UTXOs from `utxos` that fund `target`. Unsupported UTXO types are ignored.
+    fn select(&mut self, utxos: &[UTXO], target: &SelectionTarget) -> SelectionResult<Selection>;
+}
+
+/// Take candidates in order until the target, the fee, and a change output are covered.
+fn accumulate(
+    candidates: Vec<(&UTXO, u64)>,
+    target: &SelectionTarget,
+) -> SelectionResult<Selection> {
+    let available = total_value(&candidates)?;
+
+    // Estimate including a change output. If we don't end up making change, the difference
+    // goes to fees.
+    let mut required = target
+        .base_vsize
+        .checked_add(target.change_vsize)
+        .and_then(|vsize| vsize.checked_mul(target.fee_rate))
+        .and_then(|fee| fee.checked_add(target.value))
+        .ok_or(SelectionError::ValueOverflow)?;
+    let mut selected = vec![];
+    let mut selected_value = 0u64;
+    for (utxo, vsize) in candidates.into_iter() {
+        if selected_value >= required {
+            break;
+        }
+        selected.push(utxo.clone());
+        // can't overflow, as the total of all candidates doesn't
+        selected_value += utxo.value;
+        required = vsize
+            .checked_mul(target.fee_rate)
+            .and_then(|fee| required.checked_add(fee))
+            .ok_or(SelectionError::ValueOverflow)?;
+    }
+    if selected_value < required {
+        return Err(SelectionError::InsufficientFunds {
+            available,
+            required,
+        });
+    }
+
+    let change_value = selected_value - required;
+    let (fee, change) = if change_value >= DUST_LIMIT {
+        (required - target.value, Some(change_value))
+    } else {
+        (selected_value - target.value, None)
+    };
+    Ok(Selection {
+        utxos: selected,
+        fee,
+        change,
+    })
+}
+
+/// The total value of `candidates`. Errors with `SelectionError::ValueOverflow` if it exceeds
+/// `u64::MAX`.
+fn total_value(candidates: &[(&UTXO, u64)]) -> SelectionResult<u64> {
+    candidates
+        .iter()
+        .try_fold(0u64, |acc, (u, _)| acc.checked_add(u.value))
+        .ok_or(SelectionError::ValueOverflow)
+}
+
+fn eligible(utxos: &[UTXO]) -> Vec<(&UTXO, u64)> {
+    utxos
+        .iter()
+        .filter_map(|u| input_vsize(u).map(|vsize| (u, vsize)))
+        .collect()
+}
+
+/// Selects the largest UTXOs first. This minimizes the number of inputs, but reveals the
+/// selection strategy to chain observers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LargestFirst;
+
+impl CoinSelector for LargestFirst {
+    fn select(&mut self, utxos: &[UTXO], target: &SelectionTarget) -> SelectionResult<Selection> {
+        let mut candidates = eligible(utxos);
+        candidates.sort_by(|a, b| b.0.value.cmp(&a.0.value));
+        accumulate(candidates, target)
+    }
+}
+
+/// Selects P2WPKH UTXOs before P2PKH UTXOs, and the largest first within each group. Segwit
+/// inputs are less than half the vsize of legacy inputs, so this usually pays a lower fee than
+/// `LargestFirst`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SegwitFirst;
+
+impl CoinSelector for SegwitFirst {
+    fn select(&mut self, utxos: &[UTXO], target: &SelectionTarget) -> SelectionResult<Selection> {
+        let mut candidates = eligible(utxos);
+        candidates.sort_by(|a, b| a.1.cmp(&b.1).then(b.0.value.cmp(&a.0.value)));
+        accumulate(candidates, target)
+    }
+}
+
+/// Branch and bound. Searches for an input set that funds the payment without a change output,
+/// as Bitcoin Core's BnB selector does. The excess over the payment and fee may be at most the
+/// cost of creating and later spending a P2WPKH change output, and is paid as fee. Of the sets
+/// found, the one with the lowest fee is selected.
+///
+/// Errors with `NoChangelessSolution` if no set is found within `BNB_MAX_TRIES` branches.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BranchAndBound;
+
+struct BnbSearch<'a> {
+    /// Candidates and their effective values, largest first
+    candidates: Vec<(&'a UTXO, u64)>,
+    lower: u64,
+    upper: u64,
+    tries: usize,
+    selected: Vec<usize>,
+    best: Option<(u64, Vec<usize>)>,
+}
+
+impl<'a> BnbSearch<'a> {
+    fn search(&mut self, idx: usize, effective: u64, remaining: u64) {
+        if self.tries >= BNB_MAX_TRIES || effective > self.upper {
+            return;
+        }
+        self.tries += 1;
+        if effective >= self.lower {
+            // Adding inputs would only increase the fee
+            let value = self
+                .selected
+                .iter()
+                .map(|i| self.candidates[*i].0.value)
+                .sum::<u64>();
+            if self.best.as_ref().map_or(true, |(best, _)| value < *best) {
+                self.best = Some((value, self.selected.clone()));
+            }
+            return;
+        }
+        if idx == self.candidates.len() || effective + remaining < self.lower {
+            return;
+        }
+        let candidate = self.candidates[idx].1;
+        self.selected.push(idx);
+        self.search(idx + 1, effective + candidate, remaining - candidate);
+        self.selected.pop();
+        self.search(idx + 1, effective, remaining - candidate);
+    }
+}
+
+impl CoinSelector for BranchAndBound {
+    fn select(&mut self, utxos: &[UTXO], target: &SelectionTarget) -> SelectionResult<Selection> {
+        let eligible = eligible(utxos);
+        let available = total_value(&eligible)?;
+        let lower = target
+            .base_vsize
+            .checked_mul(target.fee_rate)
+            .and_then(|fee| fee.checked_add(target.value))
+            .ok_or(SelectionError::ValueOverflow)?;
+        let upper = target
+            .change_vsize
+            .checked_add(P2WPKH_INPUT_VSIZE)
+            .and_then(|vsize| vsize.checked_mul(target.fee_rate))
+            .and_then(|fee| fee.checked_add(lower))
+            .ok_or(SelectionError::ValueOverflow)?;
+
+        // Effective value is the value net of the fee to spend it. Uneconomic UTXOs are dropped.
+        let mut candidates: Vec<(&UTXO, u64)> = eligible
+            .into_iter()
+            .filter_map(|(u, vsize)| {
+                let cost = vsize.checked_mul(target.fee_rate)?;
+                u.value.checked_sub(cost).map(|e| (u, e))
+            })
+            .filter(|(_, e)| *e > 0)
+            .collect();
+        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        let total = candidates.iter().map(|(_, e)| e).sum::<u64>();
+        if total < lower {
+            return Err(SelectionError::InsufficientFunds {
+                available,
+                required: lower,
+            });
+        }
+
+        let mut search = BnbSearch {
+            candidates,
+            lower,
+            upper,
+            tries: 0,
+            selected: vec![],
+            best: None,
+        };
+        search.search(0, 0, total);
+        let (value, selected) = search.best.ok_or(SelectionError::NoChangelessSolution)?;
+        Ok(Selection {
+            utxos: selected
+                .into_iter()
+                .map(|i| search.candidates[i].0.clone())
+                .collect(),
+            fee: value - target.value,
+            change: None,
+        })
+    }
+}
+
+/// Single random draw. Selects UTXOs in a random order until the payment is covered, as
+/// Bitcoin Core's SRD selector does. This avoids leaking wallet contents via a predictable
+/// selection heuristic.
+///
+/// Selection is deterministic for a given RNG state, so a seeded RNG may be used in tests.
+#[derive(Debug, Clone)]
+pub struct RandomDraw<R: RngCore> {
+    rng: R,
+}
+
+impl<R: RngCore> RandomDraw<R> {
+    /// Instantiate a selector drawing from `rng`
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+
+    /// Consume the selector, returning its RNG
+    pub fn into_rng(self) -> R {
+        self.rng
+    }
+}
+
+impl<R: RngCore> CoinSelector for RandomDraw<R> {
+    fn select(&mut self, utxos: &[UTXO], target: &SelectionTarget) -> SelectionResult<Selection> {
+        let mut candidates = eligible(utxos);
+        candidates.shuffle(&mut self.rng);
+        accumulate(candidates, target)
+    }
+}
+
+/// A plan to consolidate a wallet's UTXOs into a single P2WPKH output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsolidationPlan {
+    /// The UTXOs worth more than the fee to spend them. These are consolidated
+    pub spend: Vec<UTXO>,
+    /// The UTXOs that cost at least their value to spend at this fee rate
+    pub dust: Vec<UTXO>,
+    /// The fee paid by the consolidation transaction
+    pub fee: u64,
+    /// The value of the consolidated output. `None` if it would be below the dust limit, in
+    /// which case consolidating at this fee rate is not worthwhile
+    pub output_value: Option<u64>,
+}
+
+/// Plan a consolidation of `utxos` at `fee_rate` sat/vbyte. UTXOs that cost at least their value
+/// to spend are reported as dust and left out. The rest are combined into a single P2WPKH
+/// output. Unsupported UTXO types are ignored.
+///
+/// Returns `None` if the fee or the total value of the spent UTXOs overflows a `u64`.
+pub fn plan_consolidation(utxos: &[UTXO], fee_rate: u64) -> Option<ConsolidationPlan> {
+    let (spend, dust): (Vec<_>, Vec<_>) = eligible(utxos).into_iter().partition(|(u, vsize)| {
+        vsize
+            .checked_mul(fee_rate)
+            .map_or(false, |cost| u.value > cost)
+    });
+
+    let vsize =
+        TX_OVERHEAD_VSIZE + P2WPKH_OUTPUT_VSIZE + spend.iter().map(|(_, vsize)| vsize).sum::<u64>();
+    let fee = vsize.checked_mul(fee_rate)?;
+    let output_value = spend
+        .iter()
+        .try_fold(0u64, |acc, (u, _)| acc.checked_add(u.value))?
+        .checked_sub(fee)
+        .filter(|v| *v >= DUST_LIMIT);
+
+    Some(ConsolidationPlan {
+        spend: spend.into_iter().map(|(u, _)| u.clone()).collect(),
+        dust: dust.into_iter().map(|(u, _)| u.clone()).collect(),
+        fee,
+        output_value,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        hashes::TXID,
+        types::{BitcoinOutpoint, ScriptPubkey, SpendScript},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn utxos() -> Vec<UTXO> {
+        let spk = ScriptPubkey::from(
+            hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap(),
+        );
+        (0..10)
+            .map(|i| {
+                UTXO::new(
+                    BitcoinOutpoint::new(TXID::default(), i),
+                    10_000 * (i as u64 + 1),
+                    spk.clone(),
+                    SpendScript::None,
+                )
+            })
+            .chain(std::iter::once(UTXO::new(
+                BitcoinOutpoint::new(TXID::default(), 10),
+                1_000_000,
+                ScriptPubkey::default(),
+                SpendScript::None,
+            )))
+            .collect()
+    }
+
+    fn target(value: u64) -> SelectionTarget {
+        SelectionTarget {
+            value,
+            fee_rate: 2,
+            base_vsize: TX_OVERHEAD_VSIZE + 31,
+            change_vsize: 31,
+        }
+    }
+
+    #[test]
+    fn it_selects_largest_first() {
+        let selection = LargestFirst.select(&utxos(), &target(150_000)).unwrap();
+        let idxs: Vec<u32> = selection.utxos.iter().map(|u| u.outpoint.idx).collect();
+        assert_eq!(idxs, vec![9, 8]);
+        assert_eq!(selection.fee, (TX_OVERHEAD_VSIZE + 31 + 31 + 2 * 68) * 2);
+        assert_eq!(selection.change, Some(190_000 - 150_000 - selection.fee));
+    }
+
+    #[test]
+    fn it_selects_randomly_and_deterministically() {
+        let utxos = utxos();
+        let target = target(150_000);
+
+        let first = RandomDraw::new(StdRng::seed_from_u64(7))
+            .select(&utxos, &target)
+            .unwrap();
+        let second = RandomDraw::new(StdRng::seed_from_u64(7))
+            .select(&utxos, &target)
+            .unwrap();
+        assert_eq!(first, second);
+
+        for seed in 0..20 {
+            let selection = RandomDraw::new(StdRng::seed_from_u64(seed))
+                .select(&utxos, &target)
+                .unwrap();
+            let total = selection.utxos.iter().map(|u| u.value).sum::<u64>();
+            let vsize = target.base_vsize
+                + selection.change.map_or(0, |_| target.change_vsize)
+                + P2WPKH_INPUT_VSIZE * selection.utxos.len() as u64;
+            assert!(selection.fee >= vsize * target.fee_rate);
+            assert_eq!(
+                total,
+                target.value + selection.fee + selection.change.unwrap_or(0)
+            );
+            // the unsupported UTXO is never selected
+            assert!(selection.utxos.iter().all(|u| u.outpoint.idx != 10));
+        }
+    }
+
+    #[test]
+    fn it_prefers_segwit_inputs() {
+        let mut utxos = utxos();
+        utxos.push(UTXO::new(
+            BitcoinOutpoint::new(TXID::default(), 11),
+            200_000,
+            ScriptPubkey::from(
+                hex::decode("76a914758ce550380d964051086798d6546bebdca27a7388ac").unwrap(),
+            ),
+            SpendScript::None,
+        ));
+        let largest = LargestFirst.select(&utxos, &target(150_000)).unwrap();
+        assert_eq!(largest.utxos[0].outpoint.idx, 11);
+
+        let selection = SegwitFirst.select(&utxos, &target(150_000)).unwrap();
+        let idxs: Vec<u32> = selection.utxos.iter().map(|u| u.outpoint.idx).collect();
+        assert_eq!(idxs, vec![9, 8]);
+        assert!(selection.fee < largest.fee);
+    }
+
+    #[test]
+    fn it_finds_changeless_solutions() {
+        // two inputs cost 272 sats, and the base tx costs 84, so 150k of inputs is exact
+        let selection = BranchAndBound
+            .select(&utxos(), &target(150_000 - 84 - 272))
+            .unwrap();
+        let idxs: Vec<u32> = selection.utxos.iter().map(|u| u.outpoint.idx).collect();
+        assert_eq!(idxs, vec![9, 4]);
+        assert_eq!(selection.fee, 356);
+        assert_eq!(selection.change, None);
+
+        // any excess up to the cost of change is paid as fee
+        let selection = BranchAndBound
+            .select(&utxos(), &target(150_000 - 84 - 272 - 100))
+            .unwrap();
+        assert_eq!(selection.fee, 456);
+
+        assert_eq!(
+            BranchAndBound.select(&utxos(), &target(150_000)),
+            Err(SelectionError::NoChangelessSolution)
+        );
+        match BranchAndBound.select(&utxos(), &target(600_000)) {
+            Err(SelectionError::InsufficientFunds { available, .. }) => {
+                assert_eq!(available, 550_000)
+            }
+            _ => assert!(false, "expected InsufficientFunds"),
+        }
+    }
+
+    #[test]
+    fn it_plans_consolidations() {
+        let mut utxos = utxos();
+        utxos.push(UTXO::new(
+            BitcoinOutpoint::new(TXID::default(), 11),
+            20_000,
+            ScriptPubkey::from(
+                hex::decode("76a914758ce550380d964051086798d6546bebdca27a7388ac").unwrap(),
+            ),
+            SpendScript::None,
+        ));
+
+        // spending costs 13,600 sats per P2WPKH input and 29,600 per P2PKH input
+        let plan = plan_consolidation(&utxos, 200).unwrap();
+        let spent: Vec<u32> = plan.spend.iter().map(|u| u.outpoint.idx).collect();
+        let dust: Vec<u32> = plan.dust.iter().map(|u| u.outpoint.idx).collect();
+        assert_eq!(spent, (1..10).collect::<Vec<_>>());
+        assert_eq!(dust, vec![0, 11]);
+        assert_eq!(plan.fee, (TX_OVERHEAD_VSIZE + 31 + 9 * 68) * 200);
+        assert_eq!(plan.output_value, Some(540_000 - plan.fee));
+
+        // at a low rate, everything supported is consolidated
+        let plan = plan_consolidation(&utxos, 1).unwrap();
+        assert_eq!(plan.spend.len(), 11);
+        assert!(plan.dust.is_empty());
+
+        // nothing worth spending
+        let plan = plan_consolidation(&utxos[..1], 200).unwrap();
+        assert!(plan.spend.is_empty());
+        assert_eq!(plan.output_value, None);
+
+        // overflowing fees and values
+        assert_eq!(plan_consolidation(&utxos, u64::MAX), None);
+        let mut large = utxos[..2].to_vec();
+        large.iter_mut().for_each(|u| u.value = u64::MAX / 2 + 1);
+        assert_eq!(plan_consolidation(&large, 1), None);
+    }
+
+    #[test]
+    fn it_errors_on_insufficient_funds() {
+        match RandomDraw::new(StdRng::seed_from_u64(0)).select(&utxos(), &target(600_000)) {
+            Err(SelectionError::InsufficientFunds { available, .. }) => {
+                assert_eq!(available, 550_000)
+            }
+            _ => assert!(false, "expected InsufficientFunds"),
+        }
+    }
+
+    #[test]
+    fn it_errors_on_overflow() {
+        let mut huge_rate = target(150_000);
+        huge_rate.fee_rate = u64::MAX / 100;
+        assert_eq!(
+            LargestFirst.select(&utxos(), &huge_rate),
+            Err(SelectionError::ValueOverflow)
+        );
+        assert_eq!(
+            SegwitFirst.select(&utxos(), &target(u64::MAX)),
+            Err(SelectionError::ValueOverflow)
+        );
+
+        let mut large = utxos();
+        large.iter_mut().for_each(|u| u.value = u64::MAX / 2 + 1);
+        assert_eq!(
+            LargestFirst.select(&large, &target(150_000)),
+            Err(SelectionError::ValueOverflow)
+        );
+
+        assert_eq!(
+            BranchAndBound.select(&utxos(), &huge_rate),
+            Err(SelectionError::ValueOverflow)
+        );
+        assert_eq!(
+            BranchAndBound.select(&utxos(), &target(u64::MAX)),
+            Err(SelectionError::ValueOverflow)
+        );
+        assert_eq!(
+            BranchAndBound.select(&large, &target(150_000)),
+            Err(SelectionError::ValueOverflow)
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Script disassembly. Produces the same human-readable `asm` strings as Bitcoin Core's RPC
+//! interface.
+//!
+//! Pushes of 4 bytes or fewer are shown as script numbers. Longer pushes are shown as hex.
+//! Other opcodes are shown by name.
+
+use crate::types::tx::Sighash;
+
+/// Return the name of an opcode, as Bitcoin Core displays it. Small number opcodes are shown as
+/// their number.
+pub fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "0",
+        0x4c => "OP_PUSHDATA1",
+        0x4d => "OP_PUSHDATA2",
+        0x4e => "OP_PUSHDATA4",
+        0x4f => "-1",
+        0x50 => "OP_RESERVED",
+        0x51 => "1",
+        0x52 => "2",
+        0x53 => "3",
+        0x54 => "4",
+        0x55 => "5",
+        0x56 => "6",
+        0x57 => "7",
+        0x58 => "8",
+        0x59 => "9",
+        0x5a => "10",
+        0x5b => "11",
+        0x5c => "12",
+        0x5d => "13",
+        0x5e => "14",
+        0x5f => "15",
+        0x60 => "16",
+        0x61 => "OP_NOP",
+        0x62 => "OP_VER",
+        0x63 => "OP_IF",
+        0x64 => "OP_NOTIF",
+        0x65 => "OP_VERIF",
+        0x66 => "OP_VERNOTIF",
+        0x67 => "OP_ELSE",
+        0x68 => "OP_ENDIF",
+        0x69 => "OP_VERIFY",
+        0x6a => "OP_RETURN",
+        0x6b => "OP_TOALTSTACK",
+        0x6c => "OP_FROMALTSTACK",
+        0x6d => "OP_2DROP",
+        0x6e => "OP_2DUP",
+        0x6f => "OP_3DUP",
+        0x70 => "OP_2OVER",
+        0x71 => "OP_2ROT",
+        0x72 => "OP_2SWAP",
+        0x73 => "OP_IFDUP",
+        0x74 => "OP_DEPTH",
+        0x75 => "OP_DROP",
+        0x76 => "OP_DUP",
+        0x77 => "OP_NIP",
+        0x78 => "OP_OVER",
+        0x79 => "OP_PICK",
+        0x7a => "OP_ROLL",
+        0x7b => "OP_ROT",
+        0x7c => "OP_SWAP",
+        0x7d => "OP_TUCK",
+        0x7e => "OP_CAT",
+        0x7f => "OP_SUBSTR",
+        0x80 => "OP_LEFT",
+        0x81 => "OP_RIGHT",
+        0x82 => "OP_SIZE",
+        0x83 => "OP_INVERT",
+        0x84 => "OP_AND",
+        0x85 => "OP_OR",
+        0x86 => "OP_XOR",
+        0x87 => "OP_EQUAL",
+        0x88 => "OP_EQUALVERIFY",
+        0x89 => "OP_RESERVED1",
+        0x8a => "OP_RESERVED2",
+        0x8b => "OP_1ADD",
+        0x8c => "OP_1SUB",
+        0x8d => "OP_2MUL",
+        0x8e => "OP_2DIV",
+        0x8f => "OP_NEGATE",
+        0x90 => "OP_ABS",
+        0x91 => "OP_NOT",
+        0x92 => "OP_0NOTEQUAL",
+        0x93 => "OP_ADD",
+        0x94 => "OP_SUB",
+        0x95 => "OP_MUL",
+        0x96 => "OP_DIV",
+        0x97 => "OP_MOD",
+        0x98 => "OP_LSHIFT",
+        0x99 => "OP_RSHIFT",
+        0x9a => "OP_BOOLAND",
+        0x9b => "OP_BOOLOR",
+        0x9c => "OP_NUMEQUAL",
+        0x9d => "OP_NUMEQUALVERIFY",
+        0x9e => "OP_NUMNOTEQUAL",
+        0x9f => "OP_LESSTHAN",
+        0xa0 => "OP_GREATERTHAN",
+        0xa1 => "OP_LESSTHANOREQUAL",
+        0xa2 => "OP_GREATERTHANOREQUAL",
+        0xa3 => "OP_MIN",
+        0xa4 => "OP_MAX",
+        0xa5 => "OP_WITHIN",
+        0xa6 => "OP_RIPEMD160",
+        0xa7 => "OP_SHA1",
+        0xa8 => "OP_SHA256",
+        0xa9 => "OP_HASH160",
+        0xaa => "OP_HASH256",
+        0xab => "OP_CODESEPARATOR",
+        0xac => "OP_CHECKSIG",
+        0xad => "OP_CHECKSIGVERIFY",
+        0xae => "OP_CHECKMULTISIG",
+        0xaf => "OP_CHECKMULTISIGVERIFY",
+        0xb0 => "OP_NOP1",
+        0xb1 => "OP_CHECKLOCKTIMEVERIFY",
+        0xb2 => "OP_CHECKSEQUENCEVERIFY",
+        0xb3 => "OP_NOP4",
+        0xb4 => "OP_NOP5",
+        0xb5 => "OP_NOP6",
+        0xb6 => "OP_NOP7",
+        0xb7 => "OP_NOP8",
+        0xb8 => "OP_NOP9",
+        0xb9 => "OP_NOP10",
+        0xba => "OP_CHECKSIGADD",
+        _ => "OP_UNKNOWN",
+    }
+}
+
+/// True if `item` has the shape of a DER-encoded signature followed by a sighash byte.
+pub(crate) fn is_der_signature(item: &[u8]) -> bool {
+    item.len() >= 9 && item[0] == 0x30 && item[1] as usize == item.len() - 3
+}
+
+/// Interpret up to 8 bytes as a minimally-encoded script number.
+fn script_num(bytes: &[u8]) -> i64 {
+    if bytes.is_empty() {
+        return 0;
+    }
+    let mut result = 0i64;
+    for (i, byte) in bytes.iter().enumerate() {
+        result |= (*byte as i64) << (8 * i);
+    }
+    let last = bytes[bytes.len() - 1];
+    if last & 0x80 != 0 {
+        -(result & !(0x80i64 << (8 * (bytes.len() - 1))))
+    } else {
+        result
+    }
+}
+
+fn sighash_name(sighash: Sighash) -> &'static str {
+    match sighash {
+        Sighash::All => "ALL",
+        Sighash::None => "NONE",
+        Sighash::Single => "SINGLE",
+        Sighash::AllACP => "ALL|ANYONECANPAY",
+        Sighash::NoneACP => "NONE|ANYONECANPAY",
+        Sighash::SingleACP => "SINGLE|ANYONECANPAY",
+    }
+}
+
+/// Read the next opcode and its push data (if any) from `script`, starting at `cursor`.
+/// Returns the opcode, the push data, and the new cursor, or `None` if the push runs past the
+/// end of the script.
+pub(crate) fn next_op(script: &[u8], cursor: usize) -> Option<(u8, &[u8], usize)> {
+    let opcode = *script.get(cursor)?;
+    let mut cursor = cursor + 1;
+    let len = match opcode {
+        0x01..=0x4b => opcode as usize,
+        0x4c => {
+            let len = *script.get(cursor)? as usize;
+            cursor += 1;
+            len
+        }
+        0x4d => {
+            let bytes = script.get(cursor..cursor + 2)?;
+            cursor += 2;
+            u16::from_le_bytes([bytes[0], bytes[1]]) as usize
+        }
+        0x4e => {
+            let bytes = script.get(cursor..cursor + 4)?;
+            cursor += 4;
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+        }
+        _ => 0,
+    };
+    let data = script.get(cursor..cursor.checked_add(len)?)?;
+    Some((opcode, data, cursor + len))
+}
+
+/// Disassemble a script to Bitcoin Core's `asm` format. If `decode_sighash` is true, pushes
+/// that look like signatures are shown with their sighash mode, e.g. `[ALL]`. Core does this
+/// for script sigs, but not for script pubkeys or witnesses.
+///
+/// Malformed pushes are shown as `[error]`, and disassembly stops.
+pub fn disassemble(script: &[u8], decode_sighash: bool) -> String {
+    let mut ops: Vec<String> = vec![];
+    let mut cursor = 0;
+    // Core does not decode sighashes in unspendable scripts
+    let decode_sighash = decode_sighash && script.first() != Some(&0x6a);
+
+    while cursor < script.len() {
+        let (opcode, data, next) = match next_op(script, cursor) {
+            Some(op) => op,
+            None => {
+                ops.push("[error]".to_owned());
+                break;
+            }
+        };
+        cursor = next;
+
+        if opcode > 0x4e {
+            ops.push(opcode_name(opcode).to_owned());
+        } else if data.len() <= 4 {
+            ops.push(script_num(data).to_string());
+        } else if decode_sighash && is_der_signature(data) {
+            let (sig, flag) = data.split_at(data.len() - 1);
+            match Sighash::from_u8(flag[0]) {
+                Ok(sighash) => ops.push(format!("{}[{}]", hex::encode(sig), sighash_name(sighash))),
+                Err(_) => ops.push(hex::encode(data)),
+            }
+        } else {
+            ops.push(hex::encode(data));
+        }
+    }
+    ops.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_disassembles_scripts() {
+        let cases = [
+            (
+                "76a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac",
+                "OP_DUP OP_HASH160 bc3b654dca7e56b04dca18f2566cdaf02e8d9ada OP_EQUALVERIFY OP_CHECKSIG",
+            ),
+            (
+                "0014758ce550380d964051086798d6546bebdca27a73",
+                "0 758ce550380d964051086798d6546bebdca27a73",
+            ),
+            ("6a0401020304", "OP_RETURN 67305985"),
+            ("5152ae", "1 2 OP_CHECKMULTISIG"),
+            ("0181", "-1"),
+            ("4c", "[error]"),
+            ("4c0201", "[error]"),
+            ("", ""),
+        ];
+        for case in cases.iter() {
+            let script = hex::decode(case.0).unwrap();
+            assert_eq!(disassemble(&script, false), case.1);
+        }
+    }
+
+    #[test]
+    fn it_decodes_signature_sighashes() {
+        let script_sig = hex::decode("483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278a").unwrap();
+        assert_eq!(
+            disassemble(&script_sig, true),
+            "3045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed[ALL] 0349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278a"
+        );
+        assert_eq!(
+            disassemble(&script_sig, false),
+            "3045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01 0349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278a"
+        );
+    }
+}
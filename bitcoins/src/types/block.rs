@@ -0,0 +1,239 @@
+//! Bitcoin blocks
+
+use std::io::{Read, Write};
+
+use bitcoin_spv::types::RawHeader;
+use coins_core::{
+    hashes::MarkedDigestOutput,
+    ser::{self, ByteFormat},
+    types::tx::Transaction,
+};
+
+use crate::{
+    merkle::{compute_witness_commitment, merkle_root, WITNESS_COMMITMENT_HEADER},
+    types::{
+        tx::{BitcoinTransaction, BitcoinTx, TxError},
+        witness::WitnessTransaction,
+    },
+};
+
+/// A block, consisting of an 80-byte header and a list of transactions. The first transaction
+/// is the coinbase.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Block {
+    /// The block header
+    pub header: RawHeader,
+    /// The transactions, beginning with the coinbase
+    pub txns: Vec<BitcoinTx>,
+}
+
+impl Block {
+    /// Calculate the merkle root of the block's txids, in internal byte order
+    pub fn merkle_root(&self) -> [u8; 32] {
+        let txids: Vec<[u8; 32]> = self.txns.iter().map(|tx| to_array(tx.txid())).collect();
+        merkle_root(&txids)
+    }
+
+    /// Return the merkle root committed to by the header, in internal byte order
+    pub fn header_merkle_root(&self) -> [u8; 32] {
+        let mut root = [0u8; 32];
+        root.copy_from_slice(&self.header.as_ref()[36..68]);
+        root
+    }
+
+    /// True if the header commits to the block's transactions
+    pub fn check_merkle_root(&self) -> bool {
+        self.merkle_root() == self.header_merkle_root()
+    }
+
+    /// Return the witness commitment in the coinbase, if any. If several outputs hold a
+    /// commitment, the last one is used, per BIP141.
+    pub fn witness_commitment(&self) -> Option<[u8; 32]> {
+        let coinbase = self.txns.first()?;
+        coinbase.outputs().iter().rev().find_map(|output| {
+            let script = output.script_pubkey.items();
+            if script.len() >= 38
+                && script[..2] == [0x6a, 0x24]
+                && script[2..6] == WITNESS_COMMITMENT_HEADER
+            {
+                let mut commitment = [0u8; 32];
+                commitment.copy_from_slice(&script[6..38]);
+                Some(commitment)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Check the BIP141 witness commitment. Blocks without witness data need no commitment. If
+    /// any transaction has witness data, the coinbase must commit to the block's wtxids, and its
+    /// witness must be a single 32-byte reserved value.
+    pub fn check_witness_commitment(&self) -> bool {
+        let has_witness = self
+            .txns
+            .iter()
+            .any(|tx| tx.witnesses().iter().any(|w| !w.is_empty()));
+
+        let commitment = match self.witness_commitment() {
+            Some(commitment) => commitment,
+            None => return !has_witness,
+        };
+
+        let reserved_value = match self.txns[0].witnesses() {
+            [witness] if witness.len() == 1 && witness[0].len() == 32 => {
+                let mut value = [0u8; 32];
+                value.copy_from_slice(witness[0].items());
+                value
+            }
+            _ => return false,
+        };
+
+        let wtxids: Vec<[u8; 32]> = self
+            .txns
+            .iter()
+            .map(|tx| match tx {
+                BitcoinTx::Witness(tx) => to_array(tx.wtxid()),
+                BitcoinTx::Legacy(tx) => to_array(tx.txid()),
+            })
+            .collect();
+        compute_witness_commitment(&wtxids, reserved_value) == commitment
+    }
+}
+
+fn to_array<T: MarkedDigestOutput + AsRef<[u8]>>(digest: T) -> [u8; 32] {
+    let mut array = [0u8; 32];
+    array.copy_from_slice(digest.as_ref());
+    array
+}
+
+impl ByteFormat for Block {
+    type Error = TxError;
+
+    fn serialized_length(&self) -> usize {
+        let mut len = 80;
+        len += ser::prefix_byte_len(self.txns.len() as u64) as usize;
+        len += self
+            .txns
+            .iter()
+            .map(|tx| tx.serialized_length())
+            .sum::<usize>();
+        len
+    }
+
+    fn read_from<R>(reader: &mut R) -> Result<Self, Self::Error>
+    where
+        R: Read,
+        Self: std::marker::Sized,
+    {
+        let header = RawHeader::read_from(reader)?;
+        let txns = ser::read_prefix_vec(reader)?;
+        Ok(Self { header, txns })
+    }
+
+    fn write_to<W>(&self, writer: &mut W) -> Result<usize, Self::Error>
+    where
+        W: Write,
+    {
+        let mut len = self.header.write_to(writer)?;
+        len += ser::write_prefix_vec(writer, &self.txns)?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::*;
+
+    #[test]
+    fn it_checks_merkle_roots_and_witness_commitments() {
+        // a mainnet P2PKH spend, in both formats. The witness copy is given a witness
+        let witness_hex = "01000000000101813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac0019430600";
+        let legacy_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let mut with_witness = BitcoinTx::deserialize_hex(witness_hex)
+            .unwrap()
+            .into_witness();
+        with_witness.witnesses = vec![vec![vec![1u8, 2, 3].into()]];
+        let txns = vec![
+            BitcoinTx::deserialize_hex(legacy_hex).unwrap(),
+            BitcoinTx::Witness(with_witness),
+        ];
+
+        let reserved_value = [0u8; 32];
+        let mut wtxids = vec![[0u8; 32]];
+        wtxids.extend(txns.iter().map(|tx| match tx {
+            BitcoinTx::Witness(tx) => to_array(tx.wtxid()),
+            BitcoinTx::Legacy(tx) => to_array(tx.txid()),
+        }));
+        let commitment = compute_witness_commitment(&wtxids, reserved_value);
+        // computed independently, as SHA256d(merkle root of the wtxids || reserved value)
+        assert_eq!(
+            hex::encode(commitment),
+            "8c48f0faf357adf06cc19d641306af344522a568b577ee792fa43594cf1c8adc"
+        );
+        let mut commitment_script = vec![0x6a, 0x24];
+        commitment_script.extend(&WITNESS_COMMITMENT_HEADER);
+        commitment_script.extend(&commitment);
+
+        let coinbase_witness: Vec<Witness> = vec![vec![reserved_value.to_vec().into()]];
+        let coinbase = <WitnessTx as WitnessTransaction>::new(
+            1,
+            vec![BitcoinTxIn::new(
+                BitcoinOutpoint::null(),
+                vec![0x03u8, 0x01, 0x02, 0x03].into(),
+                0xffff_ffff,
+            )],
+            vec![
+                TxOut::new(625_000_000, ScriptPubkey::new(vec![0x51])),
+                TxOut::new(0, commitment_script.into()),
+            ],
+            coinbase_witness,
+            0,
+        )
+        .unwrap();
+        let mut all_txns = vec![BitcoinTx::Witness(coinbase)];
+        all_txns.extend(txns);
+
+        let mut block = Block {
+            header: [0u8; 80].into(),
+            txns: all_txns,
+        };
+        let mut header = [0u8; 80];
+        header[36..68].copy_from_slice(&block.merkle_root());
+        block.header = header.into();
+
+        let block = Block::deserialize_hex(&block.serialize_hex()).unwrap();
+        assert_eq!(block.serialized_length(), block.serialize_hex().len() / 2);
+        assert!(block.check_merkle_root());
+        assert_eq!(block.witness_commitment(), Some(commitment));
+        assert!(block.check_witness_commitment());
+
+        let mut tampered = block.clone();
+        tampered.txns.swap(1, 2);
+        assert!(!tampered.check_merkle_root());
+        assert!(!tampered.check_witness_commitment());
+
+        let mut no_commitment = block.clone();
+        no_commitment.txns[0] = BitcoinTx::deserialize_hex(legacy_hex).unwrap();
+        assert!(!no_commitment.check_witness_commitment());
+        no_commitment.txns.truncate(2);
+        assert!(no_commitment.check_witness_commitment());
+    }
+
+    #[test]
+    fn it_checks_the_genesis_block() {
+        let block_hex = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c0101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+        let block = Block::deserialize_hex(block_hex).unwrap();
+        assert_eq!(block.serialize_hex(), block_hex);
+
+        let mut root = block.merkle_root();
+        root.reverse();
+        assert_eq!(
+            hex::encode(root),
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert!(block.check_merkle_root());
+        assert_eq!(block.witness_commitment(), None);
+        assert!(block.check_witness_commitment());
+    }
+}
@@ -0,0 +1,908 @@
+//! A minimal script interpreter for verifying standard spends locally.
+//!
+//! This is not a full implementation of Bitcoin's script rules. It executes P2PKH, P2SH,
+//! P2WPKH, P2WSH, and bare multisig spends, including P2SH-wrapped witness programs. Only the
+//! opcodes those scripts use are supported:
+//!
+//! - pushes, `OP_1NEGATE`, and `OP_1` through `OP_16`
+//! - `OP_NOP`, `OP_VERIFY`, `OP_DROP`, `OP_DUP`
+//! - `OP_EQUAL`, `OP_EQUALVERIFY`
+//! - `OP_SHA256`, `OP_HASH160`, `OP_HASH256`
+//! - `OP_CHECKSIG`, `OP_CHECKSIGVERIFY`, `OP_CHECKMULTISIG`, `OP_CHECKMULTISIGVERIFY`
+//!
+//! Any other opcode, including flow control, `OP_CODESEPARATOR`, and timelocks, produces
+//! `ScriptError::Unsupported`. `OP_RETURN` and the reserved opcodes produce
+//! `ScriptError::Unsupported` as well, rather than a spend failure. Witness programs of
+//! versions other than 0 produce `ScriptError::UnsupportedWitnessVersion`. An unsupported
+//! script is never treated as valid.
+//!
+//! Taproot spends are checked only as far as the control block: a script-path spend whose
+//! control block does not commit to its script fails, and any other taproot spend produces
+//! `ScriptError::UnsupportedWitnessVersion`. Neither key-path signatures nor tapscript are
+//! executed.
+//!
+//! Some standardness rules are enforced regardless of script type: script sigs must be
+//! push-only, and the `OP_CHECKMULTISIG` dummy element must be empty.
+
+use thiserror::Error;
+
+use coins_core::{
+    hashes::{Digest, Hash160, Hash256, Hash256Digest, MarkedDigest, Sha256},
+    types::tx::Transaction,
+};
+
+use crate::types::{
+    asm::{self, next_op},
+    legacy::LEGACY_SIGHASH_ONE,
+    script::{Script, ScriptPubkey, ScriptSig, Witness},
+    taproot::{ControlBlock, TaprootError, ANNEX_TAG},
+    tx::{check_sig, hash_with_sig_byte, BitcoinTransaction, BitcoinTx, Sighash},
+    witness::{WitnessSighashArgs, WitnessTransaction},
+};
+
+/// The maximum number of pubkeys in an `OP_CHECKMULTISIG`
+const MAX_PUBKEYS_PER_MULTISIG: i64 = 20;
+
+/// Errors produced while executing a script
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The script uses an opcode this interpreter does not implement
+    #[error("Unsupported opcode: {}", asm::opcode_name(*.0))]
+    Unsupported(u8),
+
+    /// The spend uses a witness program version this interpreter does not implement
+    #[error("Unsupported witness version: {0}")]
+    UnsupportedWitnessVersion(u8),
+
+    /// A push ran past the end of the script
+    #[error("Malformed push")]
+    BadPush,
+
+    /// An opcode needed more stack elements than were available
+    #[error("Stack underflow")]
+    StackUnderflow,
+
+    /// A number was longer than 4 bytes, or out of range for its use
+    #[error("Invalid script number")]
+    BadNumber,
+
+    /// An `OP_VERIFY` variant failed
+    #[error("{} failed", asm::opcode_name(*.0))]
+    VerifyFailed(u8),
+
+    /// Execution finished with an empty stack, or a false value on top
+    #[error("Script evaluated to false")]
+    EvalFalse,
+
+    /// The script sig contains non-push opcodes
+    #[error("Script sig is not push-only")]
+    NonPushScriptSig,
+
+    /// The `OP_CHECKMULTISIG` dummy element was not empty
+    #[error("Multisig dummy element must be empty")]
+    NonEmptyDummy,
+
+    /// The script sig of a native witness spend was not empty, or the script sig of a P2SH
+    /// witness spend contained more than the redeem script
+    #[error("Witness spends may not carry extra script sig data")]
+    WitnessMalleated,
+
+    /// A non-witness spend carried a witness
+    #[error("Unexpected witness")]
+    UnexpectedWitness,
+
+    /// The witness did not match the witness program
+    #[error("Witness does not match the witness program")]
+    WitnessProgramMismatch,
+
+    /// A version 0 witness program was neither 20 nor 32 bytes
+    #[error("Witness program has wrong length: {0}")]
+    WitnessProgramWrongLength(usize),
+
+    /// The witness script left more than one element on the stack
+    #[error("Witness script must leave exactly one element on the stack")]
+    CleanStack,
+
+    /// The input index is not in the transaction
+    #[error("Input index {0} is out of range")]
+    InputIndexOutOfRange(usize),
+
+    /// In strict mode, a signature used a consensus-valid but non-standard sighash byte
+    #[error("Non-standard sighash byte: {0}")]
+    NonStandardSighash(u8),
+
+    /// A taproot script-path spend had an invalid control block
+    #[error(transparent)]
+    Taproot(#[from] TaprootError),
+}
+
+/// Type alias for results with a `ScriptError`
+pub type ScriptResult<T> = Result<T, ScriptError>;
+
+/// The signature hashing rules in effect while executing a script
+#[derive(Clone, Copy)]
+enum SigVersion {
+    Base,
+    WitnessV0,
+}
+
+/// The transaction context needed to check signatures
+struct Checker<'a> {
+    tx: &'a BitcoinTx,
+    index: usize,
+    prevout_value: u64,
+    strict: bool,
+}
+
+impl<'a> Checker<'a> {
+    /// In strict mode, reject signatures with non-standard sighash bytes. Empty signatures
+    /// are always allowed, as they fail without aborting the script.
+    fn check_encoding(&self, sig: &[u8]) -> ScriptResult<()> {
+        match sig.last() {
+            Some(flag) if self.strict && Sighash::from_u8(*flag).is_err() => {
+                Err(ScriptError::NonStandardSighash(*flag))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check `sig` against `pubkey`. `script_code` is the script being executed.
+    fn check(&self, sig: &[u8], pubkey: &[u8], script_code: &[u8], version: SigVersion) -> bool {
+        match version {
+            SigVersion::Base => {
+                // Legacy scripts commit to the script code with the signature removed
+                let script_code = find_and_delete(script_code, sig);
+                let legacy = self.tx.as_legacy();
+                check_sig(sig, pubkey, |flag: Sighash, sig_byte: u8| {
+                    // Reproduce the `SIGHASH_SINGLE` bug
+                    let single = flag == Sighash::Single || flag == Sighash::SingleACP;
+                    if single && self.index >= legacy.vout.len() {
+                        return Some(LEGACY_SIGHASH_ONE.into());
+                    }
+                    let mut preimage = vec![];
+                    legacy
+                        .write_legacy_preimage(&mut preimage, self.index, &script_code, flag as u32)
+                        .ok()?;
+                    Some(hash_with_sig_byte(preimage, sig_byte))
+                })
+            }
+            SigVersion::WitnessV0 => {
+                let witness_tx = self.tx.clone().into_witness();
+                check_sig(
+                    sig,
+                    pubkey,
+                    |flag: Sighash, sig_byte: u8| -> Option<Hash256Digest> {
+                        let args = WitnessSighashArgs {
+                            index: self.index,
+                            sighash_flag: flag,
+                            prevout_script: script_code.into(),
+                            prevout_value: self.prevout_value,
+                        };
+                        let mut preimage = vec![];
+                        witness_tx
+                            .write_witness_sighash_preimage(&mut preimage, &args)
+                            .ok()?;
+                        Some(hash_with_sig_byte(preimage, sig_byte))
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Verify that input `index` of `tx` validly spends an output with `script_pubkey` and
+/// `prevout_value`, using `script_sig` and `witness` as its spending data. See the module
+/// documentation for the supported script types.
+///
+/// If `strict` is true, signatures with non-standard sighash bytes abort verification, as
+/// they do under standardness rules. Otherwise, they are interpreted as consensus does.
+///
+/// ## Errors
+///
+/// - `ScriptError::Unsupported` if a script uses an opcode this interpreter does not implement
+/// - `ScriptError::NonStandardSighash` in strict mode, if a checked signature has a
+///   non-standard sighash byte
+/// - Any other `ScriptError` if the spend is invalid
+pub fn verify_script(
+    script_sig: &ScriptSig,
+    script_pubkey: &ScriptPubkey,
+    witness: &Witness,
+    tx: &BitcoinTx,
+    index: usize,
+    prevout_value: u64,
+    strict: bool,
+) -> ScriptResult<()> {
+    if index >= tx.inputs().len() {
+        return Err(ScriptError::InputIndexOutOfRange(index));
+    }
+    let checker = Checker {
+        tx,
+        index,
+        prevout_value,
+        strict,
+    };
+    let script_sig = script_sig.items();
+    let script_pubkey = script_pubkey.items();
+    let witness: Vec<Vec<u8>> = witness.iter().map(|item| item.items().to_vec()).collect();
+
+    let mut stack = push_only(script_sig)?;
+    let redeem_script = stack.last().cloned();
+    eval(&mut stack, script_pubkey, &checker, SigVersion::Base)?;
+    check_top(&stack)?;
+
+    if let Some((version, program)) = witness_program(script_pubkey) {
+        if !script_sig.is_empty() {
+            return Err(ScriptError::WitnessMalleated);
+        }
+        return verify_witness_program(version, program, witness, &checker, false);
+    }
+
+    if is_p2sh(script_pubkey) {
+        // `push_only` guarantees the stack is the script sig's pushes, and the hash check
+        // passed, so the last push is the redeem script
+        let redeem_script = redeem_script.ok_or(ScriptError::StackUnderflow)?;
+        let mut stack = push_only(script_sig)?;
+        stack.pop();
+        eval(&mut stack, &redeem_script, &checker, SigVersion::Base)?;
+        check_top(&stack)?;
+
+        if let Some((version, program)) = witness_program(&redeem_script) {
+            if script_sig != &push_encoding(&redeem_script)[..] {
+                return Err(ScriptError::WitnessMalleated);
+            }
+            return verify_witness_program(version, program, witness, &checker, true);
+        }
+    }
+
+    if !witness.is_empty() {
+        return Err(ScriptError::UnexpectedWitness);
+    }
+    Ok(())
+}
+
+/// Execute a witness program against its witness. `nested` is true if the program is the
+/// redeem script of a P2SH spend.
+fn verify_witness_program(
+    version: u8,
+    program: &[u8],
+    mut witness: Vec<Vec<u8>>,
+    checker: &Checker,
+    nested: bool,
+) -> ScriptResult<()> {
+    if version == 1 && program.len() == 32 && !nested {
+        return verify_taproot_commitment(program, witness);
+    }
+    if version != 0 {
+        return Err(ScriptError::UnsupportedWitnessVersion(version));
+    }
+
+    let script = match program.len() {
+        20 => {
+            if witness.len() != 2 {
+                return Err(ScriptError::WitnessProgramMismatch);
+            }
+            let mut script = vec![0x76, 0xa9, 0x14];
+            script.extend(program);
+            script.extend(&[0x88, 0xac]);
+            script
+        }
+        32 => {
+            let script = witness.pop().ok_or(ScriptError::WitnessProgramMismatch)?;
+            if Sha256::digest(&script).as_slice() != program {
+                return Err(ScriptError::WitnessProgramMismatch);
+            }
+            script
+        }
+        len => return Err(ScriptError::WitnessProgramWrongLength(len)),
+    };
+
+    let mut stack = witness;
+    eval(&mut stack, &script, checker, SigVersion::WitnessV0)?;
+    check_top(&stack)?;
+    if stack.len() != 1 {
+        return Err(ScriptError::CleanStack);
+    }
+    Ok(())
+}
+
+/// Check the control block of a taproot script-path spend against the output key. Neither
+/// key-path spends nor tapscript are executed, so this never succeeds: a valid commitment
+/// produces `ScriptError::UnsupportedWitnessVersion`.
+fn verify_taproot_commitment(program: &[u8], mut witness: Vec<Vec<u8>>) -> ScriptResult<()> {
+    if witness.len() >= 2 && witness.last().and_then(|item| item.first()) == Some(&ANNEX_TAG) {
+        witness.pop();
+    }
+    if witness.len() >= 2 {
+        let control_block = ControlBlock::from_bytes(&witness.pop().expect("checked length"))?;
+        let script = witness.pop().expect("checked length");
+        let mut output_key = [0u8; 32];
+        output_key.copy_from_slice(program);
+        if !control_block.verify_commitment(&output_key, &script)? {
+            return Err(ScriptError::WitnessProgramMismatch);
+        }
+    }
+    Err(ScriptError::UnsupportedWitnessVersion(1))
+}
+
+/// Execute `script` against `stack`
+fn eval(
+    stack: &mut Vec<Vec<u8>>,
+    script: &[u8],
+    checker: &Checker,
+    version: SigVersion,
+) -> ScriptResult<()> {
+    let mut cursor = 0;
+    while cursor < script.len() {
+        let (opcode, data, next) = next_op(script, cursor).ok_or(ScriptError::BadPush)?;
+        cursor = next;
+
+        match opcode {
+            0x00..=0x4e => stack.push(data.to_vec()),
+            0x4f => stack.push(encode_num(-1)),
+            0x51..=0x60 => stack.push(encode_num((opcode - 0x50) as i64)),
+            // OP_NOP
+            0x61 => {}
+            // OP_VERIFY
+            0x69 => verify(opcode, cast_to_bool(&pop(stack)?))?,
+            // OP_DROP
+            0x75 => {
+                pop(stack)?;
+            }
+            // OP_DUP
+            0x76 => {
+                let top = stack.last().ok_or(ScriptError::StackUnderflow)?.clone();
+                stack.push(top);
+            }
+            // OP_EQUAL, OP_EQUALVERIFY
+            0x87 | 0x88 => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+                push_or_verify(stack, opcode, 0x88, a == b)?;
+            }
+            // OP_SHA256
+            0xa8 => {
+                let top = pop(stack)?;
+                stack.push(Sha256::digest(&top).to_vec());
+            }
+            // OP_HASH160
+            0xa9 => {
+                let top = pop(stack)?;
+                stack.push(Hash160::digest_marked(&top).as_ref().to_vec());
+            }
+            // OP_HASH256
+            0xaa => {
+                let top = pop(stack)?;
+                stack.push(Hash256::digest_marked(&top).as_ref().to_vec());
+            }
+            // OP_CHECKSIG, OP_CHECKSIGVERIFY
+            0xac | 0xad => {
+                let pubkey = pop(stack)?;
+                let sig = pop(stack)?;
+                checker.check_encoding(&sig)?;
+                let valid = !sig.is_empty() && checker.check(&sig, &pubkey, script, version);
+                push_or_verify(stack, opcode, 0xad, valid)?;
+            }
+            // OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY
+            0xae | 0xaf => {
+                let valid = check_multisig(stack, script, checker, version)?;
+                push_or_verify(stack, opcode, 0xaf, valid)?;
+            }
+            _ => return Err(ScriptError::Unsupported(opcode)),
+        }
+    }
+    Ok(())
+}
+
+/// Pop the operands of an `OP_CHECKMULTISIG` and check the signatures, in order, against the
+/// pubkeys
+fn check_multisig(
+    stack: &mut Vec<Vec<u8>>,
+    script: &[u8],
+    checker: &Checker,
+    version: SigVersion,
+) -> ScriptResult<bool> {
+    let n = decode_num(&pop(stack)?)?;
+    if n < 0 || n > MAX_PUBKEYS_PER_MULTISIG {
+        return Err(ScriptError::BadNumber);
+    }
+    let mut pubkeys = (0..n)
+        .map(|_| pop(stack))
+        .collect::<ScriptResult<Vec<_>>>()?;
+    let m = decode_num(&pop(stack)?)?;
+    if m < 0 || m > n {
+        return Err(ScriptError::BadNumber);
+    }
+    let mut sigs = (0..m)
+        .map(|_| pop(stack))
+        .collect::<ScriptResult<Vec<_>>>()?;
+    if !pop(stack)?.is_empty() {
+        return Err(ScriptError::NonEmptyDummy);
+    }
+
+    // Popped in reverse, so the first sig and pubkey are at the end
+    while let Some(sig) = sigs.last() {
+        if pubkeys.len() < sigs.len() {
+            return Ok(false);
+        }
+        let pubkey = pubkeys.pop().expect("checked length");
+        checker.check_encoding(sig)?;
+        if !sig.is_empty() && checker.check(sig, &pubkey, script, version) {
+            sigs.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// Parse a push-only script into a stack
+fn push_only(script: &[u8]) -> ScriptResult<Vec<Vec<u8>>> {
+    let mut stack = vec![];
+    let mut cursor = 0;
+    while cursor < script.len() {
+        let (opcode, data, next) = next_op(script, cursor).ok_or(ScriptError::BadPush)?;
+        match opcode {
+            0x00..=0x4e => stack.push(data.to_vec()),
+            0x4f => stack.push(encode_num(-1)),
+            0x51..=0x60 => stack.push(encode_num((opcode - 0x50) as i64)),
+            _ => return Err(ScriptError::NonPushScriptSig),
+        }
+        cursor = next;
+    }
+    Ok(stack)
+}
+
+/// Return the version and program if `script` is a witness program
+fn witness_program(script: &[u8]) -> Option<(u8, &[u8])> {
+    if script.len() < 4 || script.len() > 42 || script[1] as usize != script.len() - 2 {
+        return None;
+    }
+    match script[0] {
+        0x00 => Some((0, &script[2..])),
+        0x51..=0x60 => Some((script[0] - 0x50, &script[2..])),
+        _ => None,
+    }
+}
+
+/// True if `script` is a P2SH script pubkey
+fn is_p2sh(script: &[u8]) -> bool {
+    script.len() == 23 && script[0] == 0xa9 && script[1] == 0x14 && script[22] == 0x87
+}
+
+/// Encode `data` as a minimal push
+fn push_encoding(data: &[u8]) -> Vec<u8> {
+    let mut encoded = match data.len() {
+        0..=0x4b => vec![data.len() as u8],
+        0x4c..=0xff => vec![0x4c, data.len() as u8],
+        _ => {
+            let mut prefix = vec![0x4d];
+            prefix.extend(&(data.len() as u16).to_le_bytes());
+            prefix
+        }
+    };
+    encoded.extend(data);
+    encoded
+}
+
+/// Remove every push of `sig` from `script`, as legacy signature checks do before hashing
+fn find_and_delete(script: &[u8], sig: &[u8]) -> Script {
+    let target = push_encoding(sig);
+    let mut result = vec![];
+    let mut cursor = 0;
+    while cursor < script.len() {
+        match next_op(script, cursor) {
+            Some((_, _, next)) => {
+                if script[cursor..next] != target[..] {
+                    result.extend(&script[cursor..next]);
+                }
+                cursor = next;
+            }
+            None => {
+                result.extend(&script[cursor..]);
+                break;
+            }
+        }
+    }
+    result.into()
+}
+
+fn pop(stack: &mut Vec<Vec<u8>>) -> ScriptResult<Vec<u8>> {
+    stack.pop().ok_or(ScriptError::StackUnderflow)
+}
+
+fn verify(opcode: u8, value: bool) -> ScriptResult<()> {
+    if value {
+        Ok(())
+    } else {
+        Err(ScriptError::VerifyFailed(opcode))
+    }
+}
+
+/// Push the result of a comparison, or fail if `opcode` is the `VERIFY` variant and it is false
+fn push_or_verify(
+    stack: &mut Vec<Vec<u8>>,
+    opcode: u8,
+    verify_opcode: u8,
+    value: bool,
+) -> ScriptResult<()> {
+    if opcode == verify_opcode {
+        verify(opcode, value)
+    } else {
+        stack.push(if value { vec![1] } else { vec![] });
+        Ok(())
+    }
+}
+
+fn check_top(stack: &[Vec<u8>]) -> ScriptResult<()> {
+    match stack.last() {
+        Some(top) if cast_to_bool(top) => Ok(()),
+        _ => Err(ScriptError::EvalFalse),
+    }
+}
+
+/// Interpret a stack element as a boolean. Any non-zero value is true, except negative zero.
+fn cast_to_bool(item: &[u8]) -> bool {
+    match item.split_last() {
+        Some((last, rest)) => rest.iter().any(|b| *b != 0) || (*last != 0 && *last != 0x80),
+        None => false,
+    }
+}
+
+/// Decode a script number of up to 4 bytes
+fn decode_num(item: &[u8]) -> ScriptResult<i64> {
+    if item.len() > 4 {
+        return Err(ScriptError::BadNumber);
+    }
+    let (last, _) = match item.split_last() {
+        Some(split) => split,
+        None => return Ok(0),
+    };
+    let mut value = 0i64;
+    for (i, b) in item.iter().enumerate() {
+        value |= (*b as i64) << (8 * i);
+    }
+    if last & 0x80 != 0 {
+        let mask = !(0x80i64 << (8 * (item.len() - 1)));
+        Ok(-(value & mask))
+    } else {
+        Ok(value)
+    }
+}
+
+/// Encode a small script number
+fn encode_num(value: i64) -> Vec<u8> {
+    match value {
+        0 => vec![],
+        -1 => vec![0x81],
+        _ => vec![value as u8],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{builder::BitcoinTxBuilder, enc::encoder::MainnetEncoder, types::*};
+    use coins_bip32::{
+        curve::{Privkey, ScalarDeserialize, Secp256k1, SigSerialize},
+        keys::GenericPrivkey,
+        model::{HasPubkey, SigningKey},
+    };
+    use coins_core::{builder::TxBuilder, ser::ByteFormat};
+
+    #[test]
+    fn it_verifies_a_mainnet_p2pkh_spend() {
+        // 03ee4f7a4e68f802303bc659f8f817964b4b74fe046facc3ae1be4679d622c45 spends a P2PKH output
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        let script_sig = tx.inputs()[0].script_sig.clone();
+        let pubkey = &script_sig.items()[script_sig.items().len() - 33..];
+        let mut spk = vec![0x76, 0xa9, 0x14];
+        spk.extend(Hash160::digest_marked(pubkey).as_ref());
+        spk.extend(&[0x88, 0xac]);
+        let spk: ScriptPubkey = spk.into();
+
+        verify_script(&script_sig, &spk, &vec![], &tx, 0, 0, false).unwrap();
+
+        let mut wrong = spk.items().to_vec();
+        wrong[5] ^= 1;
+        assert_eq!(
+            verify_script(
+                &script_sig,
+                &wrong.clone().into(),
+                &vec![],
+                &tx,
+                0,
+                0,
+                false
+            ),
+            Err(ScriptError::VerifyFailed(0x88))
+        );
+        assert_eq!(
+            verify_script(&script_sig, &spk, &vec![vec![1u8].into()], &tx, 0, 0, false),
+            Err(ScriptError::UnexpectedWitness)
+        );
+
+        // the same checks, through the transaction
+        tx.verify(&[TxOut::new(0, spk.clone())], false).unwrap();
+        match tx.verify(&[TxOut::new(0, wrong.into())], false) {
+            Err(TxError::ScriptFailure {
+                index: 0,
+                source: ScriptError::VerifyFailed(0x88),
+            }) => {}
+            _ => assert!(false, "expected ScriptFailure"),
+        }
+        match tx.verify(&[], false) {
+            Err(TxError::PrevoutsLengthMismatch { .. }) => {}
+            _ => assert!(false, "expected PrevoutsLengthMismatch"),
+        }
+
+        // Same tx with the signature's sighash byte changed to 0x41
+        let nonstandard = tx_hex.replace("8e10615bed01", "8e10615bed41");
+        let tx = BitcoinTx::deserialize_hex(&nonstandard).unwrap();
+        let script_sig = tx.inputs()[0].script_sig.clone();
+        assert_eq!(
+            verify_script(&script_sig, &spk, &vec![], &tx, 0, 0, false),
+            Err(ScriptError::EvalFalse)
+        );
+        assert_eq!(
+            verify_script(&script_sig, &spk, &vec![], &tx, 0, 0, true),
+            Err(ScriptError::NonStandardSighash(0x41))
+        );
+    }
+
+    #[test]
+    fn it_verifies_signed_standard_spends() {
+        let keys: Vec<_> = (1..=3u8)
+            .map(|seed| GenericPrivkey {
+                key: Privkey::from_privkey_array([seed; 32]).unwrap(),
+                backend: Some(Secp256k1::static_ref()),
+            })
+            .collect();
+        let pubkeys: Vec<Vec<u8>> = keys
+            .iter()
+            .map(|k| k.derive_verifying_key().unwrap().pubkey_bytes().to_vec())
+            .collect();
+
+        // 2-of-3 multisig
+        let mut multisig = vec![0x52];
+        for pubkey in pubkeys.iter() {
+            multisig.extend(push_encoding(pubkey));
+        }
+        multisig.extend(&[0x53, 0xae]);
+        let wpkh = {
+            let mut v = vec![0x00, 0x14];
+            v.extend(Hash160::digest_marked(&pubkeys[0]).as_ref());
+            v
+        };
+        let wsh = {
+            let mut v = vec![0x00, 0x20];
+            v.extend(Sha256::digest(&multisig));
+            v
+        };
+        let sh = |script: &[u8]| {
+            let mut v = vec![0xa9, 0x14];
+            v.extend(Hash160::digest_marked(script).as_ref());
+            v.push(0x87);
+            ScriptPubkey::from(v)
+        };
+
+        let outpoint = BitcoinOutpoint::new(Default::default(), 0);
+        let tx: BitcoinTx = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .version(2)
+            .spend(outpoint, 0xffff_fffd)
+            .pay_script_pubkey(90_000, sh(&multisig))
+            .build_witness()
+            .unwrap();
+        let value = 100_000;
+        let sign = |key: &coins_bip32::Privkey, version: SigVersion, script: &[u8]| {
+            let digest: Hash256Digest = match version {
+                SigVersion::Base => {
+                    legacy_sighash(tx.as_legacy(), 0, &script.into(), Sighash::All as u32).into()
+                }
+                SigVersion::WitnessV0 => tx
+                    .clone()
+                    .into_witness()
+                    .witness_sighash(&WitnessSighashArgs {
+                        index: 0,
+                        sighash_flag: Sighash::All,
+                        prevout_script: script.into(),
+                        prevout_value: value,
+                    })
+                    .unwrap()
+                    .into(),
+            };
+            let mut sig = key.sign_digest(digest).unwrap().to_der();
+            sig.push(0x01);
+            sig
+        };
+
+        // P2SH multisig
+        let mut script_sig = vec![0x00];
+        script_sig.extend(push_encoding(&sign(&keys[0], SigVersion::Base, &multisig)));
+        script_sig.extend(push_encoding(&sign(&keys[2], SigVersion::Base, &multisig)));
+        script_sig.extend(push_encoding(&multisig));
+        verify_script(
+            &script_sig.into(),
+            &sh(&multisig),
+            &vec![],
+            &tx,
+            0,
+            value,
+            false,
+        )
+        .unwrap();
+
+        // out of pubkey order
+        let mut script_sig = vec![0x00];
+        script_sig.extend(push_encoding(&sign(&keys[2], SigVersion::Base, &multisig)));
+        script_sig.extend(push_encoding(&sign(&keys[0], SigVersion::Base, &multisig)));
+        script_sig.extend(push_encoding(&multisig));
+        assert_eq!(
+            verify_script(
+                &script_sig.into(),
+                &sh(&multisig),
+                &vec![],
+                &tx,
+                0,
+                value,
+                false
+            ),
+            Err(ScriptError::EvalFalse)
+        );
+
+        // P2WPKH, native and nested
+        let mut p2pkh = vec![0x76, 0xa9, 0x14];
+        p2pkh.extend(&wpkh[2..]);
+        p2pkh.extend(&[0x88, 0xac]);
+        let witness: Witness = vec![
+            sign(&keys[0], SigVersion::WitnessV0, &p2pkh).into(),
+            pubkeys[0].clone().into(),
+        ];
+        verify_script(
+            &ScriptSig::null(),
+            &wpkh.clone().into(),
+            &witness,
+            &tx,
+            0,
+            value,
+            false,
+        )
+        .unwrap();
+        verify_script(
+            &push_encoding(&wpkh).into(),
+            &sh(&wpkh),
+            &witness,
+            &tx,
+            0,
+            value,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            verify_script(
+                &ScriptSig::null(),
+                &wpkh.clone().into(),
+                &witness,
+                &tx,
+                0,
+                value + 1,
+                false
+            ),
+            Err(ScriptError::EvalFalse)
+        );
+
+        // P2WSH multisig
+        let witness: Witness = vec![
+            WitnessStackItem::null(),
+            sign(&keys[1], SigVersion::WitnessV0, &multisig).into(),
+            sign(&keys[2], SigVersion::WitnessV0, &multisig).into(),
+            multisig.clone().into(),
+        ];
+        verify_script(
+            &ScriptSig::null(),
+            &wsh.clone().into(),
+            &witness,
+            &tx,
+            0,
+            value,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            verify_script(
+                &vec![0x51].into(),
+                &wsh.into(),
+                &witness,
+                &tx,
+                0,
+                value,
+                false
+            ),
+            Err(ScriptError::WitnessMalleated)
+        );
+    }
+
+    #[test]
+    fn it_rejects_unsupported_scripts() {
+        let tx: BitcoinTx = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .spend(BitcoinOutpoint::default(), 0)
+            .pay_script_pubkey(1, vec![0x51].into())
+            .build()
+            .unwrap();
+        let cases: [(Vec<u8>, Vec<u8>, ScriptError); 5] = [
+            (
+                vec![0x51],
+                vec![0x63, 0x51, 0x68],
+                ScriptError::Unsupported(0x63),
+            ),
+            (vec![0x51], vec![0xab, 0x51], ScriptError::Unsupported(0xab)),
+            (vec![0x51], vec![0x6a], ScriptError::Unsupported(0x6a)),
+            (vec![0x76], vec![0x51], ScriptError::NonPushScriptSig),
+            (vec![], vec![0x51, 0x20], ScriptError::BadPush),
+        ];
+        for (script_sig, spk, err) in cases.iter() {
+            assert_eq!(
+                verify_script(
+                    &script_sig.clone().into(),
+                    &spk.clone().into(),
+                    &vec![],
+                    &tx,
+                    0,
+                    0,
+                    false
+                ),
+                Err(err.clone())
+            );
+        }
+
+        let taproot: ScriptPubkey = [vec![0x51u8, 0x20], vec![1; 32]].concat().into();
+        assert_eq!(
+            verify_script(
+                &ScriptSig::null(),
+                &taproot,
+                &vec![vec![1u8].into()],
+                &tx,
+                0,
+                0,
+                false
+            ),
+            Err(ScriptError::UnsupportedWitnessVersion(1))
+        );
+
+        // script-path spends are checked against the control block. From the BIP341 wallet
+        // test vectors
+        let taproot: ScriptPubkey =
+            hex::decode("5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+                .unwrap()
+                .into();
+        let script =
+            hex::decode("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac")
+                .unwrap();
+        let control_block =
+            hex::decode("c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27")
+                .unwrap();
+        let witness: Witness = vec![
+            vec![1u8; 64].into(),
+            script.clone().into(),
+            control_block.clone().into(),
+        ];
+        assert_eq!(
+            verify_script(&ScriptSig::null(), &taproot, &witness, &tx, 0, 0, false),
+            Err(ScriptError::UnsupportedWitnessVersion(1))
+        );
+        let witness: Witness = vec![
+            vec![1u8; 64].into(),
+            vec![0x51].into(),
+            control_block.into(),
+        ];
+        assert_eq!(
+            verify_script(&ScriptSig::null(), &taproot, &witness, &tx, 0, 0, false),
+            Err(ScriptError::WitnessProgramMismatch)
+        );
+        let witness: Witness = vec![vec![1u8; 64].into(), script.into(), vec![0xc0; 34].into()];
+        assert_eq!(
+            verify_script(&ScriptSig::null(), &taproot, &witness, &tx, 0, 0, false),
+            Err(ScriptError::Taproot(
+                TaprootError::InvalidControlBlockLength(34)
+            ))
+        );
+    }
+}
@@ -10,6 +10,7 @@ use coins_core::{
 use crate::{
     hashes::TXID,
     types::{
+        asm::next_op,
         script::{Script, ScriptSig, Witness},
         tx::*,
         txin::{BitcoinTxIn, Vin},
@@ -90,15 +91,18 @@ impl LegacyTx {
     /// https://en.bitcoin.it/wiki/OP_CHECKSIG#How_it_works
     /// https://bitcoin.stackexchange.com/questions/3374/how-to-redeem-a-basic-tx
     ///
-    /// OP_CODESEPARATOR functionality is NOT provided here.
+    /// Any `OP_CODESEPARATOR`s in the prevout script are removed, as Bitcoin Core does. The
+    /// caller is responsible for passing only the part of the script after the last executed
+    /// `OP_CODESEPARATOR`.
     ///
     /// TODO: memoize
     fn legacy_sighash_prep(&self, index: usize, prevout_script: &Script) -> Self {
         let mut copy_tx = self.clone();
+        let script_code = strip_code_separators(prevout_script.items());
 
         for i in 0..copy_tx.vin.len() {
             copy_tx.vin[i].script_sig = if i == index {
-                ScriptSig::from(prevout_script.items())
+                ScriptSig::from(&script_code[..])
             } else {
                 ScriptSig::null()
             };
@@ -106,6 +110,21 @@ impl LegacyTx {
         copy_tx
     }
 
+    /// Modifies copy_tx according to legacy SIGHASH_NONE semantics.
+    ///
+    /// For Legacy sighash documentation, see here:
+    ///
+    /// - https://en.bitcoin.it/wiki/OP_CHECKSIG#Hashtype_SIGHASH_NONE
+    fn legacy_sighash_none(copy_tx: &mut Self, index: usize) -> TxResult<()> {
+        copy_tx.vout = vec![];
+        for (i, txin) in copy_tx.vin.iter_mut().enumerate() {
+            if i != index {
+                txin.sequence = 0;
+            }
+        }
+        Ok(())
+    }
+
     /// Modifies copy_tx according to legacy SIGHASH_SINGLE semantics.
     ///
     /// For Legacy sighash documentation, see here:
@@ -199,25 +218,126 @@ impl Transaction for LegacyTx {
             return Err(TxError::NoneUnsupported);
         }
 
-        let mut copy_tx: Self = self.legacy_sighash_prep(args.index, &args.prevout_script);
         if args.sighash_flag == Sighash::Single || args.sighash_flag == Sighash::SingleACP {
             if args.index >= self.outputs().len() {
                 return Err(TxError::SighashSingleBug);
             }
-            Self::legacy_sighash_single(&mut copy_tx, args.index)?;
         }
 
-        if args.sighash_flag as u8 & 0x80 == 0x80 {
-            Self::legacy_sighash_anyone_can_pay(&mut copy_tx, args.index)?;
+        self.write_legacy_preimage(
+            writer,
+            args.index,
+            &args.prevout_script,
+            args.sighash_flag as u32,
+        )
+    }
+}
+
+impl LegacyTx {
+    /// Write the legacy sighash preimage for any hash type, including `SIGHASH_NONE` and
+    /// non-standard values. Callers must ensure that `index` is in range, and that an output
+    /// exists at `index` for `SIGHASH_SINGLE`.
+    pub(crate) fn write_legacy_preimage<W: Write>(
+        &self,
+        writer: &mut W,
+        index: usize,
+        prevout_script: &Script,
+        hash_type: u32,
+    ) -> TxResult<()> {
+        let mut copy_tx: Self = self.legacy_sighash_prep(index, prevout_script);
+        match hash_type & 0x1f {
+            0x02 => Self::legacy_sighash_none(&mut copy_tx, index)?,
+            0x03 => Self::legacy_sighash_single(&mut copy_tx, index)?,
+            _ => {}
+        }
+
+        if hash_type & 0x80 == 0x80 {
+            Self::legacy_sighash_anyone_can_pay(&mut copy_tx, index)?;
         }
 
         copy_tx.write_to(writer)?;
-        coins_core::ser::write_u32_le(writer, args.sighash_flag as u32)?;
+        coins_core::ser::write_u32_le(writer, hash_type)?;
 
         Ok(())
     }
 }
 
+/// The digest returned by Bitcoin Core for sighash requests it can't satisfy: `SIGHASH_SINGLE`
+/// with no output at the input's index, or an input index out of range. It is the number 1,
+/// little-endian.
+pub const LEGACY_SIGHASH_ONE: [u8; 32] = {
+    let mut one = [0u8; 32];
+    one[0] = 1;
+    one
+};
+
+/// Remove all `OP_CODESEPARATOR`s from a script. Bytes inside pushes are left alone. If a push
+/// runs past the end of the script, the remaining bytes are kept as-is.
+fn strip_code_separators(script: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(script.len());
+    let mut cursor = 0;
+    while cursor < script.len() {
+        match next_op(script, cursor) {
+            Some((0xab, _, next)) => cursor = next,
+            Some((_, _, next)) => {
+                result.extend(&script[cursor..next]);
+                cursor = next;
+            }
+            None => {
+                result.extend(&script[cursor..]);
+                break;
+            }
+        }
+    }
+    result
+}
+
+/// Serialize the legacy sighash preimage of input `index`. This is the data hashed by
+/// `legacy_sighash`, and is useful for comparing against other implementations when debugging.
+///
+/// Returns `None` where `legacy_sighash` returns `LEGACY_SIGHASH_ONE`. In those cases no
+/// preimage is hashed.
+pub fn legacy_sighash_preimage(
+    tx: &LegacyTx,
+    index: usize,
+    subscript: &Script,
+    hash_type: u32,
+) -> Option<Vec<u8>> {
+    let single = hash_type & 0x1f == 0x03;
+    if index >= tx.vin.len() || (single && index >= tx.vout.len()) {
+        return None;
+    }
+
+    let mut preimage = vec![];
+    tx.write_legacy_preimage(&mut preimage, index, subscript, hash_type)
+        .expect("writes to a vec do not fail");
+    Some(preimage)
+}
+
+/// Calculate the legacy sighash digest of input `index`, exactly as Bitcoin Core's
+/// `SignatureHash` does for non-witness inputs.
+///
+/// `subscript` is the script code being executed. In scripts with `OP_CODESEPARATOR` this must
+/// be the part after the last executed separator. Any remaining separators are removed.
+///
+/// `hash_type` is the full 32-bit hash type, as Core takes it. It is committed to as-is, while
+/// its low 5 bits select the mode and bit 7 selects `ANYONECANPAY`. Standard modes can be
+/// passed as e.g. `Sighash::All as u32`.
+///
+/// Unlike `LegacyTx::sighash`, this supports `SIGHASH_NONE`, and reproduces the
+/// `SIGHASH_SINGLE` bug: if `index` is out of range, or there is no output at `index` for
+/// `SIGHASH_SINGLE`, it returns `LEGACY_SIGHASH_ONE`.
+pub fn legacy_sighash(tx: &LegacyTx, index: usize, subscript: &Script, hash_type: u32) -> [u8; 32] {
+    match legacy_sighash_preimage(tx, index, subscript, hash_type) {
+        Some(preimage) => {
+            let mut digest = [0u8; 32];
+            digest.copy_from_slice(&Hash256::digest(&preimage));
+            digest
+        }
+        None => LEGACY_SIGHASH_ONE,
+    }
+}
+
 impl BitcoinTransaction for LegacyTx {
     fn as_legacy(&self) -> &LegacyTx {
         &self
@@ -279,8 +399,8 @@ impl ByteFormat for LegacyTx {
         W: Write,
     {
         let mut len = coins_core::ser::write_u32_le(writer, self.version())?;
-        ser::write_prefix_vec(writer, &self.vin)?;
-        ser::write_prefix_vec(writer, &self.vout)?;
+        len += ser::write_prefix_vec(writer, &self.vin)?;
+        len += ser::write_prefix_vec(writer, &self.vout)?;
         len += coins_core::ser::write_u32_le(writer, self.locktime())?;
         Ok(len)
     }
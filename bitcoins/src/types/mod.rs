@@ -2,16 +2,26 @@
 //! Extends the `Transaction` trait to maintain a type distinction between Legacy and Witness
 //! transactions (and allow conversion from one to the other).
 
+pub mod asm;
+pub mod block;
+pub mod interpreter;
 pub mod legacy;
+pub mod program;
 pub mod script;
+pub mod taproot;
 pub mod tx;
 pub mod txin;
 pub mod txout;
 pub mod utxo;
 pub mod witness;
 
+pub use asm::*;
+pub use block::*;
+pub use interpreter::*;
 pub use legacy::*;
+pub use program::*;
 pub use script::*;
+pub use taproot::*;
 pub use tx::*;
 pub use txin::*;
 pub use txout::*;
@@ -0,0 +1,168 @@
+//! Witness programs. A witness program is a version and a 2 to 40 byte program. Its script
+//! pubkey is the version opcode followed by a single push of the program.
+//!
+//! Version-specific rules live here: v0 programs must be 20 or 32 bytes, and v1 programs must
+//! be 32 bytes. v0 programs are encoded as bech32 addresses, and all later versions as bech32m,
+//! per BIP350.
+
+use coins_core::enc::{
+    bases::{decode_bech32_variant, encode_bech32_variant, Bech32Variant},
+    EncodingError, EncodingResult,
+};
+
+use crate::types::script::ScriptPubkey;
+
+/// The highest witness version
+pub const MAX_WITNESS_VERSION: u8 = 16;
+
+/// A validated witness program
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WitnessProgram {
+    /// The witness version, 0 through 16
+    version: u8,
+    /// The program
+    program: Vec<u8>,
+}
+
+impl WitnessProgram {
+    /// Instantiate a witness program. Errors with `SegwitVersionError` if the version is above
+    /// 16, and `InvalidSizeError` if the program length is not valid for the version.
+    pub fn new(version: u8, program: Vec<u8>) -> EncodingResult<Self> {
+        if version > MAX_WITNESS_VERSION {
+            return Err(EncodingError::SegwitVersionError(version));
+        }
+        let valid_len = match version {
+            0 => program.len() == 20 || program.len() == 32,
+            1 => program.len() == 32,
+            _ => (2..=40).contains(&program.len()),
+        };
+        if !valid_len {
+            return Err(EncodingError::InvalidSizeError);
+        }
+        Ok(Self { version, program })
+    }
+
+    /// The witness version
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The program bytes
+    pub fn program(&self) -> &[u8] {
+        &self.program
+    }
+
+    /// The bech32 checksum variant used to encode this program's address
+    pub fn variant(&self) -> Bech32Variant {
+        if self.version == 0 {
+            Bech32Variant::Bech32
+        } else {
+            Bech32Variant::Bech32m
+        }
+    }
+
+    /// Parse a witness program from a script pubkey. Returns `None` if the script is not a
+    /// witness program, or if the program is invalid for its version.
+    pub fn from_script_pubkey(script: &[u8]) -> Option<Self> {
+        let (opcode, rest) = script.split_first()?;
+        let (len, program) = rest.split_first()?;
+        if *len as usize != program.len() {
+            return None;
+        }
+        let version = match opcode {
+            0x00 => 0,
+            0x51..=0x60 => opcode - 0x50,
+            _ => return None,
+        };
+        Self::new(version, program.to_vec()).ok()
+    }
+
+    /// Serialize the witness program as a script pubkey
+    pub fn to_script_pubkey(&self) -> ScriptPubkey {
+        let opcode = if self.version == 0 {
+            0x00
+        } else {
+            self.version + 0x50
+        };
+        let mut script = vec![opcode, self.program.len() as u8];
+        script.extend(&self.program);
+        script.into()
+    }
+
+    /// Encode the witness program as an address with the specified HRP
+    pub fn encode(&self, hrp: &str) -> EncodingResult<String> {
+        encode_bech32_variant(hrp, self.version, &self.program, self.variant())
+    }
+
+    /// Decode a witness program from an address. Errors with `WrongHRP` if the HRP does not
+    /// match. Errors with `SegwitVersionError` if the checksum variant does not match the
+    /// version.
+    pub fn decode(expected_hrp: &str, s: &str) -> EncodingResult<Self> {
+        let (version, program, variant) = decode_bech32_variant(expected_hrp, s)?;
+        let program = Self::new(version, program)?;
+        if program.variant() != variant {
+            return Err(EncodingError::SegwitVersionError(version));
+        }
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_validates_and_encodes_witness_programs() {
+        let taproot =
+            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let cases = [
+            (
+                1,
+                taproot.clone(),
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            ),
+            (16, vec![0x75, 0x1e], "bc1sw50qgdz25j"),
+            (
+                0,
+                hex::decode("1bf8a1831db5443b42a44f30a121d1b616d011ab").unwrap(),
+                "bc1qr0u2rqcak4zrks4yfuc2zgw3kctdqydt3wy5yh",
+            ),
+        ];
+        for (version, program, address) in cases.iter() {
+            let wp = WitnessProgram::new(*version, program.clone()).unwrap();
+            assert_eq!(wp.encode("bc").unwrap(), *address);
+            assert_eq!(WitnessProgram::decode("bc", address).unwrap(), wp);
+            assert_eq!(
+                WitnessProgram::from_script_pubkey(wp.to_script_pubkey().items()),
+                Some(wp)
+            );
+        }
+
+        // the same program with a bech32 checksum
+        match WitnessProgram::decode(
+            "bc",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+        ) {
+            Err(EncodingError::SegwitVersionError(1)) => {}
+            _ => assert!(false, "expected SegwitVersionError"),
+        }
+
+        let errors = [
+            (0, vec![0; 21]),
+            (1, vec![0; 20]),
+            (2, vec![0; 1]),
+            (2, vec![0; 41]),
+        ];
+        for (version, program) in errors.iter() {
+            match WitnessProgram::new(*version, program.clone()) {
+                Err(EncodingError::InvalidSizeError) => {}
+                _ => assert!(false, "expected InvalidSizeError"),
+            }
+        }
+        match WitnessProgram::new(17, vec![0; 32]) {
+            Err(EncodingError::SegwitVersionError(17)) => {}
+            _ => assert!(false, "expected SegwitVersionError"),
+        }
+    }
+}
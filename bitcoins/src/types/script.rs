@@ -1,8 +1,9 @@
 //! Simple types for Bitcoin Script Witness stack datastructures, each of which are treated as
 //! opaque, wrapped `Vec<u8>` instance.
 //!
-//! We do not handle assembly, disassembly, or Script execution in `bitcoins`. Scripts are treated
-//! as opaque bytes vectors with no semantics.
+//! We do not handle assembly or Script execution in `bitcoins`. Scripts are treated as opaque
+//! bytes vectors with no semantics. For display, the `asm` module disassembles scripts to
+//! Bitcoin Core's format.
 //!
 //! Scripts can be freely converted between eachother using `From` and `Into`. This merely rewraps
 //! the underlying `Vec<u8>` in the new type.
@@ -21,12 +22,17 @@
 //! let script = bitcoins::types::Script::from(script.into_bytes());
 //! ```
 use coins_core::{
-    hashes::{Digest, Hash160, Hash160Digest, Hash256Digest, MarkedDigestOutput, Sha256},
+    hashes::{
+        Digest, Hash160, Hash160Digest, Hash256Digest, MarkedDigest, MarkedDigestOutput, Sha256,
+    },
     impl_hex_serde, impl_script_conversion,
+    ser::{self, SerError, SerResult},
     types::tx::RecipientIdentifier,
     wrap_prefixed_byte_vector,
 };
 
+use crate::types::program::WitnessProgram;
+
 /// A wrapped script.
 pub trait BitcoinScript {}
 
@@ -91,15 +97,64 @@ pub type Witness = Vec<WitnessStackItem>;
 /// A TxWitness is the UNPREFIXED vector of witnesses
 pub type TxWitness = Vec<Witness>;
 
+/// A flat serialization of a single input's witness, for storing it outside a transaction. This
+/// is the format used inside transaction serialization: a CompactInt item count, then each item
+/// with a CompactInt length prefix.
+pub trait FlatWitness: Sized {
+    /// Serialize the witness
+    fn to_flat_bytes(&self) -> Vec<u8>;
+
+    /// Deserialize a witness. Errors if `bytes` is malformed or has trailing data.
+    fn from_flat_bytes(bytes: &[u8]) -> SerResult<Self>;
+}
+
+impl FlatWitness for Witness {
+    fn to_flat_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        ser::write_prefix_vec::<_, SerError, _>(&mut buf, self)
+            .expect("writes to a vec do not fail");
+        buf
+    }
+
+    fn from_flat_bytes(bytes: &[u8]) -> SerResult<Self> {
+        let mut reader = bytes;
+        let witness = ser::read_prefix_vec(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(SerError::ComponentError(format!(
+                "{} trailing bytes after witness",
+                reader.len()
+            )));
+        }
+        Ok(witness)
+    }
+}
+
 impl ScriptPubkey {
-    /// Instantiate a standard p2pkh script pubkey from a pubkey.
+    /// Instantiate a standard p2pkh script pubkey from a pubkey. Uses the compressed pubkey.
     pub fn p2pkh<'a, T, B>(key: &T) -> Self
     where
         B: coins_bip32::curve::Secp256k1Backend,
         T: coins_bip32::model::HasPubkey<'a, B>,
     {
+        Self::p2pkh_with_compression(key, true)
+    }
+
+    /// Instantiate a standard p2pkh script pubkey from a pubkey, hashing either its compressed
+    /// or uncompressed representation. The two produce different addresses. Uncompressed keys
+    /// were the default in early wallets, so recovering their funds requires
+    /// `compressed = false`.
+    pub fn p2pkh_with_compression<'a, T, B>(key: &T, compressed: bool) -> Self
+    where
+        B: coins_bip32::curve::Secp256k1Backend,
+        T: coins_bip32::model::HasPubkey<'a, B>,
+    {
+        let pkh = if compressed {
+            key.pubkey_hash160()
+        } else {
+            Hash160::digest_marked(&key.pubkey_bytes_uncompressed()[..])
+        };
         let mut v: Vec<u8> = vec![0x76, 0xa9, 0x14]; // DUP, HASH160, PUSH_20
-        v.extend(key.pubkey_hash160().as_slice());
+        v.extend(pkh.as_slice());
         v.extend(&[0x88, 0xac]); // EQUALVERIFY, CHECKSIG
         v.into()
     }
@@ -164,6 +219,12 @@ impl ScriptPubkey {
         None
     }
 
+    /// Parse the script as a witness program. Returns `None` if it is not a valid witness
+    /// program of any version.
+    pub fn witness_program(&self) -> Option<WitnessProgram> {
+        WitnessProgram::from_script_pubkey(self.items())
+    }
+
     /// Inspect the `Script` to determine its type.
     pub fn standard_type(&self) -> ScriptType {
         if let Some(data) = self.extract_op_return_data() {
@@ -188,18 +249,17 @@ impl ScriptPubkey {
                     return ScriptType::SH(buf);
                 }
             }
-            0x16 => {
-                // WPKH
-                if items[0..2] == [0x00, 0x14] {
-                    let mut buf = Hash160Digest::default();
-                    buf.as_mut_slice().copy_from_slice(&items[2..22]);
-                    return ScriptType::WPKH(buf);
-                }
-            }
-            0x22 => {
-                if items[0..2] == [0x00, 0x20] {
+            0x16 | 0x22 => {
+                // WPKH and WSH
+                if let Some(program) = self.witness_program().filter(|p| p.version() == 0) {
+                    let program = program.program();
+                    if program.len() == 20 {
+                        let mut buf = Hash160Digest::default();
+                        buf.as_mut_slice().copy_from_slice(program);
+                        return ScriptType::WPKH(buf);
+                    }
                     let mut buf = Hash256Digest::default();
-                    buf.as_mut_slice().copy_from_slice(&items[2..34]);
+                    buf.as_mut_slice().copy_from_slice(program);
                     return ScriptType::WSH(buf);
                 }
             }
@@ -210,6 +270,60 @@ impl ScriptPubkey {
     }
 }
 
+impl ScriptSig {
+    /// Extract the redeem script from a p2sh spend. This is the last push in the script sig.
+    /// Returns `None` if the script sig is empty, malformed, contains a non-push opcode, or ends
+    /// with an empty push.
+    ///
+    /// The script sig is not checked against any prevout, so the result is only meaningful if
+    /// the input is known to spend a p2sh output.
+    pub fn redeem_script(&self) -> Option<Script> {
+        let script = self.items();
+        let mut cursor = 0;
+        let mut last = None;
+        while cursor < script.len() {
+            let (opcode, data, next) = crate::types::asm::next_op(script, cursor)?;
+            // p2sh script sigs must be push-only
+            if opcode > 0x60 {
+                return None;
+            }
+            last = Some(data);
+            cursor = next;
+        }
+        match last {
+            Some(data) if !data.is_empty() => Some(Script::from(data)),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a standard bare multisig script. Returns the threshold and the pubkeys, in script
+/// order, or `None` if the script is not a standard multisig script.
+pub fn parse_multisig(script: &[u8]) -> Option<(usize, Vec<&[u8]>)> {
+    let (first, rest) = script.split_first()?;
+    let (last, rest) = rest.split_last()?;
+    let (count, mut keys) = rest.split_last()?;
+    if !(0x51..=0x60).contains(first) || !(0x51..=0x60).contains(count) || *last != 0xae {
+        return None;
+    }
+    let m = (first - 0x50) as usize;
+    let n = (count - 0x50) as usize;
+
+    let mut pubkeys = vec![];
+    while let Some((len, tail)) = keys.split_first() {
+        let len = *len as usize;
+        if (len != 33 && len != 65) || tail.len() < len {
+            return None;
+        }
+        pubkeys.push(&tail[..len]);
+        keys = &tail[len..];
+    }
+    if pubkeys.len() != n || m > n {
+        return None;
+    }
+    Some((m, pubkeys))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -315,4 +429,118 @@ mod test {
             assert_eq!(script.standard_type(), *t);
         }
     }
+
+    #[test]
+    fn it_builds_compressed_and_uncompressed_p2pkh_scripts() {
+        use crate::enc::{Address, MainnetEncoder};
+        use coins_bip32::{
+            curve::{Privkey, ScalarDeserialize, Secp256k1},
+            keys::GenericPrivkey,
+            model::CanDerivePubkey,
+        };
+        use coins_core::enc::AddressEncoder;
+
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let pubkey = GenericPrivkey {
+            key: Privkey::from_privkey_array(secret).unwrap(),
+            backend: Some(Secp256k1::static_ref()),
+        }
+        .derive_verifying_key()
+        .unwrap();
+
+        let compressed = ScriptPubkey::p2pkh_with_compression(&pubkey, true);
+        let uncompressed = ScriptPubkey::p2pkh_with_compression(&pubkey, false);
+        assert_eq!(compressed, ScriptPubkey::p2pkh(&pubkey));
+        assert_eq!(
+            MainnetEncoder::encode_address(&compressed).unwrap(),
+            Address::PKH("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH".to_owned())
+        );
+        assert_eq!(
+            MainnetEncoder::encode_address(&uncompressed).unwrap(),
+            Address::PKH("1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_extracts_p2sh_redeem_scripts() {
+        // 2-of-3 multisig
+        let redeem_script = Script::new(hex::decode("52210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817982102c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee52102f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f953ae").unwrap());
+        let sig = hex::decode("3044022000000000000000000000000000000000000000000000000000000000000000010220000000000000000000000000000000000000000000000000000000000000000101").unwrap();
+
+        // OP_0 <sig> <sig> OP_PUSHDATA1 <redeem_script>
+        let mut script_sig = vec![0x00];
+        for _ in 0..2 {
+            script_sig.push(sig.len() as u8);
+            script_sig.extend(&sig);
+        }
+        script_sig.push(0x4c);
+        script_sig.push(redeem_script.len() as u8);
+        script_sig.extend(redeem_script.items());
+        let script_sig = ScriptSig::new(script_sig);
+
+        let extracted = script_sig.redeem_script().unwrap();
+        assert_eq!(extracted, redeem_script);
+        assert_eq!(
+            ScriptPubkey::p2sh(&extracted),
+            ScriptPubkey::p2sh(&redeem_script)
+        );
+
+        // nested p2wpkh
+        let nested =
+            ScriptSig::new(hex::decode("160014758ce550380d964051086798d6546bebdca27a73").unwrap());
+        assert_eq!(
+            nested.redeem_script().unwrap().items(),
+            &hex::decode("0014758ce550380d964051086798d6546bebdca27a73").unwrap()[..]
+        );
+
+        assert_eq!(ScriptSig::null().redeem_script(), None);
+        assert_eq!(ScriptSig::new(vec![0x00]).redeem_script(), None);
+        assert_eq!(ScriptSig::new(vec![0x4c, 0x02, 0x01]).redeem_script(), None);
+        // non-push opcode
+        assert_eq!(ScriptSig::new(vec![0x01, 0x01, 0x76]).redeem_script(), None);
+    }
+
+    #[test]
+    fn it_roundtrips_flat_witnesses() {
+        let witness: Witness = vec![
+            WitnessStackItem::null(),
+            WitnessStackItem::new(vec![0xaa; 72]),
+            WitnessStackItem::null(),
+            WitnessStackItem::new(vec![0xbb; 300]),
+        ];
+        let flat = witness.to_flat_bytes();
+        assert_eq!(&flat[..4], &[0x04, 0x00, 0x48, 0xaa]);
+        assert_eq!(flat.len(), 1 + 1 + 1 + 72 + 1 + 3 + 300);
+        assert_eq!(Witness::from_flat_bytes(&flat).unwrap(), witness);
+
+        let empty = Witness::default();
+        assert_eq!(empty.to_flat_bytes(), vec![0x00]);
+        assert_eq!(Witness::from_flat_bytes(&[0x00]).unwrap(), empty);
+
+        assert!(Witness::from_flat_bytes(&flat[..flat.len() - 1]).is_err());
+        let mut trailing = flat;
+        trailing.push(0);
+        assert!(Witness::from_flat_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn it_parses_multisig_scripts() {
+        let mut script = vec![0x52];
+        for key in [[2u8; 33], [3u8; 33]].iter() {
+            script.push(33);
+            script.extend(&key[..]);
+        }
+        script.extend(&[0x52, 0xae]);
+        let (threshold, pubkeys) = parse_multisig(&script).unwrap();
+        assert_eq!(threshold, 2);
+        assert_eq!(pubkeys, vec![&[2u8; 33][..], &[3u8; 33][..]]);
+
+        // threshold above the key count
+        script[0] = 0x53;
+        assert!(parse_multisig(&script).is_none());
+        // truncated pubkey
+        assert!(parse_multisig(&[0x51, 33, 2, 0x51, 0xae]).is_none());
+        assert!(parse_multisig(&[]).is_none());
+    }
 }
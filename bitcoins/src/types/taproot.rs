@@ -0,0 +1,598 @@
+//! Taproot script-path spend data, as defined in BIP341.
+//!
+//! A script-path spend reveals a control block as the last witness item (or the item before an
+//! annex). The control block commits to the leaf version, the parity of the output key, the
+//! internal key, and the merkle path from the leaf to the taproot output key.
+//!
+//! Also contains the BIP341 signature message and sighash.
+
+use coins_bip32::curve::{PointDeserialize, PointSerialize, Pubkey, Secp256k1, Secp256k1Backend};
+use coins_core::{
+    hashes::{Digest, Sha256},
+    ser::{self, ByteFormat},
+};
+use thiserror::Error;
+
+use crate::types::{
+    script::Witness,
+    tx::{TxError, TxResult},
+    txout::TxOut,
+    witness::WitnessTx,
+};
+
+/// The length of a control block with an empty merkle path
+pub const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+
+/// The length of each merkle path node in a control block
+pub const CONTROL_BLOCK_NODE_SIZE: usize = 32;
+
+/// The maximum number of merkle path nodes in a control block
+pub const TAPROOT_CONTROL_MAX_NODE_COUNT: usize = 128;
+
+/// The first byte of a witness annex
+pub const ANNEX_TAG: u8 = 0x50;
+
+/// The leaf version of BIP342 tapscript
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// Errors produced while parsing taproot data
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TaprootError {
+    /// The control block is not `33 + 32 * m` bytes for some `m <= 128`
+    #[error("Invalid control block length: {0}")]
+    InvalidControlBlockLength(usize),
+
+    /// The internal key is not the x coordinate of a curve point
+    #[error("Invalid internal key")]
+    InvalidInternalKey,
+}
+
+/// A BIP341 control block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlBlock {
+    /// The leaf version. The low bit of the first control block byte is masked off
+    pub leaf_version: u8,
+    /// True if the output key has an odd y-coordinate
+    pub output_key_parity: bool,
+    /// The x-only internal key
+    pub internal_key: [u8; 32],
+    /// The merkle path from the leaf to the root, in the order it is applied
+    pub merkle_path: Vec<[u8; 32]>,
+}
+
+impl ControlBlock {
+    /// Parse a control block. Errors if the length is not `33 + 32 * m` for some `m <= 128`.
+    /// The internal key is not checked to be a valid curve point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TaprootError> {
+        let len = bytes.len();
+        if len < CONTROL_BLOCK_BASE_SIZE
+            || (len - CONTROL_BLOCK_BASE_SIZE) % CONTROL_BLOCK_NODE_SIZE != 0
+            || (len - CONTROL_BLOCK_BASE_SIZE) / CONTROL_BLOCK_NODE_SIZE
+                > TAPROOT_CONTROL_MAX_NODE_COUNT
+        {
+            return Err(TaprootError::InvalidControlBlockLength(len));
+        }
+
+        let mut internal_key = [0u8; 32];
+        internal_key.copy_from_slice(&bytes[1..CONTROL_BLOCK_BASE_SIZE]);
+        let merkle_path = bytes[CONTROL_BLOCK_BASE_SIZE..]
+            .chunks(CONTROL_BLOCK_NODE_SIZE)
+            .map(|chunk| {
+                let mut node = [0u8; 32];
+                node.copy_from_slice(chunk);
+                node
+            })
+            .collect();
+
+        Ok(Self {
+            leaf_version: bytes[0] & 0xfe,
+            output_key_parity: bytes[0] & 1 == 1,
+            internal_key,
+            merkle_path,
+        })
+    }
+
+    /// Find and parse the control block in a taproot script-path witness. The control block is
+    /// the last item, or the second-to-last if the last item is an annex. Returns `None` if the
+    /// witness is too short to be a script-path spend.
+    pub fn from_witness(witness: &Witness) -> Option<Result<Self, TaprootError>> {
+        let mut items = witness.iter().rev();
+        let last = items.next()?;
+        let control_block = if witness.len() >= 2 && last.items().first() == Some(&ANNEX_TAG) {
+            items.next()?
+        } else {
+            last
+        };
+        // A script-path spend also reveals the script, before the control block
+        items.next()?;
+        Some(Self::from_bytes(control_block.items()))
+    }
+
+    /// The number of nodes in the merkle path. This is the depth of the leaf in the script tree.
+    pub fn depth(&self) -> usize {
+        self.merkle_path.len()
+    }
+
+    /// The serialized length of the control block
+    pub fn serialized_length(&self) -> usize {
+        CONTROL_BLOCK_BASE_SIZE + CONTROL_BLOCK_NODE_SIZE * self.merkle_path.len()
+    }
+
+    /// Serialize the control block
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_length());
+        bytes.push(self.leaf_version | self.output_key_parity as u8);
+        bytes.extend_from_slice(&self.internal_key);
+        for node in self.merkle_path.iter() {
+            bytes.extend_from_slice(node);
+        }
+        bytes
+    }
+
+    /// The merkle root of a script-path spend of `script`. The tapleaf hash of `script` is
+    /// combined with each node of the merkle path, smaller hash first.
+    pub fn merkle_root(&self, script: &[u8]) -> [u8; 32] {
+        let mut root = tapleaf_hash(self.leaf_version, script);
+        for node in self.merkle_path.iter() {
+            let (left, right) = if root < *node {
+                (root, *node)
+            } else {
+                (*node, root)
+            };
+            let mut msg = left.to_vec();
+            msg.extend(&right);
+            root = tagged_hash("TapBranch", &msg);
+        }
+        root
+    }
+
+    /// True if the x-only `output_key` commits to a script-path spend of `script` with this
+    /// control block. The internal key, tweaked by the merkle root, must equal the output key,
+    /// with the parity in the control block.
+    ///
+    /// Errors with `TaprootError::InvalidInternalKey` if the internal key is not on the curve.
+    pub fn verify_commitment(
+        &self,
+        output_key: &[u8; 32],
+        script: &[u8],
+    ) -> Result<bool, TaprootError> {
+        let mut internal = [0x02; 33];
+        internal[1..].copy_from_slice(&self.internal_key);
+        let internal =
+            Pubkey::from_pubkey_array(internal).map_err(|_| TaprootError::InvalidInternalKey)?;
+
+        let mut msg = self.internal_key.to_vec();
+        msg.extend(&self.merkle_root(script));
+        let tweak = tagged_hash("TapTweak", &msg);
+        let output = match Secp256k1::static_ref().tweak_pubkey(&internal, tweak) {
+            Ok(output) => output.pubkey_array(),
+            Err(_) => return Ok(false),
+        };
+        Ok(output[1..] == output_key[..] && (output[0] == 0x03) == self.output_key_parity)
+    }
+}
+
+/// Compute the BIP341 tapleaf hash of `script`
+pub fn tapleaf_hash(leaf_version: u8, script: &[u8]) -> [u8; 32] {
+    let mut msg = vec![leaf_version];
+    ser::write_compact_int(&mut msg, script.len() as u64).expect("writes to a vec do not fail");
+    msg.extend(script);
+    tagged_hash("TapLeaf", &msg)
+}
+
+/// The BIP341 default sighash type. It commits to the same data as `SIGHASH_ALL`, but is
+/// omitted from the signature.
+pub const SIGHASH_DEFAULT: u8 = 0x00;
+
+/// Compute a BIP340 tagged hash: `SHA256(SHA256(tag) || SHA256(tag) || msg)`
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(
+        &Sha256::new()
+            .chain(&tag_hash)
+            .chain(&tag_hash)
+            .chain(msg)
+            .finalize(),
+    );
+    digest
+}
+
+/// Serialize some items, and SHA256 the result
+fn sha256_items<T: ByteFormat<Error = ser::SerError>>(items: &[T]) -> TxResult<Vec<u8>> {
+    let mut data = vec![];
+    for item in items.iter() {
+        item.write_to(&mut data)?;
+    }
+    Ok(Sha256::digest(&data).to_vec())
+}
+
+/// Calculate the BIP341 sighash of input `index`, for a key-path or script-path spend.
+///
+/// `prevouts` must hold the output spent by each input, in input order. `leaf_hash` is the
+/// tapleaf hash of the executed script for a script-path spend, and `None` for a key-path spend.
+/// `annex` is the full annex, including its `0x50` tag, if the witness has one. The code
+/// separator position is always the default, `0xffffffff`.
+///
+/// ## Errors
+///
+/// - `TxError::UnknownSighash` if `sighash_type` is not a valid taproot sighash type
+/// - `TxError::PrevoutsLengthMismatch` if `prevouts` does not match the inputs
+/// - `TxError::IndexOutOfBounds` if `index` is not an input
+/// - `TxError::MissingOutput` for `SIGHASH_SINGLE` without an output at `index`
+pub fn taproot_sighash(
+    tx: &WitnessTx,
+    index: usize,
+    prevouts: &[TxOut],
+    sighash_type: u8,
+    leaf_hash: Option<[u8; 32]>,
+    annex: Option<&[u8]>,
+) -> TxResult<[u8; 32]> {
+    match sighash_type {
+        0x00..=0x03 | 0x81..=0x83 => {}
+        _ => return Err(TxError::UnknownSighash(sighash_type)),
+    }
+    let tx = &tx.legacy_tx;
+    if prevouts.len() != tx.vin.len() {
+        return Err(TxError::PrevoutsLengthMismatch {
+            tx_ins: tx.vin.len(),
+            prevouts: prevouts.len(),
+        });
+    }
+    if index >= tx.vin.len() {
+        return Err(TxError::IndexOutOfBounds {
+            index,
+            len: tx.vin.len(),
+        });
+    }
+
+    let anyone_can_pay = sighash_type & 0x80 == 0x80;
+    let output_type = sighash_type & 0x03;
+    let (none, single) = (output_type == 0x02, output_type == 0x03);
+    if single && index >= tx.vout.len() {
+        return Err(TxError::MissingOutput(index));
+    }
+
+    let mut msg = vec![0x00, sighash_type]; // epoch, hash type
+    msg.extend(&tx.version.to_le_bytes());
+    msg.extend(&tx.locktime.to_le_bytes());
+
+    if !anyone_can_pay {
+        let outpoints: Vec<_> = tx.vin.iter().map(|i| i.outpoint).collect();
+        msg.extend(sha256_items(&outpoints)?);
+        let amounts: Vec<u8> = prevouts
+            .iter()
+            .flat_map(|p| p.value.to_le_bytes().to_vec())
+            .collect();
+        msg.extend(Sha256::digest(&amounts));
+        let scripts: Vec<_> = prevouts.iter().map(|p| p.script_pubkey.clone()).collect();
+        msg.extend(sha256_items(&scripts)?);
+        let sequences: Vec<u8> = tx
+            .vin
+            .iter()
+            .flat_map(|i| i.sequence.to_le_bytes().to_vec())
+            .collect();
+        msg.extend(Sha256::digest(&sequences));
+    }
+    if !none && !single {
+        msg.extend(sha256_items(&tx.vout)?);
+    }
+
+    let spend_type = (leaf_hash.is_some() as u8) * 2 + annex.is_some() as u8;
+    msg.push(spend_type);
+
+    if anyone_can_pay {
+        let input = &tx.vin[index];
+        input.outpoint.write_to(&mut msg)?;
+        msg.extend(&prevouts[index].value.to_le_bytes());
+        prevouts[index].script_pubkey.write_to(&mut msg)?;
+        msg.extend(&input.sequence.to_le_bytes());
+    } else {
+        msg.extend(&(index as u32).to_le_bytes());
+    }
+
+    if let Some(annex) = annex {
+        let mut data = vec![];
+        ser::write_compact_int(&mut data, annex.len() as u64)?;
+        data.extend(annex);
+        msg.extend(Sha256::digest(&data));
+    }
+
+    if single {
+        msg.extend(sha256_items(&tx.vout[index..=index])?);
+    }
+
+    if let Some(leaf_hash) = leaf_hash {
+        msg.extend(&leaf_hash);
+        msg.push(0x00); // key version
+        msg.extend(&0xffff_ffffu32.to_le_bytes()); // code separator position
+    }
+
+    Ok(tagged_hash("TapSighash", &msg))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{BitcoinTransaction, LegacyTx, ScriptPubkey, WitnessStackItem};
+    use std::convert::TryInto;
+
+    #[test]
+    fn it_parses_control_blocks() {
+        let mut bytes = vec![0xc1];
+        bytes.extend(&[0x11; 32]);
+        bytes.extend(&[0x22; 32]);
+        bytes.extend(&[0x33; 32]);
+
+        let control_block = ControlBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(control_block.leaf_version, 0xc0);
+        assert!(control_block.output_key_parity);
+        assert_eq!(control_block.internal_key, [0x11; 32]);
+        assert_eq!(control_block.merkle_path, vec![[0x22; 32], [0x33; 32]]);
+        assert_eq!(control_block.depth(), 2);
+        assert_eq!(control_block.to_bytes(), bytes);
+
+        let witness: Witness = vec![
+            WitnessStackItem::new(vec![0x51]),
+            WitnessStackItem::new(bytes.clone()),
+            WitnessStackItem::new(vec![ANNEX_TAG, 0x00]),
+        ];
+        assert_eq!(
+            ControlBlock::from_witness(&witness),
+            Some(Ok(control_block))
+        );
+        assert_eq!(ControlBlock::from_witness(&witness[..1].to_vec()), None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_control_block_lengths() {
+        let lengths = [0, 32, 34, 64, 33 + 32 * 129];
+        for len in lengths.iter() {
+            assert_eq!(
+                ControlBlock::from_bytes(&vec![0xc0; *len]),
+                Err(TaprootError::InvalidControlBlockLength(*len))
+            );
+        }
+        ControlBlock::from_bytes(&vec![0xc0; 33 + 32 * 128]).unwrap();
+    }
+
+    #[test]
+    fn it_verifies_control_block_commitments() {
+        // scriptPubKey vector 1 from the BIP341 wallet test vectors
+        let script =
+            hex::decode("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac")
+                .unwrap();
+        let output_key: [u8; 32] =
+            hex::decode("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+                .unwrap()
+                .as_slice()
+                .try_into()
+                .unwrap();
+        let control_block = ControlBlock::from_bytes(
+            &hex::decode("c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(tapleaf_hash(TAPSCRIPT_LEAF_VERSION, &script)),
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"
+        );
+        assert!(control_block
+            .verify_commitment(&output_key, &script)
+            .unwrap());
+        assert!(!control_block
+            .verify_commitment(&output_key, &[0x51])
+            .unwrap());
+
+        let mut wrong_parity = control_block.clone();
+        wrong_parity.output_key_parity = false;
+        assert!(!wrong_parity
+            .verify_commitment(&output_key, &script)
+            .unwrap());
+
+        // a leaf at depth 2. Generated with an independent port of BIP341
+        let mut deep = control_block;
+        deep.merkle_path = vec![[0x22; 32], [0xee; 32]];
+        assert_eq!(
+            hex::encode(deep.merkle_root(&[0x51])),
+            "7481112815829bde9e94015c75e9f8d6d974b5a89af4f895ae744d4b0bc1a114"
+        );
+        let output_key: [u8; 32] =
+            hex::decode("6cd7158fcddd4a566304e206b21928acd4321c649da247039363136b4edeeec1")
+                .unwrap()
+                .as_slice()
+                .try_into()
+                .unwrap();
+        assert!(deep.verify_commitment(&output_key, &[0x51]).unwrap());
+
+        // x = 5 is not on the curve
+        let mut invalid = deep.clone();
+        invalid.internal_key = [0; 32];
+        invalid.internal_key[31] = 5;
+        assert_eq!(
+            invalid.verify_commitment(&output_key, &[0x51]),
+            Err(TaprootError::InvalidInternalKey)
+        );
+    }
+
+    #[test]
+    fn it_calculates_taproot_sighashes() {
+        // Generated with an independent port of the BIP341 signature message algorithm
+        let tx_hex = "020000000211111111111111111111111111111111111111111111111111111111111111110000000000ffffffff22222222222222222222222222222222222222222222222222222222222222220100000000fdffffff0250c30000000000001600143333333333333333333333333333333333333333204e000000000000225120444444444444444444444444444444444444444444444444444444444444444420a10700";
+        let tx = LegacyTx::deserialize_hex(tx_hex).unwrap().into_witness();
+        let prevouts = vec![
+            TxOut::new(
+                40_000,
+                ScriptPubkey::new(hex::decode(format!("5120{}", "55".repeat(32))).unwrap()),
+            ),
+            TxOut::new(
+                35_000,
+                ScriptPubkey::new(hex::decode(format!("5120{}", "66".repeat(32))).unwrap()),
+            ),
+        ];
+        let leaf = Some([0x77; 32]);
+        let annex: &[u8] = &[ANNEX_TAG, 0xaa, 0xbb];
+
+        let cases: [(usize, u8, Option<[u8; 32]>, Option<&[u8]>, &str); 10] = [
+            (
+                0,
+                0x00,
+                None,
+                None,
+                "c78aa444bbbc600ae968bbb4a31eec9b64af69e115f80881f07f1c4c72a0a8e9",
+            ),
+            (
+                1,
+                0x01,
+                None,
+                None,
+                "9bdea97eb4232be3add82cf6bc9f526796e2b569b36fb36cd2bff54e78fa690f",
+            ),
+            (
+                0,
+                0x02,
+                None,
+                None,
+                "cfea012a95e44e535b9b43e1c6c2edcedf782af4f10d2b80d66269ebf23cc3f5",
+            ),
+            (
+                1,
+                0x03,
+                None,
+                None,
+                "9cd1cc28a400fe5d097db9fc412d4210528003e68e241074e20358e7ab1d109c",
+            ),
+            (
+                0,
+                0x81,
+                None,
+                None,
+                "34d57f4dbc4969ed14d11ad696d9d011d14a55059dacd656100534fccfab24f3",
+            ),
+            (
+                1,
+                0x82,
+                None,
+                None,
+                "5ce421df83bc55973ef90ad1b8909f8f2db1329e1038017a184f36aa5c4301a7",
+            ),
+            (
+                1,
+                0x83,
+                None,
+                None,
+                "8ffcf1e6a0a108b5e82f265f440d2a0336a8e3fad2147c9bd775004cdab7eb1e",
+            ),
+            (
+                0,
+                0x00,
+                leaf,
+                None,
+                "751cca1a2fd273dbbf524ee0d144095d4126057cfea0f23541c6a139cc6157e6",
+            ),
+            (
+                1,
+                0x03,
+                leaf,
+                Some(annex),
+                "da82f73776ee0e53374d01d88124080a3e556b4b47c3fa1686af51dc6cfe1034",
+            ),
+            (
+                0,
+                0x81,
+                None,
+                Some(annex),
+                "c30a29999bfd50de52d9a7a501ab4cffa5eddb34d21315f3461e936e9099e0ed",
+            ),
+        ];
+        for (index, sighash_type, leaf_hash, annex, expected) in cases.iter() {
+            let sighash =
+                taproot_sighash(&tx, *index, &prevouts, *sighash_type, *leaf_hash, *annex).unwrap();
+            assert_eq!(hex::encode(sighash), *expected);
+        }
+
+        match taproot_sighash(&tx, 0, &prevouts, 0x04, None, None) {
+            Err(TxError::UnknownSighash(0x04)) => {}
+            _ => assert!(false, "expected UnknownSighash"),
+        }
+        match taproot_sighash(&tx, 0, &prevouts[..1], 0x00, None, None) {
+            Err(TxError::PrevoutsLengthMismatch { .. }) => {}
+            _ => assert!(false, "expected PrevoutsLengthMismatch"),
+        }
+        match taproot_sighash(&tx, 2, &prevouts, 0x00, None, None) {
+            Err(TxError::IndexOutOfBounds { index: 2, len: 2 }) => {}
+            _ => assert!(false, "expected IndexOutOfBounds"),
+        }
+    }
+
+    #[test]
+    fn it_calculates_bip341_key_path_sighashes() {
+        // keyPathSpending from the BIP341 wallet test vectors
+        let tx_hex = "02000000097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd990000000000fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a418420000000000fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd050000000000000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af10100000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b0065cd1d";
+        let tx = LegacyTx::deserialize_hex(tx_hex).unwrap().into_witness();
+        let utxos = [
+            (
+                "512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+                420_000_000,
+            ),
+            (
+                "5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+                462_000_000,
+            ),
+            (
+                "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+                294_000_000,
+            ),
+            (
+                "5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e",
+                504_000_000,
+            ),
+            (
+                "512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605",
+                630_000_000,
+            ),
+            ("00147dd65592d0ab2fe0d0257d571abf032cd9db93dc", 378_000_000),
+            (
+                "512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831",
+                672_000_000,
+            ),
+            (
+                "5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+                546_000_000,
+            ),
+            (
+                "512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220",
+                588_000_000,
+            ),
+        ];
+        let prevouts: Vec<TxOut> = utxos
+            .iter()
+            .map(|(script, value)| {
+                TxOut::new(*value, ScriptPubkey::new(hex::decode(script).unwrap()))
+            })
+            .collect();
+
+        let cases = [
+            (
+                0,
+                0x03,
+                "2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555",
+            ),
+            (
+                1,
+                0x83,
+                "325a644af47e8a5a2591cda0ab0723978537318f10e6a63d4eed783b96a71a4d",
+            ),
+            (
+                3,
+                0x01,
+                "bf013ea93474aa67815b1b6cc441d23b64fa310911d991e713cd34c7f5d46669",
+            ),
+        ];
+        for (index, sighash_type, expected) in cases.iter() {
+            let sighash =
+                taproot_sighash(&tx, *index, &prevouts, *sighash_type, None, None).unwrap();
+            assert_eq!(hex::encode(sighash), *expected);
+        }
+    }
+}
@@ -1,20 +1,31 @@
 //! Bitcoin transaction types and associated sighash arguments.
-use std::io::{Error as IOError, Read, Write};
+use std::{
+    collections::HashSet,
+    io::{Error as IOError, Read, Write},
+};
 use thiserror::Error;
 
 use coins_core::{
+    enc::EncodingResult,
     hashes::*,
     ser::{ByteFormat, SerError},
     types::tx::Transaction,
 };
 
+use coins_bip32::curve::{
+    PointDeserialize, Pubkey, Secp256k1, Secp256k1Backend, SigSerialize, Signature,
+};
+
 use crate::{
     hashes::TXID,
     types::{
+        asm,
+        interpreter::{verify_script, ScriptError},
         legacy::*,
-        script::Witness,
+        script::{ScriptPubkey, ScriptType, Witness},
         txin::{BitcoinOutpoint, BitcoinTxIn},
         txout::TxOut,
+        utxo::UTXO,
         witness::*,
     },
 };
@@ -79,6 +90,449 @@ impl BitcoinTx {
             _ => false,
         }
     }
+
+    /// Copy the transaction, stripping all script sigs and witnesses. The result is a legacy
+    /// transaction, in the form used to build legacy sighash preimages. It is useful for
+    /// re-signing, or for converting a signed transaction back into a PSBT.
+    ///
+    /// The txid is unchanged only if every script sig was already empty, e.g. when all inputs
+    /// spend native witness outputs.
+    pub fn unsigned_clone(&self) -> BitcoinTx {
+        let legacy = self.as_legacy();
+        LegacyTx {
+            version: legacy.version,
+            vin: legacy.vin.iter().map(BitcoinTxIn::unsigned).collect(),
+            vout: legacy.vout.clone(),
+            locktime: legacy.locktime,
+        }
+        .into()
+    }
+
+    /// Encode each output's script pubkey as an address, using the encoder `E`. Outputs that
+    /// have no address, such as `OP_RETURN` and non-standard outputs, produce `None`.
+    pub fn output_addresses<E>(&self) -> Vec<Option<String>>
+    where
+        E: crate::enc::encoder::BitcoinEncoderMarker,
+    {
+        self.outputs()
+            .iter()
+            .map(|output| {
+                E::encode_address(&output.script_pubkey)
+                    .ok()
+                    .map(|address| address.as_string())
+            })
+            .collect()
+    }
+
+    /// True if the outputs paying `address` sum to at least `min_amount` sats. A payment may be
+    /// split across several outputs to the same address. The address is parsed with the
+    /// encoder `E`, and outputs are matched by script pubkey.
+    pub fn paid_to<E>(&self, address: &str, min_amount: u64) -> EncodingResult<bool>
+    where
+        E: crate::enc::encoder::BitcoinEncoderMarker,
+    {
+        let script_pubkey = E::decode_address(&E::string_to_address(address)?)?;
+        let paid = self
+            .outputs()
+            .iter()
+            .filter(|o| o.script_pubkey == script_pubkey)
+            .fold(0u64, |acc, o| acc.saturating_add(o.value));
+        Ok(paid >= min_amount)
+    }
+
+    /// The data pushed by each `OP_RETURN` output, in output order, with one item per push.
+    /// `OP_0` pushes an empty item, and `OP_1NEGATE` and `OP_1` through `OP_16` push their
+    /// number as a single byte. Outputs whose `OP_RETURN` is followed by anything other than
+    /// pushes are skipped.
+    pub fn op_return_data(&self) -> Vec<Vec<Vec<u8>>> {
+        self.outputs()
+            .iter()
+            .filter_map(|output| op_return_pushes(output.script_pubkey.items()))
+            .collect()
+    }
+
+    /// Produce a JSON object in the format of Bitcoin Core's `decoderawtransaction` and verbose
+    /// `getrawtransaction` RPCs. If `prevouts` is provided, it must contain the output spent by
+    /// each input, in the same order as the vin, and the fee is included.
+    ///
+    /// The address encoder `T` is used to populate each output's `address` field. Requires the
+    /// `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn to_rpc_json<T>(&self, prevouts: Option<&[TxOut]>) -> TxResult<serde_json::Value>
+    where
+        T: crate::enc::encoder::BitcoinEncoderMarker,
+    {
+        use crate::types::script::ScriptType;
+        use coins_core::enc::AddressEncoder;
+        use serde_json::{json, Map, Value};
+
+        let witnesses = self.witnesses();
+        let vin: Vec<Value> = self
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let mut obj = Map::new();
+                if input.outpoint == BitcoinOutpoint::null() {
+                    obj.insert(
+                        "coinbase".into(),
+                        json!(hex::encode(input.script_sig.items())),
+                    );
+                } else {
+                    obj.insert("txid".into(), json!(input.outpoint.txid_be_hex()));
+                    obj.insert("vout".into(), json!(input.outpoint.idx));
+                    obj.insert(
+                        "scriptSig".into(),
+                        json!({
+                            "asm": asm::disassemble(input.script_sig.items(), true),
+                            "hex": hex::encode(input.script_sig.items()),
+                        }),
+                    );
+                }
+                if let Some(witness) = witnesses.get(i).filter(|w| !w.is_empty()) {
+                    let items: Vec<String> =
+                        witness.iter().map(|w| hex::encode(w.items())).collect();
+                    obj.insert("txinwitness".into(), json!(items));
+                }
+                obj.insert("sequence".into(), json!(input.sequence));
+                Value::Object(obj)
+            })
+            .collect();
+
+        let vout: Vec<Value> = self
+            .outputs()
+            .iter()
+            .enumerate()
+            .map(|(n, output)| {
+                let spk = &output.script_pubkey;
+                let type_name = match spk.standard_type() {
+                    ScriptType::PKH(_) => "pubkeyhash",
+                    ScriptType::SH(_) => "scripthash",
+                    ScriptType::WPKH(_) => "witness_v0_keyhash",
+                    ScriptType::WSH(_) => "witness_v0_scripthash",
+                    ScriptType::OP_RETURN(_) => "nulldata",
+                    ScriptType::NonStandard => "nonstandard",
+                };
+                let mut script_pubkey = Map::new();
+                script_pubkey.insert("asm".into(), json!(asm::disassemble(spk.items(), false)));
+                script_pubkey.insert("hex".into(), json!(hex::encode(spk.items())));
+                if let Ok(address) = T::encode_address(spk) {
+                    script_pubkey.insert("address".into(), json!(address.as_string()));
+                }
+                script_pubkey.insert("type".into(), json!(type_name));
+                json!({
+                    "value": output.value as f64 / 100_000_000f64,
+                    "n": n,
+                    "scriptPubKey": Value::Object(script_pubkey),
+                })
+            })
+            .collect();
+
+        let mut obj = Map::new();
+        obj.insert("txid".into(), json!(self.txid().to_be_hex()));
+        let wtxid = match self {
+            BitcoinTx::Witness(tx) => tx.wtxid().to_be_hex(),
+            BitcoinTx::Legacy(_) => self.txid().to_be_hex(),
+        };
+        obj.insert("hash".into(), json!(wtxid));
+        obj.insert("version".into(), json!(self.version()));
+        obj.insert("size".into(), json!(self.serialized_length()));
+        obj.insert("vsize".into(), json!(self.vsize()));
+        obj.insert("weight".into(), json!(self.weight()));
+        obj.insert("locktime".into(), json!(self.locktime()));
+        obj.insert("vin".into(), json!(vin));
+        obj.insert("vout".into(), json!(vout));
+        if let Some(prevouts) = prevouts {
+            let fee = self.fee(prevouts)?;
+            obj.insert("fee".into(), json!(fee as f64 / 100_000_000f64));
+        }
+        obj.insert("hex".into(), json!(self.serialize_hex()));
+        Ok(Value::Object(obj))
+    }
+
+    /// Deserialize a transaction, reading at most `max_bytes` from the reader. Use this when
+    /// parsing untrusted data, as length prefixes are attacker-controlled. Errors with
+    /// `SerError::TxTooLarge` if the transaction does not complete within `max_bytes`. Malformed
+    /// transactions that fit within `max_bytes` produce their deserialization error instead.
+    ///
+    /// One byte past `max_bytes` may be read, to tell the two apart.
+    pub fn read_from_bounded<R>(reader: &mut R, max_bytes: u64) -> TxResult<Self>
+    where
+        R: Read,
+    {
+        let limit = max_bytes.saturating_add(1);
+        let mut bounded = reader.take(limit);
+        let result = Self::read_from(&mut bounded);
+        if limit - bounded.limit() > max_bytes {
+            return Err(SerError::TxTooLarge(max_bytes).into());
+        }
+        result
+    }
+
+    /// Deserialize a transaction, then check that it does not create value. `prevouts` must
+    /// contain the output spent by each input, in the same order as the vin. Errors if any
+    /// output value or the output total exceeds `MAX_MONEY`, or if the outputs spend more than
+    /// the prevouts provide.
+    ///
+    /// This is NOT full consensus validation. Scripts, signatures, locktimes, and the prevouts
+    /// themselves are not checked.
+    pub fn read_from_validated<R>(
+        reader: &mut R,
+        prevouts: &[TxOut],
+    ) -> Result<Self, ValidationError>
+    where
+        R: Read,
+    {
+        let tx = Self::read_from(reader)?;
+        if prevouts.len() != tx.inputs().len() {
+            return Err(TxError::PrevoutsLengthMismatch {
+                tx_ins: tx.inputs().len(),
+                prevouts: prevouts.len(),
+            }
+            .into());
+        }
+
+        let mut out_value = 0u64;
+        for output in tx.outputs().iter() {
+            out_value = out_value
+                .checked_add(output.value)
+                .filter(|v| output.value <= MAX_MONEY && *v <= MAX_MONEY)
+                .ok_or(ValidationError::ValueOutOfRange(output.value))?;
+        }
+        let in_value = prevouts
+            .iter()
+            .try_fold(0u64, |acc, p| acc.checked_add(p.value))
+            .unwrap_or(std::u64::MAX);
+        if out_value > in_value {
+            return Err(ValidationError::ValueCreated {
+                in_value,
+                out_value,
+            });
+        }
+        Ok(tx)
+    }
+
+    /// Check whether every input carries valid signatures for the output it spends. `prevouts`
+    /// must contain the UTXO spent by each input, in the same order as the vin.
+    ///
+    /// This answers "can I broadcast this", not "is this valid". Each input is executed by the
+    /// `interpreter`, as in `verify`, so the supported input types are those it supports.
+    /// Signatures with non-standard sighash bytes are interpreted as consensus does. Locktimes
+    /// are not checked. Returns false for any unsupported input, or if `prevouts` does not match
+    /// the vin.
+    pub fn is_fully_signed(&self, prevouts: &[UTXO]) -> bool {
+        if prevouts.len() != self.inputs().len() {
+            return false;
+        }
+        let empty = Witness::default();
+        self.inputs()
+            .iter()
+            .zip(prevouts.iter())
+            .enumerate()
+            .all(|(index, (input, utxo))| {
+                let witness = self.witnesses().get(index).unwrap_or(&empty);
+                verify_script(
+                    &input.script_sig,
+                    &utxo.script_pubkey,
+                    witness,
+                    self,
+                    index,
+                    utxo.value,
+                    false,
+                )
+                .is_ok()
+            })
+    }
+
+    /// Verify every input against the output it spends, by executing its scripts. `prevouts`
+    /// must contain the output spent by each input, in the same order as the vin. If `strict`
+    /// is true, signatures with non-standard sighash bytes fail verification.
+    ///
+    /// See the `interpreter` module for the supported script types. Inputs spending any other
+    /// script type fail with `ScriptError::Unsupported` or
+    /// `ScriptError::UnsupportedWitnessVersion`, rather than passing. Locktimes and sequences
+    /// are not checked.
+    ///
+    /// ## Errors
+    ///
+    /// - `TxError::PrevoutsLengthMismatch` if `prevouts` does not match the vin
+    /// - `TxError::ScriptFailure` with the index of the first input that fails
+    pub fn verify(&self, prevouts: &[TxOut], strict: bool) -> TxResult<()> {
+        if prevouts.len() != self.inputs().len() {
+            return Err(TxError::PrevoutsLengthMismatch {
+                tx_ins: self.inputs().len(),
+                prevouts: prevouts.len(),
+            });
+        }
+        let empty = Witness::default();
+        for (index, (input, prevout)) in self.inputs().iter().zip(prevouts.iter()).enumerate() {
+            let witness = self.witnesses().get(index).unwrap_or(&empty);
+            verify_script(
+                &input.script_sig,
+                &prevout.script_pubkey,
+                witness,
+                self,
+                index,
+                prevout.value,
+                strict,
+            )
+            .map_err(|source| TxError::ScriptFailure { index, source })?;
+        }
+        Ok(())
+    }
+
+    /// True if any two outputs pay the same script pubkey. Reuse is an exact script pubkey
+    /// match, so e.g. P2PKH and P2WPKH outputs to the same key are not reuse.
+    pub fn detects_address_reuse(&self) -> bool {
+        self.detects_address_reuse_with(&HashSet::new())
+    }
+
+    /// True if any two outputs pay the same script pubkey, or if any output pays a script
+    /// pubkey in `used`.
+    pub fn detects_address_reuse_with(&self, used: &HashSet<ScriptPubkey>) -> bool {
+        let mut seen = HashSet::new();
+        self.outputs()
+            .iter()
+            .any(|o| used.contains(&o.script_pubkey) || !seen.insert(&o.script_pubkey))
+    }
+
+    /// Guess which output is change, for privacy analysis of a third-party transaction. Returns
+    /// `None` if there are fewer than 2 outputs, or if the heuristics don't single out exactly
+    /// one output.
+    ///
+    /// Two heuristics narrow the candidates. Each is skipped if it would eliminate every
+    /// candidate:
+    ///
+    /// 1. Round values. Outputs with values that are multiples of `ROUND_VALUE` are assumed to
+    ///    be payments.
+    /// 2. Script type. If every input spends the same type of script, outputs of other types are
+    ///    assumed to be payments. Input types are inferred from script sigs and witnesses: a
+    ///    2-item witness ending in a compressed pubkey is P2WPKH, any other bare witness is
+    ///    P2WSH, a witness with a script sig is P2SH, and a script sig of a signature and pubkey
+    ///    is P2PKH.
+    ///
+    /// These are guesses. Wallets that randomize change types or amounts defeat them, and
+    /// payments may have unround values. The unnecessary-input heuristic is not applied, as it
+    /// requires the prevout values.
+    pub fn guess_change_output(&self) -> Option<usize> {
+        let outputs = self.outputs();
+        if outputs.len() < 2 {
+            return None;
+        }
+        let mut candidates: Vec<usize> = (0..outputs.len()).collect();
+
+        let unround: Vec<usize> = candidates
+            .iter()
+            .cloned()
+            .filter(|i| outputs[*i].value % ROUND_VALUE != 0)
+            .collect();
+        if !unround.is_empty() {
+            candidates = unround;
+        }
+
+        let witnesses = self.witnesses();
+        let mut input_kinds = self
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(i, input)| input_kind(input, witnesses.get(i)));
+        let first = input_kinds.next().flatten();
+        if first.is_some() && input_kinds.all(|k| k == first) {
+            let matching: Vec<usize> = candidates
+                .iter()
+                .cloned()
+                .filter(|i| output_kind(&outputs[*i].standard_type()) == first)
+                .collect();
+            if !matching.is_empty() {
+                candidates = matching;
+            }
+        }
+
+        match candidates.as_slice() {
+            [change] => Some(*change),
+            _ => None,
+        }
+    }
+}
+
+/// Output values that are multiples of this are considered round by
+/// `BitcoinTx::guess_change_output`. 0.0001 BTC
+pub const ROUND_VALUE: u64 = 10_000;
+
+/// The standard script types distinguished by `BitcoinTx::guess_change_output`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SpendKind {
+    PKH,
+    SH,
+    WPKH,
+    WSH,
+}
+
+fn output_kind(script_type: &ScriptType) -> Option<SpendKind> {
+    match script_type {
+        ScriptType::PKH(_) => Some(SpendKind::PKH),
+        ScriptType::SH(_) => Some(SpendKind::SH),
+        ScriptType::WPKH(_) => Some(SpendKind::WPKH),
+        ScriptType::WSH(_) => Some(SpendKind::WSH),
+        _ => None,
+    }
+}
+
+/// Infer the type of script an input spends from its script sig and witness
+fn input_kind(input: &BitcoinTxIn, witness: Option<&Witness>) -> Option<SpendKind> {
+    let script_sig = input.script_sig.items();
+    match witness {
+        Some(witness) if !witness.is_empty() => {
+            if !script_sig.is_empty() {
+                Some(SpendKind::SH)
+            } else if witness.len() == 2 && witness[1].len() == 33 {
+                Some(SpendKind::WPKH)
+            } else {
+                Some(SpendKind::WSH)
+            }
+        }
+        _ => match script_sig_pushes(script_sig).as_slice() {
+            [sig, pubkey] if asm::is_der_signature(sig) && [33, 65].contains(&pubkey.len()) => {
+                Some(SpendKind::PKH)
+            }
+            [] => None,
+            _ => Some(SpendKind::SH),
+        },
+    }
+}
+
+/// Sum the value of every output in `txs` that pays one of `scripts`. This is the received
+/// side of a wallet balance. Returns `None` if the total overflows a `u64`.
+pub fn sum_outputs_to_scripts(txs: &[BitcoinTx], scripts: &HashSet<ScriptPubkey>) -> Option<u64> {
+    txs.iter()
+        .flat_map(|tx| tx.outputs().iter())
+        .filter(|output| scripts.contains(&output.script_pubkey))
+        .try_fold(0u64, |acc, output| acc.checked_add(output.value))
+}
+
+/// The maximum number of satoshis that may exist. No output value, or total of output values,
+/// may exceed this.
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+/// Errors produced by `BitcoinTx::read_from_validated`
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    /// Bubbled up from deserialization
+    #[error(transparent)]
+    TxError(#[from] TxError),
+
+    /// An output value, or the running total of output values, exceeds `MAX_MONEY`
+    #[error("Output value {0} is out of range")]
+    ValueOutOfRange(u64),
+
+    /// The outputs spend more value than the prevouts provide
+    #[error("Output value {out_value} exceeds input value {in_value}")]
+    ValueCreated {
+        /// The total value of the prevouts
+        in_value: u64,
+        /// The total value of the outputs
+        out_value: u64,
+    },
 }
 
 impl ByteFormat for BitcoinTx {
@@ -235,6 +689,72 @@ pub enum TxError {
     /// No inputs in vin
     #[error("Vin may not be empty")]
     EmptyVin,
+
+    /// Number of prevouts provided does not match the number of inputs
+    #[error("Prevout count mismatch. Got {prevouts} prevouts for {tx_ins} inputs")]
+    PrevoutsLengthMismatch {
+        /// The number of inputs in the tx
+        tx_ins: usize,
+        /// The number of prevouts provided
+        prevouts: usize,
+    },
+
+    /// A signature used a sighash byte that is consensus-valid but non-standard
+    #[error("Non-standard sighash byte: {}", .0)]
+    NonStandardSighash(u8),
+
+    /// The outputs spend more value than the prevouts provide
+    #[error("Output value {out_value} exceeds input value {in_value}")]
+    NegativeFee {
+        /// The total value of the prevouts
+        in_value: u64,
+        /// The total value of the outputs
+        out_value: u64,
+    },
+
+    /// A sum of values exceeded `u64::MAX`
+    #[error("Value overflow")]
+    ValueOverflow,
+
+    /// The spend size of a UTXO could not be estimated, as its script type is not supported
+    #[error("Can't estimate the size of spending {0:?}")]
+    UnestimatedInput(BitcoinOutpoint),
+
+    /// An output index was out of range
+    #[error("No output at index {0}")]
+    MissingOutput(usize),
+
+    /// A fee bump could not reach the target fee while leaving a change output above the dust
+    /// limit
+    #[error("Insufficient value for fee bump. Have {available} sats. Need {required}")]
+    InsufficientFeeBump {
+        /// The value available for the change output and fee
+        available: u64,
+        /// The new fee plus the dust limit
+        required: u64,
+    },
+
+    /// No input spends this outpoint
+    #[error("No input spends {0:?}")]
+    MissingInput(BitcoinOutpoint),
+
+    /// An insertion index was past the end of the vector
+    #[error("Index {index} out of bounds for length {len}")]
+    IndexOutOfBounds {
+        /// The requested index
+        index: usize,
+        /// The length of the vector
+        len: usize,
+    },
+
+    /// An input failed script verification
+    #[error("Input {index} failed script verification: {source}")]
+    ScriptFailure {
+        /// The index of the input
+        index: usize,
+        /// The interpreter error
+        source: ScriptError,
+    },
 }
 
 /// Type alias for result with TxError
@@ -268,6 +788,91 @@ pub trait BitcoinTransaction:
     /// For witness txns, this will ALWAYS be the same length as the input vector.
     fn witnesses(&self) -> &[Witness];
 
+    /// Return the BIP141 weight of the transaction. This is 3 times the size of the tx without
+    /// witnesses, plus the full serialized size.
+    fn weight(&self) -> usize {
+        self.as_legacy().serialized_length() * 3 + self.serialized_length()
+    }
+
+    /// Return the virtual size of the transaction in vbytes. This is the weight divided by 4,
+    /// rounded up.
+    fn vsize(&self) -> usize {
+        (self.weight() + 3) / 4
+    }
+
+    /// Calculate the fee paid by the transaction. `prevouts` must contain the output spent by
+    /// each input, in the same order as the vin.
+    fn fee(&self, prevouts: &[TxOut]) -> TxResult<u64> {
+        if prevouts.len() != self.inputs().len() {
+            return Err(TxError::PrevoutsLengthMismatch {
+                tx_ins: self.inputs().len(),
+                prevouts: prevouts.len(),
+            });
+        }
+        let in_value = prevouts
+            .iter()
+            .try_fold(0u64, |acc, p| acc.checked_add(p.value))
+            .ok_or(TxError::ValueOverflow)?;
+        let out_value = self
+            .outputs()
+            .iter()
+            .try_fold(0u64, |acc, o| acc.checked_add(o.value))
+            .ok_or(TxError::ValueOverflow)?;
+        if out_value > in_value {
+            return Err(TxError::NegativeFee {
+                in_value,
+                out_value,
+            });
+        }
+        Ok(in_value - out_value)
+    }
+
+    /// Calculate the fee rate of the transaction in sat/vbyte. `prevouts` must contain the
+    /// output spent by each input, in the same order as the vin.
+    fn fee_rate(&self, prevouts: &[TxOut]) -> TxResult<f64> {
+        Ok(self.fee(prevouts)? as f64 / self.vsize() as f64)
+    }
+
+    /// The smallest fee that meets `min_relay_rate` sat/vbyte for this transaction's vsize. A
+    /// transaction paying less will not be relayed. Compare against `fee` to check a
+    /// transaction.
+    ///
+    /// The vsize is taken from the current script sigs and witnesses, so the transaction should
+    /// be signed. Errors with `TxError::ValueOverflow` if the fee exceeds `u64::MAX`.
+    fn minimum_viable_fee(&self, min_relay_rate: u64) -> TxResult<u64> {
+        (self.vsize() as u64)
+            .checked_mul(min_relay_rate)
+            .ok_or(TxError::ValueOverflow)
+    }
+
+    /// Detect the sighash mode of the first signature in each input. Signatures are located by
+    /// shape: the first DER-encoded item in the witness, or if there is no witness, the first
+    /// direct push in the script sig. Inputs without a recognizable signature produce `None`.
+    ///
+    /// If `strict` is true, signatures with non-standard sighash bytes produce a
+    /// `TxError::NonStandardSighash`. Otherwise, they are interpreted as consensus does.
+    fn signature_sighashes(&self, strict: bool) -> TxResult<Vec<Option<Sighash>>> {
+        let witnesses = self.witnesses();
+        let mut result = vec![];
+        for (i, input) in self.inputs().iter().enumerate() {
+            let sig = match witnesses.get(i) {
+                Some(witness) if !witness.is_empty() => witness
+                    .iter()
+                    .map(|item| item.items())
+                    .find(|item| asm::is_der_signature(item)),
+                _ => script_sig_pushes(input.script_sig.items())
+                    .into_iter()
+                    .find(|item| asm::is_der_signature(item)),
+            };
+            let sighash = match sig {
+                Some(sig) => Some(Sighash::from_sig_byte(sig[sig.len() - 1], strict)?),
+                None => None,
+            };
+            result.push(sighash);
+        }
+        Ok(result)
+    }
+
     /// Get a reference to the output by
     fn txout_from_outpoint(&self, outpoint: &BitcoinOutpoint) -> Option<&TxOut> {
         if outpoint.txid == self.txid() && (outpoint.idx as usize) < self.outputs().len() {
@@ -344,6 +949,137 @@ impl Sighash {
             _ => Err(TxError::UnknownSighash(flag)),
         }
     }
+
+    /// Interpret the sighash byte appended to a signature. If `strict` is false, non-standard
+    /// bytes select the same inputs and outputs as they do in consensus: bits other than `0x80`
+    /// and the low 5 bits are ignored, and unknown base types are treated as `ALL`. If `strict`
+    /// is true, non-standard bytes produce a `TxError::NonStandardSighash`.
+    ///
+    /// Note that consensus commits to the raw byte in the sighash preimage, not to the byte of
+    /// the returned `Sighash`. Signatures with non-standard bytes must be checked against a
+    /// preimage ending in the raw byte.
+    pub fn from_sig_byte(flag: u8, strict: bool) -> Result<Sighash, TxError> {
+        match Sighash::from_u8(flag) {
+            Ok(sighash) => Ok(sighash),
+            Err(_) if strict => Err(TxError::NonStandardSighash(flag)),
+            Err(_) => {
+                let acp = flag & 0x80 == 0x80;
+                match (flag & 0x1f, acp) {
+                    (0x02, false) => Ok(Sighash::None),
+                    (0x02, true) => Ok(Sighash::NoneACP),
+                    (0x03, false) => Ok(Sighash::Single),
+                    (0x03, true) => Ok(Sighash::SingleACP),
+                    (_, false) => Ok(Sighash::All),
+                    (_, true) => Ok(Sighash::AllACP),
+                }
+            }
+        }
+    }
+}
+
+/// Collect the pushes in a script sig. `OP_0` pushes an empty item, as e.g. the multisig
+/// dummy. Parsing stops at the first opcode that is not a push.
+fn script_sig_pushes(script: &[u8]) -> Vec<&[u8]> {
+    let mut pushes = vec![];
+    let mut cursor = 0;
+    while let Some((opcode, data, next)) = asm::next_op(script, cursor) {
+        if opcode > 0x4e {
+            break;
+        }
+        pushes.push(data);
+        cursor = next;
+    }
+    pushes
+}
+
+/// Collect the pushes following the `OP_RETURN` in a null data script, one item per push.
+/// `None` if the script is not an `OP_RETURN`, or contains a malformed push or any other
+/// opcode.
+fn op_return_pushes(script: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let (first, script) = script.split_first()?;
+    if *first != 0x6a {
+        return None;
+    }
+    let mut pushes = vec![];
+    let mut cursor = 0;
+    while cursor < script.len() {
+        let (opcode, data, next) = asm::next_op(script, cursor)?;
+        let push = match opcode {
+            0x00..=0x4e => data.to_vec(),
+            0x4f => vec![0x81],
+            0x51..=0x60 => vec![opcode - 0x50],
+            _ => return None,
+        };
+        pushes.push(push);
+        cursor = next;
+    }
+    Some(pushes)
+}
+
+/// Hash a sighash preimage with its trailing hash type replaced by the raw sighash byte of a
+/// signature. Legacy and BIP143 preimages both end in the hash type, as a 4-byte LE integer.
+pub(crate) fn hash_with_sig_byte(mut preimage: Vec<u8>, sig_byte: u8) -> Hash256Digest {
+    let len = preimage.len();
+    preimage[len - 4..].copy_from_slice(&(sig_byte as u32).to_le_bytes());
+    Hash256::digest(&preimage).into()
+}
+
+/// Check a signature with its trailing sighash byte against a serialized pubkey. `sighash`
+/// produces the digest for the signature's sighash mode and its raw sighash byte. The digest
+/// must commit to the raw byte, as consensus does.
+pub(crate) fn check_sig<F>(sig: &[u8], pubkey: &[u8], sighash: F) -> bool
+where
+    F: Fn(Sighash, u8) -> Option<Hash256Digest>,
+{
+    let (flag, der) = match sig.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let pubkey = match pubkey.len() {
+        33 => {
+            let mut buf = [0u8; 33];
+            buf.copy_from_slice(pubkey);
+            Pubkey::from_pubkey_array(buf)
+        }
+        65 => {
+            let mut buf = [0u8; 65];
+            buf.copy_from_slice(pubkey);
+            Pubkey::from_pubkey_array_uncompressed(buf)
+        }
+        _ => return false,
+    };
+    let digest = Sighash::from_sig_byte(*flag, false)
+        .ok()
+        .and_then(|mode| sighash(mode, *flag));
+    match (pubkey, Signature::try_from_der(der), digest) {
+        (Ok(pubkey), Ok(sig), Some(digest)) => Secp256k1::static_ref()
+            .verify_digest(&pubkey, digest, &sig)
+            .is_ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+impl BitcoinTx {
+    /// Parse a hex transaction, reserialize it, and assert that the result is byte-for-byte
+    /// identical, and that the reported lengths agree.
+    pub(crate) fn assert_roundtrip(hex: &str) {
+        let tx = BitcoinTx::deserialize_hex(hex).unwrap();
+        let mut buf = vec![];
+        let written = tx.write_to(&mut buf).unwrap();
+
+        assert_eq!(hex::encode(&buf), hex);
+        assert_eq!(written, hex.len() / 2);
+        assert_eq!(tx.serialized_length(), hex.len() / 2);
+        assert_eq!(tx.is_witness(), &hex[8..12] == "0001");
+
+        let legacy = tx.as_legacy();
+        let mut buf = vec![];
+        let written = legacy.write_to(&mut buf).unwrap();
+        assert_eq!(written, buf.len());
+        assert_eq!(legacy.serialized_length(), buf.len());
+        assert_eq!(&LegacyTx::read_from(&mut buf.as_slice()).unwrap(), legacy);
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +1087,291 @@ mod tests {
     use super::*;
     use crate::prelude::*;
 
+    #[test]
+    fn it_extracts_op_return_data() {
+        let vin = vec![BitcoinTxIn::new(
+            BitcoinOutpoint::null(),
+            ScriptSig::null(),
+            0xffff_ffff,
+        )];
+        let scripts = [
+            // a single push
+            "6a0401020304",
+            // not an OP_RETURN
+            "0014758ce550380d964051086798d6546bebdca27a73",
+            // a direct push and an OP_PUSHDATA1
+            "6a02abcd4c03010203",
+            // OP_0, OP_1NEGATE, OP_1, OP_16, and a direct push
+            "6a004f51600102",
+            // no pushes
+            "6a",
+            // a push past the end of the script
+            "6a0401",
+            // a non-push opcode
+            "6a02abcd75",
+        ];
+        let vout: Vec<TxOut> = scripts
+            .iter()
+            .map(|script| TxOut::new(0, ScriptPubkey::new(hex::decode(script).unwrap())))
+            .collect();
+        let tx = BitcoinTx::new(2, vin, vout, 0).unwrap();
+
+        let expected: Vec<Vec<Vec<u8>>> = vec![
+            vec![vec![1, 2, 3, 4]],
+            vec![vec![0xab, 0xcd], vec![1, 2, 3]],
+            vec![vec![], vec![0x81], vec![1], vec![16], vec![2]],
+            vec![],
+        ];
+        assert_eq!(tx.op_return_data(), expected);
+    }
+
+    #[test]
+    fn it_bounds_deserialization_length() {
+        // version, then a vin prefix claiming 2^32 inputs, then a few kb of zeroed inputs
+        let mut crafted = hex::decode("01000000ff0000000001000000").unwrap();
+        crafted.extend(vec![0u8; 4096]);
+
+        match BitcoinTx::read_from_bounded(&mut crafted.as_slice(), 1000) {
+            Err(TxError::SerError(SerError::TxTooLarge(1000))) => {}
+            _ => assert!(false, "expected TxTooLarge"),
+        }
+
+        let tx_hex = "02000000000101ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0173d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18700cafd0700";
+        let bytes = hex::decode(tx_hex).unwrap();
+        let expected = BitcoinTx::read_from(&mut bytes.as_slice()).unwrap();
+        let tx = BitcoinTx::read_from_bounded(&mut bytes.as_slice(), bytes.len() as u64).unwrap();
+        assert_eq!(tx, expected);
+
+        // version, then a non-minimal vin prefix. Invalid, rather than too large, when the
+        // limit falls exactly at its end
+        let malformed = hex::decode("01000000fd0100").unwrap();
+        match BitcoinTx::read_from_bounded(&mut malformed.as_slice(), 7) {
+            Err(TxError::SerError(SerError::NonMinimalVarInt)) => {}
+            _ => assert!(false, "expected NonMinimalVarInt"),
+        }
+        match BitcoinTx::read_from_bounded(&mut malformed.as_slice(), 6) {
+            Err(TxError::SerError(SerError::TxTooLarge(6))) => {}
+            _ => assert!(false, "expected TxTooLarge"),
+        }
+    }
+
+    #[test]
+    fn it_rejects_value_creation_when_validating() {
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx_bytes = hex::decode(tx_hex).unwrap();
+        // outputs total 0x01ef35a1 + 0x0098c399 = 42_465_594 sats
+        let enough = [TxOut::new(42_465_594, ScriptPubkey::default())];
+        let short = [TxOut::new(42_465_593, ScriptPubkey::default())];
+
+        BitcoinTx::read_from_validated(&mut tx_bytes.as_slice(), &enough).unwrap();
+        match BitcoinTx::read_from_validated(&mut tx_bytes.as_slice(), &short) {
+            Err(ValidationError::ValueCreated {
+                in_value,
+                out_value,
+            }) => {
+                assert_eq!(in_value, 42_465_593);
+                assert_eq!(out_value, 42_465_594);
+            }
+            _ => assert!(false, "expected ValueCreated"),
+        }
+        match BitcoinTx::read_from_validated(&mut tx_bytes.as_slice(), &[]) {
+            Err(ValidationError::TxError(TxError::PrevoutsLengthMismatch { .. })) => {}
+            _ => assert!(false, "expected PrevoutsLengthMismatch"),
+        }
+    }
+
+    #[test]
+    fn it_calculates_vsize_and_fee_rate() {
+        let tx_hex = "02000000000101ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0173d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18700cafd0700";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        // 83 non-witness bytes, 86 total bytes
+        assert_eq!(tx.weight(), 83 * 3 + 86);
+        assert_eq!(tx.vsize(), 84);
+
+        let prevouts = vec![TxOut::new(0x01d373 + 840, ScriptPubkey::null())];
+        assert_eq!(tx.fee(&prevouts).unwrap(), 840);
+        assert_eq!(tx.fee_rate(&prevouts).unwrap(), 10.0);
+        assert_eq!(tx.minimum_viable_fee(1).unwrap(), 84);
+        assert_eq!(tx.minimum_viable_fee(3).unwrap(), 252);
+        match tx.minimum_viable_fee(u64::MAX) {
+            Err(TxError::ValueOverflow) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+
+        match tx.fee(&[]) {
+            Err(TxError::PrevoutsLengthMismatch { .. }) => {}
+            _ => assert!(false, "expected PrevoutsLengthMismatch"),
+        }
+        match tx.fee(&[TxOut::new(1, ScriptPubkey::null())]) {
+            Err(TxError::NegativeFee { .. }) => {}
+            _ => assert!(false, "expected NegativeFee"),
+        }
+
+        let overflowing = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_ffff)
+            .pay_script_pubkey(u64::MAX, ScriptPubkey::null())
+            .pay_script_pubkey(1, ScriptPubkey::null())
+            .build()
+            .unwrap();
+        match overflowing.fee(&prevouts) {
+            Err(TxError::ValueOverflow) => {}
+            _ => assert!(false, "expected ValueOverflow"),
+        }
+    }
+
+    #[test]
+    fn it_detects_signature_sighashes() {
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        assert_eq!(
+            tx.signature_sighashes(true).unwrap(),
+            vec![Some(Sighash::All)]
+        );
+
+        // Same tx with the signature's sighash byte changed to 0x41
+        let nonstandard = tx_hex.replace("8e10615bed01", "8e10615bed41");
+        let tx = BitcoinTx::deserialize_hex(&nonstandard).unwrap();
+        assert_eq!(
+            tx.signature_sighashes(false).unwrap(),
+            vec![Some(Sighash::All)]
+        );
+        match tx.signature_sighashes(true) {
+            Err(TxError::NonStandardSighash(0x41)) => {}
+            _ => assert!(false, "expected NonStandardSighash"),
+        }
+
+        // Same tx with an OP_0 before the signature, as in a multisig script sig
+        let dummy = tx_hex.replace("000000006b48", "000000006c0048");
+        let tx = BitcoinTx::deserialize_hex(&dummy).unwrap();
+        assert_eq!(
+            tx.signature_sighashes(true).unwrap(),
+            vec![Some(Sighash::All)]
+        );
+
+        // unsigned witness tx
+        let tx_hex = "02000000000101ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0173d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18700cafd0700";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        assert_eq!(tx.signature_sighashes(true).unwrap(), vec![None]);
+    }
+
+    #[test]
+    fn it_interprets_sighash_bytes() {
+        let cases = [
+            (0x01, Sighash::All),
+            (0x83, Sighash::SingleACP),
+            (0x00, Sighash::All),
+            (0x41, Sighash::All),
+            (0xc2, Sighash::NoneACP),
+            (0x84, Sighash::AllACP),
+        ];
+        for (flag, expected) in cases.iter() {
+            assert_eq!(Sighash::from_sig_byte(*flag, false).unwrap(), *expected);
+        }
+        match Sighash::from_sig_byte(0x41, true) {
+            Err(TxError::NonStandardSighash(0x41)) => {}
+            _ => assert!(false, "expected NonStandardSighash"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn it_produces_rpc_json() {
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        let json = tx.to_rpc_json::<MainnetEncoder>(None).unwrap();
+
+        assert_eq!(
+            json["txid"],
+            "03ee4f7a4e68f802303bc659f8f817964b4b74fe046facc3ae1be4679d622c45"
+        );
+        assert_eq!(json["hash"], json["txid"]);
+        assert_eq!(json["size"], 226);
+        assert_eq!(json["vsize"], 226);
+        assert_eq!(json["weight"], 904);
+        assert_eq!(json["locktime"], 410393);
+        assert_eq!(json["vin"][0]["vout"], 0);
+        assert_eq!(json["vin"][0]["sequence"], 4294967294u32);
+        assert!(json["vin"][0]["scriptSig"]["asm"]
+            .as_str()
+            .unwrap()
+            .contains("[ALL]"));
+        assert_eq!(json["vout"][0]["value"], 0.32454049);
+        assert_eq!(json["vout"][0]["scriptPubKey"]["type"], "pubkeyhash");
+        assert_eq!(
+            json["vout"][0]["scriptPubKey"]["asm"],
+            "OP_DUP OP_HASH160 bc3b654dca7e56b04dca18f2566cdaf02e8d9ada OP_EQUALVERIFY OP_CHECKSIG"
+        );
+        assert_eq!(
+            json["vout"][0]["scriptPubKey"]["address"],
+            "1JAHBxA51vwp5C2zpSB15VbxSZK3hVJs2H"
+        );
+        assert_eq!(json["hex"], tx_hex);
+        assert!(json.get("fee").is_none());
+    }
+
+    #[test]
+    fn it_roundtrips_transactions() {
+        let corpus = [
+        "01000000000101813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac0019430600",
+        "01000000000101b77bebb3ac480e99c0d95a4c812137b116e65e2f3b3a66a36d0e252928d460180100000000ffffffff03982457000000000017a91417b8e0f150215cc70bf2fb58070041d655b162dd8740e133000000000017a9142535e444f7d55f0500c1f86609d6cfc289576b698747abfb0100000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d040047304402205c6a889efa26955bef7ce2b08792e63e25eac9859080f0d83912b0ea833d7eb402205f859f4640f1600db5012b467ec05bb4ae1779640c1b5fadc8908960740e52b30147304402201c239ea25cfeadfa9493a1b0d136d70f50f821385972b7188c4329c2bf2d23a302201ee790e4b6794af6567f85a226a387d5b0222c3dc90d2fc558d09e08062b8271016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000",
+        "01000000000101f1e46af69e3ab97a3b195dbc34af1e2131ec31d53a6e331ab714504d27b6bd940400000000ffffffff03e0a57e000000000017a914e88869b88866281ab166541ad8aafba8f8aba47a8780841e00000000001976a9140e5c3c8d420c7f11e88d76f7b860d471e6517a4488aca31843a7380000002200201bf8a1831db5443b42a44f30a121d1b616d011ab15df62b588722a845864cc990400483045022100a74e04708f8032ce177c09642556945a5f5938de821edfa5df959c0ca61cb00d02207ea3b9353e0250a8a1440809a24a1d73c1c26d2c46e12dd96c7564ea4f8c6ee001473044022066611fd52c104f8be623cca6195ab0aa5dfc58408297744ff0d7b32da218c7d002200302be14cc76abaab271d848448d0b3cd3083d4dea76af495d1b1137d129d3120169522102489ec44d0358045c4be092978c40e574790820ebbc3bf069bffc12bda57af27d2102a4bf3a2bdbbcf2e68bbf04566052bbaf45dfe230a7a6de18d97c242fd85e9abc21038d4d2936c6e57f2093c2a43cb17fcf582afb1d312a1e129f900156075a490ae753ae00000000",
+        "0100000000010672b45d6cfedc1d1eef6e9ad59a3588b58c138bd9c69c1c6663ffd83ae715c501da02000000ffffffffde87b4f1735cdd064afbcb808b3a6fe1d94bf83acf4716bce9091874ccc14af70100000000ffffffff60e70f7c23b8004413e7c2ac413e1e4d7bb392fefa2537035f9eb167c5d86eca3203000000ffffffff871910b8993509c26e4828d735547ca8f2d5727a5c9a651375544b22e40c55e8df02000000ffffffff444e259ce59625976ba5017dfb96293429b4690c8f61b5a7913d8fda32caa7251303000000ffffffff6a2e4327183e1ac697fa74f6d3ad1207082b02ee223ae25bef5158833f7e5dc1f202000000ffffffff025c9222000000000017a914709afc8e5b252a4e82b1084c251920b84dbc874a87f823060000000000160014fe6de21323c914bbfa502d200b98feab52ab6a2902483045022100c3f388701109cf0f59cef2f4582d8b6ff447795738aff35f24ddfab10b27946a022028d0decd4ce796333e8925b2d5deee6b960c66d9f95edebd1eef78023b25175101210376989e32539258a55ab252d1a91b81d30e9b5003cc95915faf9dda073f4d1b6502483045022100ccb431ca38d9a2c05cddd85c5351c7c5ee8bc7d73756df2ac5a05a24dece6f4602203e63687bbb3d8460e190ec62db1099751e57aa4a7d78f6d0d078f731049549e5012102432ac2035716878ce3202a4b745dbbe56990a474757454165ca9f2c3d989927a02473044022042ca31752904fe67ae6b4ca2faae33feb52ecefd44d031ea2afbd7604f800c4202205a79e0cff92282dd108fc9dad3864d3bb5255ac8f26e3d1b2b54d7e6f197186701210376989e32539258a55ab252d1a91b81d30e9b5003cc95915faf9dda073f4d1b6502483045022100f53030f7e533610e2707d4690e4375162fc17d1076723d987b092bd6d04ca08e02204c9445301cad690aa6b341d81589d0b21a6632dad852526de0d70a3968529d7601210376989e32539258a55ab252d1a91b81d30e9b5003cc95915faf9dda073f4d1b6502473044022033ac6853031c8219abcbbb01fa5b85fd207e4a1f842116eda153732c27d0a88a022079306e204940262c9926750dc2145c1030a9af24fe6bfc58a888cf0eeb6b754801210376989e32539258a55ab252d1a91b81d30e9b5003cc95915faf9dda073f4d1b65024730440220373f0bb56f8e1897d8ba4eecebdb4609d0f067f91cb408042be22e34fd7848b90220397639a4fe96c058fe54b8f45a40f7a0af4a555c69a3b492d58e545b7d8317c201210376989e32539258a55ab252d1a91b81d30e9b5003cc95915faf9dda073f4d1b6500000000",
+        "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600",
+        "02000000000101ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0173d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18700cafd0700",
+        "02000000000102ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffffee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0173d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f1870000cafd0700",
+        "02000000000102ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffffee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0273d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18773d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f1870000cafd0700",
+        "0200000001268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6000000006a473044022070b2245123e6bf474d60c5b50c043d4c691a5d2435f09a34a7662a9dc251790a022001329ca9dacf280bdf30740ec0390422422c81cb45839457aeb76fc12edd95b3012102657d118d3357b8e0f4c2cd46db7b39f6d9c38d9a70abcb9b2de5dc8dbfe4ce31feffffff02d3dff505000000001976a914d0c59903c5bac2868760e90fd521a4665aa7652088ac00e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787b32e1300",
+        "0200000002ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffffee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0273d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18773d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18700000000",
+        "02000000190067bcee941b9ead28616f460eb4ae9f4e24be7009d1f2cd71a9eb6339d0335f000000006b483045022100fcfacd312f567472ed75a418a4ccd0feb0106bd708b035071e89973050a29196022024d1bc3eac60ffb302a8ccb6bd5e178a35a95a8d373a4b9267c3ffb09794793f0121026bd73b80e05e8ae6d1f3f7e80c2eb73914a00a001f6f03b2c2ac482e8e4ff876feffffff018f7a1f34af38ac7c6a4a7aafbd52ddd0b4d358aaeb5e34d827b44bdf6d8da8170000006b483045022100b99902562b110b6bdfb40dda931f19b4e4a788e376b5bf4a4954c116cb47cc8f02203d1301b7566db8ba097e4eeff6f334b41d0b14bd9387980282dbca8022e4b8650121023e8fe11a589cf00c3fd5e48fdb32c70b9c2875e94ea1c65c8df3f80da0855533feffffff16813f6c08c2a465c612efde6f04a5ba0ca118a484f88b7c9a72004caa745a93000000006a4730440220318a4a89106952450de442d0f4b22e104abcd1131c8a17563bbad896db0539e6022025c5772c02d47a0562242dacccaf4c6ec6d7b1b1708d0a07035ade2cdad8fcb7012102940929eba90c4f84e43f707fa8e0f1df6f44002672e18c276d288171839edf70feffffff1f8322524eb40facfa30ae16a2bf8423d822bef854ae2a9aaf54c6a894f0b9cf010000006a47304402200bc9ee586f8f489ade18a8159b827f214640aa8716a6966852b7650951fa88f10220690ae8cd988f4b1bcb7426fceb00631e2e2279a0b7de4f79ec5866a5c17ba4c9012102fc5f07382a4b34342a44194fffeac33d7f3f4a29b3c0ff5cc44eb5802b755fe7feffffff29520d1c309848adb58bcc23860c9ea6647a4895bf86f6742dba2e3708ddd8c2000000006b483045022100dab6ceeee20a4b00fd79b48dbc169622d01943d13138602ec2b2492b9cfae17d02206ce99952a17e1d2c023b8f09d1c9a3d4e0d7395bc1ba186e8e5e5bdb7b3e1ab801210321320de95f03b9165239d1f0a56e93a7eff4afdd877f53e94139bf68728976e1feffffff2de3ae8b56e53d0d5e44355f9cae59183454091728b7f68dbc51ba94a908ca37000000006b483045022100ed71d6153ea96f0361f38d0badd31e69db55aed79d25f733d06ac709205ed1c702203f72fd860b037f511025f754ce255619018487d8bd7e6ac8670403ce065ef07e0121037b0512c03b7306f5acc25a06cd9e1625e40214ee080ac9c232a1fdb6b9973e55feffffff31decdd1ddbde39e952063088ccbcaa0f65a6fe861ffaa8d204988b696f01b31010000006b483045022100c475c969622d3fd27f216bf585af3abdb7786a1a7f4254adebb74274002b217b02202a49d8c943dfd19981784bb8fdaeafa4b7d09e1c3ee68cacf93289d1d620e54b012102fc5f07382a4b34342a44194fffeac33d7f3f4a29b3c0ff5cc44eb5802b755fe7feffffff324d466d667183cef61645e83a11a47eaa372a8d23ca2a1efba2aff337989317000000006b483045022100f05415ee89bba91d1d7a81f12ef1421255a8ee2112fd4b4b18007f57b5dea6dc02200d5a0ba64dc5da0d46a5d05651a2819b4dd1c77a1f355fff8d95c5c6cee0bd320121023e8fe11a589cf00c3fd5e48fdb32c70b9c2875e94ea1c65c8df3f80da0855533feffffff3281884e07ccdc497521f5ac7185a85eb00cf2fd069cd572a662d31edbeb3087010000006b4830450221008aee2eed61a1d678afbb3cafb16c66e436d26ecd8af39ce79cf17f1f0a3dd6450220617759454d2b0dd9cdfca2def48c78e56552baaab8735f56e6e5b0377603da74012103d61ed189dfdb1811aec888f9dbc4f1a522b3357df9eaf9ea8c0d7659fec5961bfeffffff336973d29a509685c73145fb58de9c55a9cf7a0d505e03c8afd99c7e05824a4d010000006b483045022100851df1c7bfab745d9920fb00e40f41a984c35af04af76d3761850943bf04df1202200e679f9f13209a1818ae25ebce677aa0d182920c07ffc8f3ff3ae83b1e67f6ff012103b222dacee77955c7c663e367606c4690ad3cb0f7438c6d73208a9918266a7d16feffffff35e1c055092f31128d706ff1da995083727931560a7fc85e1961dcd8a7715039010000006a47304402203669c622696f3e0de268a3841d416ad04f5b4c65ed0e09cf915497af976d3e0902201f29d1a959a4db1ef7b6c021fa7fcdb8530ba2177ff4143d63077e5700bf32400121024dcb1572c6fddd5f436327d1ea0f70a2cc4d9c6bdef4c1b2e0c620a4d598625ffeffffff3607dd0a4f07f9edd0b8210ead4e42872f3d3c1921ab2d0a1ebe5fa773b896c9010000006a473044022051695ffb7e640ecbb3acf930758911f0c771776f4091c0823380a4bddbe4795702200af03d48b136c82d865a1d3f46c435d48ae7a8d17f13369283ef691eaf9bab8d012103145ce92f50f413d8b0a4f848ae40080327d2ff1a0bb7f83e295a0e8f8a20ea54feffffff38dbd4430247b8050f7632e46f275bd874ee85e6a471f927e12692615a9cc310010000006b483045022100b94528fe52d95ae2bd4a25c54f275527c468294e005a2e692341fcad4fcdef9b02201d2ab19eaf2a436345420abd5fc30d56f24fd06db4eb0b45125c078b47154e5001210309270ca331ad524452e0070b5bc30129b49bc265a0e99888ec705bbd97b86528feffffff59fa66ddacc9388da2e15c0284efb3e128deabcca25e07cefb7a71c86fc38c20010000006a47304402204047f05ed0f53e74ed3bc0cf73f04cea6b9d816233ed0b8c9b27f8d7ed4cf5a802200d95d736b50e03c773b8c193236318c555ed9f729b47253db025cb2ad9b90b3c0121026bd73b80e05e8ae6d1f3f7e80c2eb73914a00a001f6f03b2c2ac482e8e4ff876feffffff6c89effceffdde175c0f8b5f8aab1c872682d7d1dbe3fdc8cd0ca0f51f160596010000006a4730440220163261c4bec5ff3b03e0bd66fc12bc4e39a78cea8ec618763470b2e18191d1c502201972b15c227293f41c2ba512dfd1d53ae0e606a52ed17df7331380fdbcdd7c07012102095c5f0110ba3a0c0a26c3d65cdcdb868ab618b39dfee05b67adc1aaaf391a4afeffffff6d1ad281e8cf6c5014e5d261c98928296a0db65c9534030bc36e0835064f2f49010000006b48304502210082bb38950f54a957b55769c60799ca3ea35cc2ecf00f69256c972a399f98ff5102206987cbe82209c170e12837b4a1ee5fead3410253a83020ee090560a3917b62d70121026f979f52ec0f5eddad28aa19e4d34984b7012f9f14aedf326a96fccad028b35efeffffff79b9479c7b1a9c08d5d9ac4835b2cfc33c811cdc04cd07a86a9c385b82bfbde0010000006a473044022032b7ee46c2462390ebfeb24b166c6b2efede0bb4e240f7cd258a71e2bc7db45702204e4a8499ce2c5afed3e29bcf00d041d6e532c1dbeffaf0df6f66149a4e7ef96901210321320de95f03b9165239d1f0a56e93a7eff4afdd877f53e94139bf68728976e1feffffff7d873fc09b41a66d9f8315d08d9bd2645a45021549ada840421e3b53765087bd000000006a473044022004f8ba0fde7c626482907d75e5989a7a743bb8e173704f6c289d69a171a18508022021e2ea7999e24f0123dfa0ce0e7b5e80e4ad8bd453575fad4fa48d11f3add7790121037b0512c03b7306f5acc25a06cd9e1625e40214ee080ac9c232a1fdb6b9973e55feffffff7f5d34d7fcee0eb3383a2ae1525b3556f0c7062918e4ceb0c33599d1e215980e010000006a473044022070b517e81f413018aa0f62f3d162b76deba241a1047b1e90b400c905ec4221fb0220261c385746b7dcc94b69899e87e1829807eba48e044a43e0a123a29179d91a0e012102095c5f0110ba3a0c0a26c3d65cdcdb868ab618b39dfee05b67adc1aaaf391a4afeffffff8f7ed9ebd3fd9fe6a5166d7f2c6813423dfbb934660a78cd1ca6188693d27d5c010000006b483045022100f4ff5c1e9611fca54f8dc2888e1abe8d2a30a221f734e83f1a20513bda9d5c640220649a799bafcab15b83a4bbde2cf5c7dd6b9b5450c8b2fa46610ea2539c578962012103bca3d56ce2dc497abbe906520466f571acedca4cddf54561ebf78916fe1ebb03feffffffbb1c7ed78969657467e57a6e14c64c22d407cb444edd19d086d27fe9f2f1d1bb010000006a47304402200eaabc38016aa4f3a19a17cd76110f9c64344dae4dcaf32dac2fe056986601620220603c0effbf507a6b25995b8d0e7419ed8fb0025316bbe0c0b22534355676245b0121026c6b3ae401fad3995614758a867db8de62cf0e8a10921f9e75698a3e75216f5bfeffffffcc6d5ef9cc6fa7436712f2bd2d1076819e9c97fe26146cfd5610beb645b4de34000000006a47304402206d629507f5c0692beea672623f1d48260c70008174cb8f1dae2ff57a604fde4b0220513af0d014422533a06eff6a5470223361364e4fa650c5d3c7ef365a8993a25d0121026bd73b80e05e8ae6d1f3f7e80c2eb73914a00a001f6f03b2c2ac482e8e4ff876feffffffd766a1e22667bdab32a3892943b2193376af9446eb3fc43292c294e81ceacc7b000000006a47304402200462679e3e87c676ea57bb170d2ca847691108a5acf893822acf86435f0f4b0502204e53320234017a9e2643cca05b19565f1427de7269d378c3171a79ccb1d70db20121027c9823fed06672bd3e8964bc7cd692ca16963463045ff173ccced7a2388b7282feffffffd7ccf4ccb1d32661af74a31d6c2c94112ad4059f486867400cf1de940616077c000000006b4830450221009d6e61345554abd05d87105d5d9558e7c786cf5e535eb841b669063b29df629402207aa515b8253b3a124fc33c98182d08f1d48ae7b1519c4fab5f422498cb91d30f0121026f979f52ec0f5eddad28aa19e4d34984b7012f9f14aedf326a96fccad028b35efeffffffed6e91690db0afbc395296606829ade5757003b519214b907a221d039f573be0010000006a473044022078aef7597f04eada1a47b6e1b0b837f7e446ec1e95a595af0c4ce4c907584e24022035dc2b05e39c08edd837e42e3160d354191c25cf6a686dac67249212ec508d0b0121037b0512c03b7306f5acc25a06cd9e1625e40214ee080ac9c232a1fdb6b9973e55feffffff02df3c0f00000000001976a914b0bde9b890a18362b2aac9c0e006e3ecab2e737288ac31f7ad070100000017a914c5041e89dbf1850f256e74f2cc5459afc3c7f05387f19d0900",
+        ];
+        for tx_hex in corpus.iter() {
+            BitcoinTx::assert_roundtrip(tx_hex);
+        }
+    }
+
+    #[test]
+    fn it_errors_cleanly_on_huge_vin_prefix() {
+        // version, then a vin prefix claiming 1 billion inputs, and no input data
+        let tx_hex = "01000000fe00ca9a3b";
+        match BitcoinTx::deserialize_hex(tx_hex) {
+            Err(TxError::SerError(SerError::IOError(_))) => {}
+            _ => assert!(false, "expected IOError"),
+        }
+    }
+
+    #[test]
+    fn it_resolves_output_addresses() {
+        use crate::enc::encoder::MainnetEncoder;
+
+        let tx: BitcoinTx = LegacyTx::new(
+            1,
+            vec![BitcoinTxIn::default()],
+            vec![
+                TxOut::new(
+                    1,
+                    [&[0x76u8, 0xa9, 0x14][..], &[0; 20], &[0x88, 0xac]]
+                        .concat()
+                        .into(),
+                ),
+                TxOut::op_return(b"hello"),
+                TxOut::new(2, [vec![0x00u8, 0x14], vec![0; 20]].concat().into()),
+                TxOut::new(3, vec![0x51u8].into()),
+            ],
+            0,
+        )
+        .unwrap()
+        .into();
+        assert_eq!(
+            tx.output_addresses::<MainnetEncoder>(),
+            vec![
+                Some("1111111111111111111114oLvT2".to_owned()),
+                None,
+                Some("bc1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq9e75rs".to_owned()),
+                None,
+            ]
+        );
+    }
+
     #[test]
     fn it_calculates_legacy_sighashes_and_txids() {
         // pulled from riemann-py helpers
@@ -403,6 +1424,160 @@ mod tests {
         assert_eq!(tx.sighash(&args).unwrap(), single_anyonecanpay);
     }
 
+    #[test]
+    fn it_calculates_consensus_legacy_sighashes() {
+        // matches the riemann-py vectors in it_calculates_legacy_sighashes_and_txids
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = LegacyTx::deserialize_hex(tx_hex).unwrap();
+        let prevout_script =
+            Script::deserialize_hex("17a91424d6008f143af0cca57344069c46661aa4fcea2387").unwrap();
+        for flag in [
+            Sighash::All,
+            Sighash::AllACP,
+            Sighash::Single,
+            Sighash::SingleACP,
+        ]
+        .iter()
+        {
+            let args = LegacySighashArgs {
+                index: 0,
+                sighash_flag: *flag,
+                prevout_script: prevout_script.clone(),
+            };
+            assert_eq!(
+                legacy_sighash(&tx, 0, &prevout_script, *flag as u32),
+                tx.sighash(&args).unwrap().as_slice()
+            );
+        }
+
+        // 2 inputs, 2 outputs. Generated with a port of Bitcoin Core's SignatureHash
+        let tx_hex = "02000000000102ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffffee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0273d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18773d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f1870000cafd0700";
+        let tx = WitnessTx::deserialize_hex(tx_hex)
+            .unwrap()
+            .as_legacy()
+            .clone();
+        // OP_CODESEPARATOR p2pkh OP_CODESEPARATOR [abab] OP_DROP OP_CHECKSIG
+        let script: Script =
+            hex::decode("ab76a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ab02abab75ac")
+                .unwrap()
+                .into();
+        let cases = [
+            (
+                0,
+                Sighash::All,
+                "e7e25ee2f47bea626fc32558dfeef0f8c62b11465f7cb1539abedda3886bc056",
+            ),
+            (
+                1,
+                Sighash::All,
+                "b35276d3786d2cd4f83a003b2cb9fad3d0ca69ba713ec56d58f5ad93e8ab47a5",
+            ),
+            (
+                0,
+                Sighash::None,
+                "7030605448e4628b0617ca06bcc7340ba4acc4a225638cea6e46bca843a0ef97",
+            ),
+            (
+                1,
+                Sighash::None,
+                "fe9b0f4c80a4629cd7c2d8aa486bf1b64b2ccff9564ccf907ca1ea1f3fbe7b2e",
+            ),
+            (
+                1,
+                Sighash::NoneACP,
+                "61158039900ede074ac1af369fcd2391670b6b0eac884cbea4af5df3a5d3acd3",
+            ),
+            (
+                0,
+                Sighash::Single,
+                "9d2d29047a96d75e753806cbb4fbc60b853b0dad9835137afafe4d99b6002231",
+            ),
+            (
+                1,
+                Sighash::Single,
+                "dfe0d259539ce5c3f0b1ccfd9cbc78982b2047c9675c221841408aa64b0bdca3",
+            ),
+            (
+                0,
+                Sighash::SingleACP,
+                "18991b62a6082f6fc66c1e2b6a95afda6d6f9b699fe7b45bebe5c3998c7ffd2e",
+            ),
+            (
+                1,
+                Sighash::SingleACP,
+                "6abfd42ac22339e257945b84321fff4399cf69675acbdaeffc288bcab91a4a5c",
+            ),
+        ];
+        for (index, flag, expected) in cases.iter() {
+            assert_eq!(
+                hex::encode(legacy_sighash(&tx, *index, &script, *flag as u32)),
+                *expected
+            );
+        }
+
+        assert_eq!(
+            hex::encode(legacy_sighash_preimage(&tx, 1, &script, Sighash::NoneACP as u32).unwrap()),
+            "0200000001ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d2000000001d76a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada8802abab75acfdffffff00cafd070082000000"
+        );
+
+        // separators are stripped, but 0xab inside pushes is kept
+        let stripped: Script =
+            hex::decode("76a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada8802abab75ac")
+                .unwrap()
+                .into();
+        assert_eq!(
+            legacy_sighash(&tx, 1, &stripped, Sighash::All as u32),
+            legacy_sighash(&tx, 1, &script, Sighash::All as u32)
+        );
+
+        // SIGHASH_SINGLE bug and out-of-range inputs
+        let one_output = LegacyTx::new(
+            tx.version(),
+            tx.inputs().to_vec(),
+            vec![tx.outputs()[0].clone()],
+            tx.locktime(),
+        )
+        .unwrap();
+        assert_eq!(
+            legacy_sighash(&one_output, 1, &script, Sighash::Single as u32),
+            LEGACY_SIGHASH_ONE
+        );
+        assert_ne!(
+            legacy_sighash(&one_output, 1, &script, Sighash::All as u32),
+            LEGACY_SIGHASH_ONE
+        );
+        assert_eq!(
+            legacy_sighash(&tx, 2, &script, Sighash::All as u32),
+            LEGACY_SIGHASH_ONE
+        );
+        assert!(legacy_sighash_preimage(&one_output, 1, &script, Sighash::Single as u32).is_none());
+
+        // From Bitcoin Core's sighash.json. Core displays the digests byte-reversed
+        let core_cases = [
+            (
+                "907c2bc503ade11cc3b04eb2918b6f547b0630ab569273824748c87ea14b0696526c66ba740200000004ab65ababfd1f9bdd4ef073c7afc4ae00da8a66f429c917a0081ad1e1dabce28d373eab81d8628de802000000096aab5253ab52000052ad042b5f25efb33beec9f3364e8a9139e8439d9d7e26529c3c30b6c3fd89f8684cfd68ea0200000009ab53526500636a52ab599ac2fe02a526ed040000000008535300516352515164370e010000000003006300ab2ec229",
+                "",
+                2,
+                1_864_164_639u32,
+                "31af167a6cf3f9d5f6875caa4d31704ceb0eba078d132b78dab52c3b8997317e",
+            ),
+            (
+                "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+                "5163ac63635151ac",
+                1,
+                1_190_874_345u32,
+                "06e328de263a87b09beabe222a21627a6ea5c7f560030da31610c4611f4a46bc",
+            ),
+        ];
+        for (tx_hex, script_hex, index, hash_type, expected) in core_cases.iter() {
+            let tx = LegacyTx::deserialize_hex(tx_hex).unwrap();
+            let script: Script = hex::decode(script_hex).unwrap().into();
+            let mut digest = legacy_sighash(&tx, *index, &script, *hash_type);
+            digest.reverse();
+            assert_eq!(hex::encode(digest), *expected);
+        }
+    }
+
     #[test]
     fn it_calculates_witness_sighashes_and_txids() {
         // pulled from riemann-py helpers
@@ -588,6 +1763,154 @@ mod tests {
         assert_eq!(tx.wtxid(), wtxid);
     }
 
+    #[test]
+    fn it_strips_signatures() {
+        // from mainnet: 3c7fb4af9b7bd2ba6f155318e0bc8a50432d4732ab6e36293ef45b304567b46a
+        let tx_hex = "01000000000101b77bebb3ac480e99c0d95a4c812137b116e65e2f3b3a66a36d0e252928d460180100000000ffffffff03982457000000000017a91417b8e0f150215cc70bf2fb58070041d655b162dd8740e133000000000017a9142535e444f7d55f0500c1f86609d6cfc289576b698747abfb0100000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d040047304402205c6a889efa26955bef7ce2b08792e63e25eac9859080f0d83912b0ea833d7eb402205f859f4640f1600db5012b467ec05bb4ae1779640c1b5fadc8908960740e52b30147304402201c239ea25cfeadfa9493a1b0d136d70f50f821385972b7188c4329c2bf2d23a302201ee790e4b6794af6567f85a226a387d5b0222c3dc90d2fc558d09e08062b8271016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        let stripped = tx.unsigned_clone();
+        assert!(stripped.is_legacy());
+        assert!(stripped.witnesses().is_empty());
+        assert_eq!(stripped.txid(), tx.txid());
+        assert_eq!(stripped.outputs(), tx.outputs());
+
+        // script sigs are removed, changing the txid
+        let legacy_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(legacy_hex).unwrap();
+        let stripped = tx.unsigned_clone();
+        assert!(stripped.inputs()[0].script_sig.is_empty());
+        assert_eq!(stripped.inputs()[0].outpoint, tx.inputs()[0].outpoint);
+        assert_ne!(stripped.txid(), tx.txid());
+    }
+
+    #[test]
+    fn it_checks_whether_transactions_are_fully_signed() {
+        use coins_bip32::{
+            curve::{Privkey, ScalarDeserialize},
+            keys::GenericPrivkey,
+            model::{HasPubkey, SigningKey},
+        };
+
+        // mainnet P2PKH spend
+        let legacy_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(legacy_hex).unwrap();
+        let pubkey =
+            hex::decode("0349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278a")
+                .unwrap();
+        let mut spk = vec![0x76, 0xa9, 0x14];
+        spk.extend(Hash160::digest_marked(&pubkey).as_slice());
+        spk.extend(&[0x88, 0xac]);
+        let utxo = UTXO::new(
+            tx.inputs()[0].outpoint,
+            0,
+            ScriptPubkey::new(spk),
+            SpendScript::None,
+        );
+        assert!(tx.is_fully_signed(&[utxo.clone()]));
+        assert!(!tx.unsigned_clone().is_fully_signed(&[utxo]));
+        assert!(!tx.is_fully_signed(&[]));
+
+        // 2-of-2 P2WSH multisig
+        let backend = Secp256k1::static_ref();
+        let keys: Vec<_> = [1u8, 2u8]
+            .iter()
+            .map(|seed| GenericPrivkey {
+                key: Privkey::from_privkey_array([*seed; 32]).unwrap(),
+                backend: Some(backend),
+            })
+            .collect();
+        let mut script = vec![0x52];
+        for key in keys.iter() {
+            script.push(33);
+            script.extend(&key.derive_verifying_key().unwrap().pubkey_bytes()[..]);
+        }
+        script.extend(&[0x52, 0xae]);
+        let script = Script::new(script);
+
+        let mut utxo = UTXO::new(
+            BitcoinOutpoint::new(TXID::default(), 0),
+            100_000,
+            ScriptPubkey::p2wsh(&script),
+            SpendScript::Missing,
+        );
+        assert!(utxo.set_spend_script(script.clone()));
+        let unsigned = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .version(2)
+            .spend(utxo.outpoint, 0xffff_fffd)
+            .pay_script_pubkey(90_000, utxo.script_pubkey.clone())
+            .build_witness()
+            .unwrap();
+        let args = utxo.witness_sighash_args(0, Sighash::All).unwrap();
+        let digest: Hash256Digest = unsigned.sighash(&args).unwrap().into();
+        let sigs: Vec<WitnessStackItem> = keys
+            .iter()
+            .map(|key| {
+                let mut sig = key.sign_digest(digest).unwrap().to_der();
+                sig.push(Sighash::All.to_u8());
+                sig.into()
+            })
+            .collect();
+
+        let with_sigs = |sigs: Vec<WitnessStackItem>| {
+            let mut witness = vec![WitnessStackItem::null()];
+            witness.extend(sigs);
+            witness.push(script.items().into());
+            BitcoinTxBuilder::<MainnetEncoder>::from_tx(unsigned.clone().into())
+                .extend_witnesses(vec![witness])
+                .build()
+                .unwrap()
+        };
+        let prevouts = [utxo];
+
+        assert!(with_sigs(sigs.clone()).is_fully_signed(&prevouts));
+        assert!(!with_sigs(vec![sigs[0].clone()]).is_fully_signed(&prevouts));
+        // out of pubkey order
+        assert!(!with_sigs(vec![sigs[1].clone(), sigs[0].clone()]).is_fully_signed(&prevouts));
+
+        // extra signatures and empty placeholders are rejected
+        assert!(
+            !with_sigs(vec![sigs[0].clone(), sigs[1].clone(), sigs[1].clone()])
+                .is_fully_signed(&prevouts)
+        );
+        assert!(!with_sigs(vec![
+            sigs[0].clone(),
+            WitnessStackItem::null(),
+            sigs[1].clone()
+        ])
+        .is_fully_signed(&prevouts));
+        // the dummy must come first
+        let mut misplaced = sigs.clone();
+        misplaced.push(WitnessStackItem::null());
+        misplaced.push(script.items().into());
+        let misplaced = BitcoinTxBuilder::<MainnetEncoder>::from_tx(unsigned.clone().into())
+            .extend_witnesses(vec![misplaced])
+            .build()
+            .unwrap();
+        assert!(!misplaced.is_fully_signed(&prevouts));
+
+        let mut tampered = sigs[1].items().to_vec();
+        tampered[10] ^= 1;
+        assert!(!with_sigs(vec![sigs[0].clone(), tampered.into()]).is_fully_signed(&prevouts));
+
+        // a non-standard sighash byte is interpreted as ALL, but the preimage commits to it
+        let mut preimage = vec![];
+        unsigned
+            .write_witness_sighash_preimage(&mut preimage, &args)
+            .unwrap();
+        let raw_digest = hash_with_sig_byte(preimage, 0x41);
+        let sign_with_byte = |digest: Hash256Digest| -> Vec<WitnessStackItem> {
+            keys.iter()
+                .map(|key| {
+                    let mut sig = key.sign_digest(digest).unwrap().to_der();
+                    sig.push(0x41);
+                    sig.into()
+                })
+                .collect()
+        };
+        assert!(with_sigs(sign_with_byte(raw_digest)).is_fully_signed(&prevouts));
+        assert!(!with_sigs(sign_with_byte(digest)).is_fully_signed(&prevouts));
+    }
+
     #[test]
     fn it_rejects_sighash_none() {
         let tx_hex = "02000000000102ee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffffee9242c89e79ab2aa537408839329895392b97505b3496d5543d6d2f531b94d20000000000fdffffff0273d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f18773d301000000000017a914bba5acbec4e6e3374a0345bf3609fa7cfea825f1870000cafd0700";
@@ -707,4 +2030,75 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn it_detects_address_reuse() {
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let unique = BitcoinTx::deserialize_hex(tx_hex).unwrap();
+        assert!(!unique.detects_address_reuse());
+
+        let mut used = HashSet::new();
+        used.insert(ScriptPubkey::default());
+        assert!(!unique.detects_address_reuse_with(&used));
+        used.insert(unique.outputs()[1].script_pubkey.clone());
+        assert!(unique.detects_address_reuse_with(&used));
+
+        let mut reused = unique.into_legacy();
+        reused.vout[1].script_pubkey = reused.vout[0].script_pubkey.clone();
+        assert!(BitcoinTx::from(reused).detects_address_reuse());
+    }
+
+    #[test]
+    fn it_guesses_change_outputs() {
+        // a P2PKH spend paying 2 P2PKH outputs with unround values
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap().into_legacy();
+        assert_eq!(BitcoinTx::from(tx.clone()).guess_change_output(), None);
+
+        let mut round = tx.clone();
+        round.vout[0].value = 30_000_000;
+        assert_eq!(BitcoinTx::from(round).guess_change_output(), Some(1));
+
+        let mut mixed = tx.clone();
+        mixed.vout[1].script_pubkey = hex::decode("0014758ce550380d964051086798d6546bebdca27a73")
+            .unwrap()
+            .into();
+        assert_eq!(BitcoinTx::from(mixed).guess_change_output(), Some(0));
+
+        let mut single = tx;
+        single.vout.truncate(1);
+        assert_eq!(BitcoinTx::from(single).guess_change_output(), None);
+    }
+
+    #[test]
+    fn it_checks_payments_to_an_address() {
+        let tx_hex = "0100000001813f79011acb80925dfe69b3def355fe914bd1d96a3f5f71bf8303c6a989c7d1000000006b483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278afeffffff02a135ef01000000001976a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac99c39800000000001976a9141c4bc762dd5423e332166702cb75f40df79fea1288ac19430600";
+        let tx = BitcoinTx::deserialize_hex(tx_hex).unwrap().into_legacy();
+        let address = MainnetEncoder::encode_address(&tx.vout[0].script_pubkey)
+            .unwrap()
+            .as_string();
+
+        let single = BitcoinTx::from(tx.clone());
+        assert!(single
+            .paid_to::<MainnetEncoder>(&address, 32_454_049)
+            .unwrap());
+        assert!(!single
+            .paid_to::<MainnetEncoder>(&address, 32_454_050)
+            .unwrap());
+
+        // a payment split across two outputs to the same address
+        let mut split = tx;
+        split.vout[1].script_pubkey = split.vout[0].script_pubkey.clone();
+        let split = BitcoinTx::from(split);
+        assert!(split
+            .paid_to::<MainnetEncoder>(&address, 42_465_594)
+            .unwrap());
+        assert!(!split
+            .paid_to::<MainnetEncoder>(&address, 42_465_595)
+            .unwrap());
+
+        assert!(single
+            .paid_to::<MainnetEncoder>("not an address", 1)
+            .is_err());
+    }
 }
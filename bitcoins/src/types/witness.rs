@@ -396,7 +396,7 @@ impl ByteFormat for WitnessTx {
             .map(|o| o.serialized_length())
             .sum::<usize>();
         for witness in self.witnesses.iter() {
-            len += coins_core::ser::prefix_byte_len(self.witnesses.len() as u64) as usize;
+            len += coins_core::ser::prefix_byte_len(witness.len() as u64) as usize;
             len += witness.iter().map(|w| w.serialized_length()).sum::<usize>();
         }
         len += 4; // locktime
@@ -5,6 +5,8 @@ use bech32::{
     decode as b32_decode, encode as b32_encode, u5, Error as BechError, FromBase32, ToBase32,
 };
 
+pub use bech32::Variant as Bech32Variant;
+
 use base58check::{FromBase58Check, FromBase58CheckError, ToBase58Check};
 
 use thiserror::Error;
@@ -65,18 +67,41 @@ impl From<FromBase58CheckError> for EncodingError {
 /// A simple result type alias
 pub type EncodingResult<T> = Result<T, EncodingError>;
 
-/// Encode a byte vector to bech32. This function expects `v` to be a witness program, and will
-/// return an `UnknownScriptType` if it does not meet the witness program format.
+/// Encode a version and payload to bech32, using the original BIP173 checksum.
 pub fn encode_bech32(hrp: &str, v: u8, h: &[u8]) -> EncodingResult<String> {
+    encode_bech32_variant(hrp, v, h, Bech32Variant::Bech32)
+}
+
+/// Encode a version and payload using the specified bech32 checksum variant. BIP350 requires
+/// `Bech32m` for witness versions 1 and above.
+pub fn encode_bech32_variant(
+    hrp: &str,
+    v: u8,
+    h: &[u8],
+    variant: Bech32Variant,
+) -> EncodingResult<String> {
     let mut v = vec![u5::try_from_u8(v)?];
     v.extend(&h.to_base32());
-    b32_encode(hrp, &v).map_err(|v| v.into())
+    b32_encode(hrp, &v, variant).map_err(|v| v.into())
 }
 
 /// Decode a witness program from a bech32 string. Caller specifies an expected HRP. If a
-/// different HRP is found, returns `WrongHRP`.
+/// different HRP is found, returns `WrongHRP`. Strings with a `Bech32m` checksum are rejected.
 pub fn decode_bech32(expected_hrp: &str, s: &str) -> EncodingResult<(u8, Vec<u8>)> {
-    let (hrp, data) = b32_decode(&s)?;
+    match decode_bech32_variant(expected_hrp, s)? {
+        (v, payload, Bech32Variant::Bech32) => Ok((v, payload)),
+        _ => Err(BechError::InvalidChecksum.into()),
+    }
+}
+
+/// Decode a version and payload from a bech32 or bech32m string, and report which checksum
+/// variant was used. Caller specifies an expected HRP. If a different HRP is found, returns
+/// `WrongHRP`.
+pub fn decode_bech32_variant(
+    expected_hrp: &str,
+    s: &str,
+) -> EncodingResult<(u8, Vec<u8>, Bech32Variant)> {
+    let (hrp, data, variant) = b32_decode(&s)?;
     if hrp != expected_hrp {
         return Err(EncodingError::WrongHRP {
             got: hrp,
@@ -85,10 +110,13 @@ pub fn decode_bech32(expected_hrp: &str, s: &str) -> EncodingResult<(u8, Vec<u8>
     }
 
     // Extract the witness version and payload
+    if data.is_empty() {
+        return Err(BechError::InvalidLength.into());
+    }
     let (v, p) = data.split_at(1);
     let payload = Vec::from_base32(&p)?;
 
-    Ok((v[0].to_u8(), payload))
+    Ok((v[0].to_u8(), payload, variant))
 }
 
 /// Encodes a byte slice to base58check with the specified version byte.
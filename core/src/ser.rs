@@ -35,11 +35,19 @@ pub enum SerError {
     /// Failed to pass a limit to an iterated deserializer that requires one.
     #[error("Deserialization of this struct needs additional lenght information")]
     RequiresLimit,
+
+    /// A bounded deserializer read past its byte limit before completing.
+    #[error("Transaction exceeds the maximum size of {0} bytes")]
+    TxTooLarge(u64),
 }
 
 /// Type alias for serialization errors
 pub type SerResult<T> = Result<T, SerError>;
 
+/// The maximum number of items to preallocate space for when reading a sequence. Length prefixes
+/// come from untrusted data, so larger sequences grow their vector as items are actually read.
+pub const MAX_PREALLOCATION: usize = 1024;
+
 /// Calculates the minimum prefix length for a VarInt encoding `number`
 pub fn prefix_byte_len(number: u64) -> u8 {
     match number {
@@ -100,8 +108,7 @@ where
     // Get the byte(s) representing the number, and parse as u64
     let number = if prefix_len > 1 {
         let mut buf = [0u8; 8];
-        let mut body = reader.take(prefix_len as u64 - 1); // minus 1 to account for prefix
-        let _ = body.read(&mut buf)?;
+        reader.read_exact(&mut buf[..prefix_len as usize - 1])?; // minus 1 to account for prefix
         u64::from_le_bytes(buf)
     } else {
         prefix[0] as u64
@@ -159,7 +166,10 @@ where
     I: ByteFormat<Error = E>,
 {
     let items = read_compact_int(reader)?;
-    I::read_seq_from(reader, items.try_into().unwrap()).map_err(Into::into)
+    let items: usize = items
+        .try_into()
+        .map_err(|_| SerError::ComponentError(format!("Sequence length {} too large", items)))?;
+    I::read_seq_from(reader, items).map_err(Into::into)
 }
 
 /// Convenience function to write a Bitcoin-style length-prefixed vector.
@@ -222,13 +232,15 @@ pub trait ByteFormat {
     where
         W: Write;
 
-    /// Read a sequence of exactly `limit` objects from the reader.
+    /// Read a sequence of exactly `limit` objects from the reader. At most `MAX_PREALLOCATION`
+    /// items are allocated up front, so an oversized `limit` fails on the first missing item
+    /// rather than on allocation.
     fn read_seq_from<R>(reader: &mut R, limit: usize) -> Result<Vec<Self>, Self::Error>
     where
         R: Read,
         Self: std::marker::Sized,
     {
-        let mut v = vec![];
+        let mut v = Vec::with_capacity(std::cmp::min(limit, MAX_PREALLOCATION));
         for _ in 0..limit {
             v.push(Self::read_from(reader)?);
         }
@@ -384,4 +396,14 @@ mod test {
             assert_eq!(first_byte_from_len(case.1), case.2);
         }
     }
+
+    #[test]
+    fn it_errors_cleanly_on_oversized_prefixes() {
+        // claims 1 billion items, provides none
+        let buf = hex::decode("fe00ca9a3b").unwrap();
+        match read_prefix_vec::<_, SerError, u8>(&mut buf.as_slice()) {
+            Err(SerError::IOError(_)) => {}
+            _ => assert!(false, "expected IOError"),
+        }
+    }
 }
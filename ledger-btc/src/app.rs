@@ -120,6 +120,7 @@ impl LedgerBTC {
                         index: *deriv.last().unwrap(),
                         chain_code: child.chain_code,
                         hint: coins_bip32::primitives::Hint::SegWit,
+                        network: None,
                     },
                 },
             })
@@ -137,6 +138,7 @@ impl LedgerBTC {
                         index: 0,
                         chain_code: child.chain_code,
                         hint: coins_bip32::primitives::Hint::SegWit,
+                        network: None,
                     },
                 },
             })
@@ -119,9 +119,28 @@ pub enum PSBTError {
     #[error("Can't extract Tx. Input {0} is not finalized.")]
     UnfinalizedInput(usize),
 
+    /// Attempted to finalize a multisig input that does not have enough partial signatures
+    #[error("Can't finalize input {input}. Have {have} signatures. Need {need}")]
+    InsufficientSignatures {
+        /// The index of the input
+        input: usize,
+        /// The number of usable partial signatures
+        have: usize,
+        /// The multisig threshold
+        need: usize,
+    },
+
+    /// A signature's pubkey does not appear in the multisig script it is meant to satisfy
+    #[error("Signature pubkey {0:x?} is not in the multisig script")]
+    UnknownSignatureKey(Vec<u8>),
+
     /// Missing info for some processing step
     #[error("Missing required info: {0}")]
     MissingInfo(String),
+
+    /// Attempted to combine PSBTs that do not share an unsigned transaction
+    #[error("Can't combine PSBTs with different unsigned transactions.")]
+    TxMismatch,
 }
 
 wrap_prefixed_byte_vector!(
@@ -189,6 +208,19 @@ pub trait PSTMap {
             .ok_or_else(|| PSBTError::MissingKey(key.key_type()))
     }
 
+    /// Copy every KV pair in `other` into this map. Where both maps contain a key, the value in
+    /// this map is kept.
+    fn merge(&mut self, other: &Self)
+    where
+        Self: Sized,
+    {
+        for (k, v) in other.iter() {
+            if !self.contains_key(k) {
+                self.insert(k.clone(), v.clone());
+            }
+        }
+    }
+
     /// Return a range containing any proprietary KV pairs
     fn proprietary(&self) -> btree_map::Range<PSBTKey, PSBTValue> {
         self.range_by_key_type(0xfc)
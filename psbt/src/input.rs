@@ -2,6 +2,7 @@ use coins_bip32::{
     curve::{model::Secp256k1Backend, SigSerialize},
     derived::DerivedPubkey,
     model::HasPubkey,
+    path::KeyDerivation,
 };
 use coins_core::ser::{self, ByteFormat};
 use std::collections::{btree_map, BTreeMap};
@@ -110,7 +111,62 @@ impl PSBTValidate for PSBTInput {
     }
 }
 
+/// A summary of a PSBT input, for diagnosing why it can't be signed or finalized. Values that
+/// are absent or fail to parse are omitted.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PSBTInputDescription {
+    /// True if the input has a PSBT_IN_WITNESS_UTXO
+    pub has_witness_utxo: bool,
+    /// True if the input has a PSBT_IN_NON_WITNESS_UTXO
+    pub has_non_witness_utxo: bool,
+    /// The serialized pubkey of each PSBT_IN_PARTIAL_SIG
+    pub partial_sig_pubkeys: Vec<Vec<u8>>,
+    /// The serialized pubkey and key origin of each PSBT_IN_BIP32_DERIVATION
+    pub derivations: Vec<(Vec<u8>, KeyDerivation)>,
+    /// The raw PSBT_IN_SIGHASH_TYPE, including unknown values
+    pub sighash: Option<u32>,
+    /// The PSBT_IN_REDEEM_SCRIPT
+    pub redeem_script: Option<Script>,
+    /// The PSBT_IN_WITNESS_SCRIPT
+    pub witness_script: Option<Script>,
+    /// True if the input has a finalized script sig or witness
+    pub is_finalized: bool,
+}
+
 impl PSBTInput {
+    /// Summarize the input's UTXOs, signatures, derivations, sighash, and scripts
+    pub fn describe(&self) -> PSBTInputDescription {
+        let sighash = self
+            .get(&InputKey::SIGHASH_TYPE.into())
+            .map(|v| v.items())
+            .filter(|v| v.len() == 4)
+            .map(|v| {
+                let mut buf = [0u8; 4];
+                buf.copy_from_slice(v);
+                u32::from_le_bytes(buf)
+            });
+        PSBTInputDescription {
+            has_witness_utxo: self.has_witness_utxo(),
+            has_non_witness_utxo: self.has_non_witness_utxo(),
+            partial_sig_pubkeys: self
+                .raw_partial_sigs()
+                .map(|(k, _)| k.items()[1..].to_vec())
+                .collect(),
+            derivations: self
+                .pubkey_kv_pairs()
+                .filter_map(|(k, v)| {
+                    schema::try_val_as_key_derivation(v)
+                        .ok()
+                        .map(|d| (k.items()[1..].to_vec(), d))
+                })
+                .collect(),
+            sighash,
+            redeem_script: self.redeem_script().ok(),
+            witness_script: self.witness_script().ok(),
+            is_finalized: self.is_finalized(),
+        }
+    }
+
     /// Returns true if the map has a non-witness utxo in it.
     pub fn has_non_witness_utxo(&self) -> bool {
         self.contains_key(&InputKey::NON_WITNESS_UTXO.into())
@@ -241,6 +297,11 @@ impl PSBTInput {
         Ok(script_bytes.into())
     }
 
+    /// Insert a redeem script into the input map
+    pub fn insert_redeem_script(&mut self, script: &Script) {
+        self.insert(InputKey::REDEEM_SCRIPT.into(), script.items().into());
+    }
+
     /// True if the map has a redeem script, else false.
     pub fn has_redeem_script(&self) -> bool {
         self.contains_key(&InputKey::REDEEM_SCRIPT.into())
@@ -256,6 +317,11 @@ impl PSBTInput {
         Ok(script_bytes.into())
     }
 
+    /// Insert a witness script into the input map
+    pub fn insert_witness_script(&mut self, script: &Script) {
+        self.insert(InputKey::WITNESS_SCRIPT.into(), script.items().into());
+    }
+
     /// True if the map has a witness script, else false.
     pub fn has_witness_script(&self) -> bool {
         self.contains_key(&InputKey::WITNESS_SCRIPT.into())
@@ -317,6 +383,16 @@ impl PSBTInput {
             || self.contains_key(&InputKey::FINAL_SCRIPTSIG.into())
     }
 
+    /// Remove all partial signatures, and any finalized script sig or witness.
+    pub fn clear_signatures(&mut self) {
+        let keys: Vec<_> = self.raw_partial_sigs().map(|(k, _)| k.clone()).collect();
+        for key in keys.iter() {
+            self.remove(key);
+        }
+        self.remove(&InputKey::FINAL_SCRIPTSIG.into());
+        self.remove(&InputKey::FINAL_SCRIPTWITNESS.into());
+    }
+
     /// Returns the BIP174 PSBT_IN_POR_COMMITMENT if present and valid.
     ///
     /// ## Errors
@@ -38,6 +38,7 @@ use coins_core::prelude::*;
 use bitcoins::{
     builder::BitcoinTxBuilder,
     enc::encoder::{BitcoinEncoderMarker, MainnetEncoder, TestnetEncoder},
+    hashes::TXID,
     types::{BitcoinTransaction, BitcoinTx, BitcoinTxIn, LegacyTx, TxOut},
 };
 
@@ -176,6 +177,47 @@ where
         self.insert_output(std::usize::MAX, tx_out)
     }
 
+    /// Merge another PSBT into this one, as the BIP174 Combiner role. Every KV pair in `other`'s
+    /// global, input, and output maps is copied into the matching map. This collects partial
+    /// signatures and derivation info from several signers.
+    ///
+    /// Where both PSBTs contain a key, the value in `self` is kept.
+    ///
+    /// ## Errors
+    ///
+    /// - Returns `PSBTError::TxMismatch` if the PSBTs have different unsigned transactions.
+    pub fn combine(&mut self, other: PSBT<T, E>) -> Result<(), PSBTError> {
+        if self.tx_bytes()? != other.tx_bytes()? {
+            return Err(PSBTError::TxMismatch);
+        }
+        self.global.merge(&other.global);
+        for (mine, theirs) in self.inputs.iter_mut().zip(other.inputs.iter()) {
+            mine.merge(theirs);
+        }
+        for (mine, theirs) in self.outputs.iter_mut().zip(other.outputs.iter()) {
+            mine.merge(theirs);
+        }
+        Ok(())
+    }
+
+    /// Remove all partial signatures and finalized script sigs and witnesses from every input.
+    /// Other input information is kept, so the PSBT may be signed again.
+    pub fn clear_signatures(&mut self) {
+        for input in self.inputs.iter_mut() {
+            input.clear_signatures();
+        }
+    }
+
+    /// The txid of the unsigned transaction. Signing and combining never change the unsigned
+    /// transaction, so this identifies the PSBT throughout a signing workflow.
+    ///
+    /// ## Errors
+    ///
+    /// - Returns an error if the global map's unsigned transaction is missing or malformed.
+    pub fn unsigned_txid(&self) -> Result<TXID, PSBTError> {
+        Ok(self.tx()?.txid())
+    }
+
     /// Return a parsed vector of k/v pairs. Keys are parsed as XPubs with the provided backend.
     /// Values are parsed as `KeyDerivation` structs.
     pub fn parsed_xpubs(&self) -> Result<Vec<DerivedXPub>, PSBTError> {
@@ -449,6 +491,29 @@ mod test {
         }
     }
 
+    #[test]
+    fn it_describes_inputs() {
+        let psbt = "70736274ff0100a00200000002ab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40000000000feffffffab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40100000000feffffff02603bea0b000000001976a914768a40bbd740cbe81d988e71de2a4d5c71396b1d88ac8e240000000000001976a9146f4620b553fa095e721b9ee0efe9fa039cca459788ac000000000001076a47304402204759661797c01b036b25928948686218347d89864b719e1f7fcf57d1e511658702205309eabf56aa4d8891ffd111fdf1336f3a29da866d7f8486d75546ceedaf93190121035cdc61fc7ba971c0b501a646a2a83b102cb43881217ca682dc86e2d73fa882920001012000e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787010416001485d13537f2e265405a34dbafa9e3dda01fb82308000000";
+        let p = MainnetPSBT::deserialize_hex(psbt).unwrap();
+
+        let finalized = p.input_maps()[0].describe();
+        assert!(finalized.is_finalized);
+        assert!(!finalized.has_witness_utxo);
+        assert!(finalized.partial_sig_pubkeys.is_empty());
+
+        let wrapped = p.input_maps()[1].describe();
+        assert!(!wrapped.is_finalized);
+        assert!(wrapped.has_witness_utxo);
+        assert!(!wrapped.has_non_witness_utxo);
+        assert_eq!(
+            wrapped.redeem_script.unwrap().serialize_hex(),
+            "16001485d13537f2e265405a34dbafa9e3dda01fb82308"
+        );
+        assert_eq!(wrapped.witness_script, None);
+        assert_eq!(wrapped.sighash, None);
+        assert!(wrapped.derivations.is_empty());
+    }
+
     #[test]
     fn invalid_psbt_network_tx() {
         let psbt = "0200000001268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6000000006a473044022070b2245123e6bf474d60c5b50c043d4c691a5d2435f09a34a7662a9dc251790a022001329ca9dacf280bdf30740ec0390422422c81cb45839457aeb76fc12edd95b3012102657d118d3357b8e0f4c2cd46db7b39f6d9c38d9a70abcb9b2de5dc8dbfe4ce31feffffff02d3dff505000000001976a914d0c59903c5bac2868760e90fd521a4665aa7652088ac00e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787b32e1300";
@@ -639,6 +704,11 @@ mod test {
         for (i, output) in psbt.tx().unwrap().outputs().iter().enumerate() {
             assert_eq!(output, &tx.outputs()[i]);
         }
+
+        let mut psbt = psbt;
+        psbt.clear_signatures();
+        assert!(psbt.input_maps().iter().all(|i| !i.is_finalized()));
+        assert_eq!(psbt.tx().unwrap().txid(), tx.txid());
     }
 
     #[test]
@@ -659,6 +729,11 @@ mod test {
         for (i, output) in psbt.tx().unwrap().outputs().iter().enumerate() {
             assert_eq!(output, &tx.outputs()[i]);
         }
+
+        let mut psbt = psbt;
+        psbt.clear_signatures();
+        assert!(psbt.input_maps().iter().all(|i| !i.is_finalized()));
+        assert_eq!(psbt.tx().unwrap().txid(), tx.txid());
     }
 
     #[test]
@@ -671,4 +746,43 @@ mod test {
                 .serialize_base64()
         );
     }
+
+    #[test]
+    fn it_combines_partial_signatures() {
+        use coins_bip32::{
+            curve::{Privkey, ScalarDeserialize, Secp256k1},
+            keys::GenericPrivkey,
+            model::SigningKey,
+        };
+
+        let b64 = "cHNidP8BAHECAAAAAeBANSdI+VT5VJvVfchN4UEUniZ5cfeucBkBuoA475wjAAAAAAD+////AgDh9QUAAAAAFgAU7gEhvO/VGbeMDvk2DeqaTVkRQh8AERAkAQAAABYAFCQ8xyUkB4v4DqmV7T6aVADqs8M5AAAAAAABAR8A8gUqAQAAABYAFO4BIbzv1Rm3jA75Ng3qmk1ZEUIfIgYDbXrhM7lpiaTJhxwJSplsX1r33gCcoD9xL4wEteLypE8YRwNsJ1QAAIABAACAAAAAgAAAAAAAAAAAACICA2164TO5aYmkyYccCUqZbF9a994AnKA/cS+MBLXi8qRPGEcDbCdUAACAAQAAgAAAAIAAAAAAAAAAAAAiAgONam8JJOdoEr/jubocGRelQAnn2NfLVM7jLliPK0n8KBhHA2wnVAAAgAEAAIAAAACAAQAAAAAAAAAA";
+        let mut first = MainnetPSBT::deserialize_base64(b64).unwrap();
+        let mut second = first.clone();
+
+        let backend = Secp256k1::static_ref();
+        for (psbt, seed) in vec![(&mut first, 1u8), (&mut second, 2u8)] {
+            let key = GenericPrivkey {
+                key: Privkey::from_privkey_array([seed; 32]).unwrap(),
+                backend: Some(backend),
+            };
+            let pubkey = key.derive_verifying_key().unwrap();
+            let sig = key.sign_digest([seed; 32].into()).unwrap();
+            psbt.input_maps_mut()[0].insert_partial_sig(&pubkey, &sig);
+        }
+        assert_eq!(first.input_maps()[0].partial_sigs().len(), 1);
+        let txid = first.unsigned_txid().unwrap();
+        assert_eq!(txid, second.unsigned_txid().unwrap());
+
+        first.combine(second).unwrap();
+        assert_eq!(first.input_maps()[0].partial_sigs().len(), 2);
+        assert_eq!(first.unsigned_txid().unwrap(), txid);
+        assert_eq!(txid, first.tx().unwrap().txid());
+        first.validate().unwrap();
+
+        let other = MainnetPSBT::deserialize_hex("70736274ff0100a00200000002ab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40000000000feffffffab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40100000000feffffff02603bea0b000000001976a914768a40bbd740cbe81d988e71de2a4d5c71396b1d88ac8e240000000000001976a9146f4620b553fa095e721b9ee0efe9fa039cca459788ac000000000001076a47304402204759661797c01b036b25928948686218347d89864b719e1f7fcf57d1e511658702205309eabf56aa4d8891ffd111fdf1336f3a29da866d7f8486d75546ceedaf93190121035cdc61fc7ba971c0b501a646a2a83b102cb43881217ca682dc86e2d73fa882920001012000e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787010416001485d13537f2e265405a34dbafa9e3dda01fb82308000000").unwrap();
+        match first.combine(other) {
+            Err(PSBTError::TxMismatch) => {}
+            _ => assert!(false, "expected TxMismatch"),
+        }
+    }
 }
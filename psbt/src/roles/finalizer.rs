@@ -1,8 +1,11 @@
 use crate::{input::InputKey, roles::PSTFinalizer, PSBTError, PSBTInput, PSTMap, PSBT, PST};
 use bitcoins::{
     enc::encoder::BitcoinEncoderMarker,
-    prelude::Hash160Digest,
-    types::{BitcoinOutpoint, BitcoinTransaction, ScriptType},
+    prelude::{Hash160Digest, Hash256Digest},
+    types::{
+        parse_multisig, BitcoinOutpoint, BitcoinTransaction, Script, ScriptType, Witness,
+        WitnessStackItem,
+    },
 };
 use coins_bip32::{self as bip32, curve::SigSerialize, HasPubkey};
 use coins_core::Transaction;
@@ -10,6 +13,10 @@ use coins_core::Transaction;
 /// A finalizer that creates WPKH witnesses
 pub struct PSBTWPKHFinalizer();
 
+/// A finalizer that creates witnesses for P2WSH inputs whose witness script is a standard
+/// `m <pubkey>... n OP_CHECKMULTISIG` script.
+pub struct PSBTMultisigFinalizer();
+
 fn clear_input_map(input_map: &mut PSBTInput) {
     // clears the following keys:
     // PARTIAL_SIG = 2
@@ -93,3 +100,229 @@ where
         Ok(())
     }
 }
+
+/// Sort signatures into the order of their pubkeys in a standard multisig `witness_script`, as
+/// CHECKMULTISIG requires. Signatures in any other order fail verification. `S` is typically a
+/// `Signature`, or a signature with its sighash.
+///
+/// ## Errors
+///
+/// - `PSBTError::MissingInfo` if the script is not a standard multisig script
+/// - `PSBTError::UnknownSignatureKey` if a signature's pubkey is not in the script
+pub fn order_multisig_signatures<S: Clone>(
+    witness_script: &Script,
+    sigs: &[(bip32::Pubkey, S)],
+) -> Result<Vec<S>, PSBTError> {
+    let (_, pubkeys) = parse_multisig(witness_script.items()).ok_or_else(|| {
+        PSBTError::MissingInfo("Witness script is not a standard multisig script".to_owned())
+    })?;
+
+    let mut positioned = vec![];
+    for (pubkey, sig) in sigs.iter() {
+        let bytes = pubkey.pubkey_bytes();
+        let position = pubkeys
+            .iter()
+            .position(|k| *k == &bytes[..])
+            .ok_or_else(|| PSBTError::UnknownSignatureKey(bytes.to_vec()))?;
+        positioned.push((position, sig.clone()));
+    }
+    positioned.sort_by_key(|(position, _)| *position);
+    Ok(positioned.into_iter().map(|(_, sig)| sig).collect())
+}
+
+/// Finalize a P2WSH multisig input, creating its witness
+fn finalize_multisig_input(
+    index: usize,
+    outpoint: &BitcoinOutpoint,
+    input_map: &mut PSBTInput,
+) -> Result<(), PSBTError> {
+    let prevout = input_map.as_utxo(outpoint)?;
+    match prevout.standard_type() {
+        ScriptType::WSH(_) => {}
+        other => {
+            return Err(PSBTError::WrongPrevoutScriptType {
+                got: other,
+                expected: vec![ScriptType::WSH(Hash256Digest::default())],
+            })
+        }
+    }
+
+    let witness_script = input_map.witness_script()?;
+    let (need, _) = parse_multisig(witness_script.items()).ok_or_else(|| {
+        PSBTError::MissingInfo("Witness script is not a standard multisig script".to_owned())
+    })?;
+
+    let partial_sigs: Vec<_> = input_map
+        .partial_sigs()
+        .into_iter()
+        .map(|(pubkey, sig, sighash)| {
+            let mut sig_bytes = sig.to_der();
+            sig_bytes.push(sighash.to_u8());
+            (pubkey, sig_bytes)
+        })
+        .collect();
+    let mut sigs = order_multisig_signatures(&witness_script, &partial_sigs)?;
+    sigs.truncate(need);
+    if sigs.len() < need {
+        return Err(PSBTError::InsufficientSignatures {
+            input: index,
+            have: sigs.len(),
+            need,
+        });
+    }
+
+    // The leading empty item is consumed by the CHECKMULTISIG off-by-one bug
+    let mut witness = Witness::default();
+    witness.push(WitnessStackItem::null());
+    for sig in sigs.into_iter() {
+        witness.push(sig.into());
+    }
+    witness.push(witness_script.items().into());
+
+    input_map.insert_witness(&witness);
+    clear_input_map(input_map);
+    Ok(())
+}
+
+impl<A, E> PSTFinalizer<A, PSBT<A, E>> for PSBTMultisigFinalizer
+where
+    A: BitcoinEncoderMarker,
+    E: bip32::enc::XKeyEncoder,
+{
+    type Error = PSBTError;
+
+    /// Finalize every unfinalized input. Errors with `PSBTError::InsufficientSignatures` if an
+    /// input does not have enough partial signatures to meet its threshold.
+    fn finalize(&mut self, pst: &mut PSBT<A, E>) -> Result<(), PSBTError> {
+        let outpoints: Vec<BitcoinOutpoint> = pst
+            .tx()?
+            .inputs()
+            .iter()
+            .map(|txin| txin.outpoint)
+            .collect();
+        let input_maps = pst.input_maps_mut();
+        for (i, (o, input_map)) in outpoints.iter().zip(input_maps.iter_mut()).enumerate() {
+            if !input_map.is_finalized() {
+                finalize_multisig_input(i, o, input_map)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MainnetPSBT;
+    use bitcoins::{
+        builder::BitcoinTxBuilder,
+        enc::encoder::MainnetEncoder,
+        hashes::TXID,
+        types::{ScriptPubkey, TxOut},
+    };
+    use coins_bip32::{
+        curve::{Privkey, ScalarDeserialize, Secp256k1},
+        keys::GenericPrivkey,
+        model::SigningKey,
+    };
+    use coins_core::builder::TxBuilder;
+
+    #[test]
+    fn it_reports_insufficient_multisig_signatures() {
+        let backend = Secp256k1::static_ref();
+        let keys: Vec<_> = (1u8..=3)
+            .map(|i| GenericPrivkey {
+                key: Privkey::from_privkey_array([i; 32]).unwrap(),
+                backend: Some(backend),
+            })
+            .collect();
+        let pubkeys: Vec<_> = keys
+            .iter()
+            .map(|k| k.derive_verifying_key().unwrap())
+            .collect();
+
+        let mut script = vec![0x52];
+        for pubkey in pubkeys.iter() {
+            script.push(33);
+            script.extend(pubkey.pubkey_bytes().iter());
+        }
+        script.extend(&[0x53, 0xae]);
+        let script: Script = script.into();
+
+        let tx = BitcoinTxBuilder::<MainnetEncoder>::new()
+            .spend(BitcoinOutpoint::new(TXID::default(), 0), 0xffff_fffd)
+            .pay_script_pubkey(90_000, ScriptPubkey::p2wsh(&script))
+            .build()
+            .unwrap();
+        let mut psbt = MainnetPSBT::from_tx(&tx);
+        {
+            let input_map = &mut psbt.input_maps_mut()[0];
+            input_map.insert_witness_utxo(&TxOut::new(100_000, ScriptPubkey::p2wsh(&script)));
+            input_map.insert_witness_script(&script);
+            let sig = keys[2].sign_digest([1u8; 32].into()).unwrap();
+            input_map.insert_partial_sig(&pubkeys[2], &sig);
+        }
+
+        match PSBTMultisigFinalizer().finalize(&mut psbt) {
+            Err(PSBTError::InsufficientSignatures { input, have, need }) => {
+                assert_eq!((input, have, need), (0, 1, 2))
+            }
+            _ => assert!(false, "expected InsufficientSignatures"),
+        }
+
+        let sig = keys[0].sign_digest([1u8; 32].into()).unwrap();
+        psbt.input_maps_mut()[0].insert_partial_sig(&pubkeys[0], &sig);
+        PSBTMultisigFinalizer().finalize(&mut psbt).unwrap();
+
+        let witness = psbt.input_maps()[0].finalized_script_witness().unwrap();
+        assert_eq!(witness.len(), 4);
+        assert!(witness[0].is_empty());
+        assert_eq!(witness[3].items(), script.items());
+        assert_eq!(psbt.input_maps()[0].partial_sigs().len(), 0);
+    }
+
+    #[test]
+    fn it_orders_multisig_signatures() {
+        let backend = Secp256k1::static_ref();
+        let pubkeys: Vec<_> = (1u8..=3)
+            .map(|i| {
+                GenericPrivkey {
+                    key: Privkey::from_privkey_array([i; 32]).unwrap(),
+                    backend: Some(backend),
+                }
+                .derive_verifying_key()
+                .unwrap()
+            })
+            .collect();
+
+        let mut script = vec![0x52];
+        for pubkey in pubkeys.iter() {
+            script.push(33);
+            script.extend(pubkey.pubkey_bytes().iter());
+        }
+        script.extend(&[0x53, 0xae]);
+        let script: Script = script.into();
+
+        let sigs = vec![
+            (pubkeys[2].clone(), "third"),
+            (pubkeys[0].clone(), "first"),
+            (pubkeys[1].clone(), "second"),
+        ];
+        assert_eq!(
+            order_multisig_signatures(&script, &sigs).unwrap(),
+            vec!["first", "second", "third"]
+        );
+
+        let other = GenericPrivkey {
+            key: Privkey::from_privkey_array([4; 32]).unwrap(),
+            backend: Some(backend),
+        }
+        .derive_verifying_key()
+        .unwrap();
+        let other_bytes = other.pubkey_bytes().to_vec();
+        match order_multisig_signatures(&script, &[(other, "other")]) {
+            Err(PSBTError::UnknownSignatureKey(key)) => assert_eq!(key, other_bytes),
+            _ => assert!(false, "expected UnknownSignatureKey"),
+        }
+    }
+}